@@ -14,6 +14,11 @@ pub struct Options {
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+    flag_message_format: ops::MessageFormat,
+    flag_profile: Option<String>,
     flag_lib: bool,
     flag_bin: Vec<String>,
     flag_example: Vec<String>,
@@ -38,6 +43,7 @@ Options:
     --no-run                     Compile, but don't run benchmarks
     -p SPEC, --package SPEC ...  Package to run benchmarks for
     -j N, --jobs N               The number of jobs to run in parallel
+    --profile NAME               Build artifacts with the specified custom profile
     --features FEATURES          Space-separated list of features to also build
     --no-default-features        Do not build the `default` feature
     --target TRIPLE              Build for the target triple
@@ -45,6 +51,10 @@ Options:
     -v, --verbose                Use verbose output
     -q, --quiet                  No output printed to stdout
     --color WHEN                 Coloring: auto, always, never
+    --frozen                     Require Cargo.lock and cache are up to date
+    --locked                     Require Cargo.lock is up to date
+    --offline                    Run without accessing the network
+    --message-format FMT         Error format: human, json [default: human]
 
 All of the trailing arguments are passed to the benchmark binaries generated
 for filtering benchmarks and generally providing options configuring how they
@@ -63,9 +73,12 @@ Compilation can be customized with the `bench` profile in the manifest.
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     let root = try!(find_root_manifest_for_wd(options.flag_manifest_path, config.cwd()));
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
 
     let ops = ops::TestOptions {
         no_run: options.flag_no_run,
@@ -79,8 +92,13 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
             no_default_features: options.flag_no_default_features,
             spec: &options.flag_package,
             exec_engine: None,
-            release: true,
+            release: options.flag_profile.is_none(),
+            profile: options.flag_profile.as_ref().map(|s| &s[..]),
             mode: ops::CompileMode::Bench,
+            message_format: options.flag_message_format,
+            build_plan: false,
+            explain_rebuilds: false,
+            dry_run: false,
             filter: ops::CompileFilter::new(options.flag_lib,
                                             &options.flag_bin,
                                             &options.flag_test,
@@ -16,6 +16,14 @@ pub struct Options {
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+    flag_message_format: ops::MessageFormat,
+    flag_profile: Option<String>,
+    flag_build_plan: bool,
+    flag_explain_rebuilds: bool,
+    flag_dry_run: bool,
     flag_release: bool,
     flag_lib: bool,
     flag_bin: Vec<String>,
@@ -40,6 +48,7 @@ Options:
     --test NAME                  Build only the specified test target
     --bench NAME                 Build only the specified benchmark target
     --release                    Build artifacts in release mode, with optimizations
+    --profile NAME               Build artifacts with the specified custom profile
     --features FEATURES          Space-separated list of features to also build
     --no-default-features        Do not build the `default` feature
     --target TRIPLE              Build for the target triple
@@ -47,6 +56,13 @@ Options:
     -v, --verbose                Use verbose output
     -q, --quiet                  No output printed to stdout
     --color WHEN                 Coloring: auto, always, never
+    --frozen                     Require Cargo.lock and cache are up to date
+    --locked                     Require Cargo.lock is up to date
+    --offline                    Run without accessing the network
+    --message-format FMT         Error format: human, json [default: human]
+    --build-plan                 Output the commands of the build as JSON, without running them
+    --explain-rebuilds           Explain why each unit of the build is rebuilt
+    --dry-run                    Explain what would be rebuilt, without building anything
 
 If the --package argument is given, then SPEC is a package id specification
 which indicates which package should be built. If it is not given, then the
@@ -56,14 +72,21 @@ current package is built. For more information on SPEC and its format, see the
 Compilation can be configured via the use of profiles which are configured in
 the manifest. The default profile for this command is `dev`, but passing
 the --release flag will use the `release` profile instead.
+
+A --dry-run still downloads the dependencies needed to work out what would be
+rebuilt, and locks the build directory, which creates it if it doesn't exist
+yet, but nothing else is written to it.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
     debug!("executing; cmd=cargo-build; args={:?}",
            env::args().collect::<Vec<_>>());
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
 
     let root = try!(find_root_manifest_for_wd(options.flag_manifest_path, config.cwd()));
 
@@ -76,7 +99,12 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
         spec: &options.flag_package,
         exec_engine: None,
         mode: ops::CompileMode::Build,
+        message_format: options.flag_message_format,
+        build_plan: options.flag_build_plan,
+        explain_rebuilds: options.flag_explain_rebuilds,
+        dry_run: options.flag_dry_run,
         release: options.flag_release,
+        profile: options.flag_profile.as_ref().map(|s| &s[..]),
         filter: ops::CompileFilter::new(options.flag_lib,
                                         &options.flag_bin,
                                         &options.flag_test,
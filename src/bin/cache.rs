@@ -0,0 +1,53 @@
+use cargo::ops;
+use cargo::util::{CliResult, Config};
+
+#[derive(RustcDecodable)]
+pub struct Options {
+    flag_remove_unused: Option<u32>,
+    flag_verbose: Option<bool>,
+    flag_quiet: Option<bool>,
+    flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+}
+
+pub const USAGE: &'static str = "
+Report and clean up the sources cached by Cargo.
+
+Usage:
+    cargo cache [options]
+
+Options:
+    -h, --help               Print this message
+    --remove-unused DAYS     Remove sources which haven't been used in DAYS days
+    -v, --verbose            Use verbose output
+    -q, --quiet              No output printed to stdout
+    --color WHEN             Coloring: auto, always, never
+    --frozen                 Require Cargo.lock and cache are up to date
+    --locked                 Require Cargo.lock is up to date
+    --offline                Run without accessing the network
+
+This command prints how much disk space is used by each registry and git
+repository which Cargo has downloaded into $CARGO_HOME.
+
+With `--remove-unused`, the unpacked sources of registry crates and the
+checkouts of git repositories which no build has used in the given number of
+days are removed first. They will be unpacked or checked out again the next
+time they're needed. Sources in use by a concurrent build are never removed.
+";
+
+pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
+    let opts = ops::CacheOptions {
+        config: config,
+        remove_unused: options.flag_remove_unused,
+    };
+    try!(ops::cache(&opts));
+    Ok(None)
+}
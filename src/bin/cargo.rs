@@ -47,6 +47,7 @@ Options:
 
 Some common cargo commands are:
     build       Compile the current project
+    check       Analyze the current project and report errors, but don't build
     clean       Remove the target directory
     doc         Build this project's and its dependencies' documentation
     new         Create a new cargo project
@@ -71,6 +72,8 @@ macro_rules! each_subcommand{
     ($mac:ident) => {
         $mac!(bench);
         $mac!(build);
+        $mac!(cache);
+        $mac!(check);
         $mac!(clean);
         $mac!(doc);
         $mac!(fetch);
@@ -95,6 +98,7 @@ macro_rules! each_subcommand{
         $mac!(test);
         $mac!(uninstall);
         $mac!(update);
+        $mac!(vendor);
         $mac!(verify_project);
         $mac!(version);
         $mac!(yank);
@@ -112,9 +116,12 @@ each_subcommand!(declare_mod);
   on this top-level information.
 */
 fn execute(flags: Flags, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(flags.flag_verbose,
-                                flags.flag_quiet,
-                                &flags.flag_color));
+    try!(config.configure(flags.flag_verbose,
+                          flags.flag_quiet,
+                          &flags.flag_color,
+                          false,
+                          false,
+                          false));
 
     init_git_transports(config);
     cargo::util::job::setup();
@@ -0,0 +1,102 @@
+use std::env;
+
+use cargo::ops::CompileOptions;
+use cargo::ops;
+use cargo::util::important_paths::{find_root_manifest_for_wd};
+use cargo::util::{CliResult, Config};
+
+#[derive(RustcDecodable)]
+pub struct Options {
+    flag_package: Vec<String>,
+    flag_jobs: Option<u32>,
+    flag_features: Vec<String>,
+    flag_no_default_features: bool,
+    flag_target: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_verbose: Option<bool>,
+    flag_quiet: Option<bool>,
+    flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+    flag_message_format: ops::MessageFormat,
+    flag_lib: bool,
+    flag_bin: Vec<String>,
+    flag_example: Vec<String>,
+}
+
+pub const USAGE: &'static str = "
+Check a local package and all of its dependencies for errors
+
+Usage:
+    cargo check [options]
+
+Options:
+    -h, --help                   Print this message
+    -p SPEC, --package SPEC ...  Package to check
+    -j N, --jobs N               The number of jobs to run in parallel
+    --lib                        Check only this package's library
+    --bin NAME                   Check only the specified binary
+    --example NAME               Check only the specified example
+    --features FEATURES          Space-separated list of features to also check
+    --no-default-features        Do not check the `default` feature
+    --target TRIPLE              Check for the target triple
+    --manifest-path PATH         Path to the manifest to check
+    -v, --verbose                Use verbose output
+    -q, --quiet                  No output printed to stdout
+    --color WHEN                 Coloring: auto, always, never
+    --frozen                     Require Cargo.lock and cache are up to date
+    --locked                     Require Cargo.lock is up to date
+    --offline                    Run without accessing the network
+    --message-format FMT         Error format: human, json [default: human]
+
+If the --package argument is given, then SPEC is a package id specification
+which indicates which package should be checked. If it is not given, then the
+current package is checked. For more information on SPEC and its format, see
+the `cargo help pkgid` command.
+
+Checking runs the compiler's analysis over every crate but only emits their
+metadata, so it's quicker than `cargo build` but produces nothing to run.
+Build scripts and plugins are still built in full, as they have to be run.
+";
+
+pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
+    debug!("executing; cmd=cargo-check; args={:?}",
+           env::args().collect::<Vec<_>>());
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
+
+    let root = try!(find_root_manifest_for_wd(options.flag_manifest_path, config.cwd()));
+
+    let empty = Vec::new();
+    let opts = CompileOptions {
+        config: config,
+        jobs: options.flag_jobs,
+        target: options.flag_target.as_ref().map(|t| &t[..]),
+        features: &options.flag_features,
+        no_default_features: options.flag_no_default_features,
+        spec: &options.flag_package,
+        exec_engine: None,
+        mode: ops::CompileMode::Check,
+        message_format: options.flag_message_format,
+        build_plan: false,
+        explain_rebuilds: false,
+        dry_run: false,
+        release: false,
+        profile: None,
+        filter: ops::CompileFilter::new(options.flag_lib,
+                                        &options.flag_bin,
+                                        &empty,
+                                        &options.flag_example,
+                                        &empty),
+        target_rustdoc_args: None,
+        target_rustc_args: None,
+    };
+
+    try!(ops::compile(&root, &opts));
+    Ok(None)
+}
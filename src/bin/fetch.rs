@@ -1,13 +1,18 @@
 use cargo::ops;
-use cargo::util::{CliResult, Config};
+use cargo::util::{CliError, CliResult, Config, human};
 use cargo::util::important_paths::find_root_manifest_for_wd;
 
 #[derive(RustcDecodable)]
 pub struct Options {
     flag_manifest_path: Option<String>,
+    flag_bundle: Option<String>,
+    flag_import: Option<String>,
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -19,9 +24,14 @@ Usage:
 Options:
     -h, --help               Print this message
     --manifest-path PATH     Path to the manifest to fetch dependencies for
+    --bundle FILE            Also write everything fetched to a bundle
+    --import FILE            Add the contents of a bundle to the local cache
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --frozen                 Require Cargo.lock and cache are up to date
+    --locked                 Require Cargo.lock is up to date
+    --offline                Run without accessing the network
 
 If a lockfile is available, this command will ensure that all of the git
 dependencies and/or registries dependencies are downloaded and locally
@@ -31,14 +41,34 @@ the lockfile changes.
 If the lockfile is not available, then this is the equivalent of
 `cargo generate-lockfile`. A lockfile is generated and dependencies are also
 all updated.
+
+With `--bundle`, the crate files, registry index entries and git repositories
+the package needs are additionally written from the local cache to a single
+archive. Running `cargo fetch --import` with that archive on another machine,
+from any directory, adds them to its cache so the package can be built there
+with `--offline` or `--frozen`.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
+    if let Some(ref import) = options.flag_import {
+        if options.flag_bundle.is_some() {
+            return Err(CliError::new(human("cannot specify both --bundle and \
+                                            --import"), 1))
+        }
+        try!(ops::import_bundle(config, &config.cwd().join(import)));
+        return Ok(None)
+    }
     let root = try!(find_root_manifest_for_wd(options.flag_manifest_path, config.cwd()));
-    try!(ops::fetch(&root, config));
+    let (resolve, _) = try!(ops::fetch(&root, config));
+    if let Some(ref bundle) = options.flag_bundle {
+        try!(ops::bundle(&resolve, config, &config.cwd().join(bundle)));
+    }
     Ok(None)
 }
 
@@ -13,6 +13,10 @@ pub struct Options {
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+    flag_profile: Option<String>,
     flag_root: Option<String>,
     flag_list: bool,
     flag_force: bool,
@@ -50,12 +54,16 @@ Build and install options:
     -f, --force               Force overwriting existing crates or binaries
     --no-default-features     Do not build the `default` feature
     --debug                   Build in debug mode instead of release mode
+    --profile NAME            Build artifacts with the specified custom profile
     --bin NAME                Only install the binary NAME
     --example EXAMPLE         Install the example EXAMPLE instead of binaries
     --root DIR                Directory to install packages into
     -v, --verbose             Use verbose output
     -q, --quiet               Less output printed to stdout
     --color WHEN              Coloring: auto, always, never
+    --frozen                  Require Cargo.lock and cache are up to date
+    --locked                  Require Cargo.lock is up to date
+    --offline                 Run without accessing the network
 
 This command manages Cargo's local set of installed binary crates. Only packages
 which have [[bin]] targets can be installed, and all binaries are installed into
@@ -89,9 +97,12 @@ The `--list` option will list all installed packages (and their versions).
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
 
     let compile_opts = ops::CompileOptions {
         config: config,
@@ -102,7 +113,12 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
         spec: &[],
         exec_engine: None,
         mode: ops::CompileMode::Build,
-        release: !options.flag_debug,
+        message_format: ops::MessageFormat::Human,
+        build_plan: false,
+        explain_rebuilds: false,
+        dry_run: false,
+        release: !options.flag_debug && options.flag_profile.is_none(),
+        profile: options.flag_profile.as_ref().map(|s| &s[..]),
         filter: ops::CompileFilter::new(false, &options.flag_bin, &[],
                                         &options.flag_example, &[]),
         target_rustc_args: None,
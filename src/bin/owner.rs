@@ -8,9 +8,13 @@ pub struct Options {
     flag_add: Option<Vec<String>>,
     flag_remove: Option<Vec<String>>,
     flag_index: Option<String>,
+    flag_registry: Option<String>,
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
     flag_list: bool,
 }
 
@@ -26,10 +30,14 @@ Options:
     -r, --remove LOGIN       Name of a user or team to remove as an owner
     -l, --list               List owners of a crate
     --index INDEX            Registry index to modify owners for
+    --registry REGISTRY      Name of the registry to modify owners for
     --token TOKEN            API token to use when authenticating
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --frozen                 Require Cargo.lock and cache are up to date
+    --locked                 Require Cargo.lock is up to date
+    --offline                Run without accessing the network
 
 This command will modify the owners for a package on the specified registry (or
 default). Note that owners of a package can upload new versions, yank old
@@ -41,13 +49,17 @@ and troubleshooting.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
     let opts = ops::OwnersOptions {
         krate: options.arg_crate,
         token: options.flag_token,
         index: options.flag_index,
+        registry: options.flag_registry,
         to_add: options.flag_add,
         to_remove: options.flag_remove,
         list: options.flag_list,
@@ -14,6 +14,11 @@ pub struct Options {
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+    flag_message_format: ops::MessageFormat,
+    flag_profile: Option<String>,
     flag_release: bool,
     arg_args: Vec<String>,
 }
@@ -30,6 +35,7 @@ Options:
     --example NAME          Name of the example target to run
     -j N, --jobs N          The number of jobs to run in parallel
     --release               Build artifacts in release mode, with optimizations
+    --profile NAME          Build artifacts with the specified custom profile
     --features FEATURES     Space-separated list of features to also build
     --no-default-features   Do not build the `default` feature
     --target TRIPLE         Build for the target triple
@@ -37,6 +43,10 @@ Options:
     -v, --verbose           Use verbose output
     -q, --quiet             No output printed to stdout
     --color WHEN            Coloring: auto, always, never
+    --frozen                Require Cargo.lock and cache are up to date
+    --locked                Require Cargo.lock is up to date
+    --offline               Run without accessing the network
+    --message-format FMT    Error format: human, json [default: human]
 
 If neither `--bin` nor `--example` are given, then if the project only has one
 bin target it will be run. Otherwise `--bin` specifies the bin target to run,
@@ -49,9 +59,12 @@ the ones before go to Cargo.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
 
     let root = try!(find_root_manifest_for_wd(options.flag_manifest_path, config.cwd()));
 
@@ -72,7 +85,12 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
         spec: &[],
         exec_engine: None,
         release: options.flag_release,
+        profile: options.flag_profile.as_ref().map(|s| &s[..]),
         mode: ops::CompileMode::Build,
+        message_format: options.flag_message_format,
+        build_plan: false,
+        explain_rebuilds: false,
+        dry_run: false,
         filter: if examples.is_empty() && bins.is_empty() {
             ops::CompileFilter::Everything
         } else {
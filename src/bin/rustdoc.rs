@@ -15,6 +15,9 @@ pub struct Options {
     flag_release: bool,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
     flag_package: Option<String>,
     flag_lib: bool,
     flag_bin: Vec<String>,
@@ -47,6 +50,9 @@ Options:
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --frozen                 Require Cargo.lock and cache are up to date
+    --locked                 Require Cargo.lock is up to date
+    --offline                Run without accessing the network
 
 The specified target for the current package (or package specified by SPEC if
 provided) will be documented with the specified <opts>... being passed to the
@@ -62,9 +68,12 @@ the `cargo help pkgid` command.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
 
     let root = try!(find_root_manifest_for_wd(options.flag_manifest_path,
                                               config.cwd()));
@@ -80,12 +89,17 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
             spec: &options.flag_package.map_or(Vec::new(), |s| vec![s]),
             exec_engine: None,
             release: options.flag_release,
+            profile: None,
             filter: ops::CompileFilter::new(options.flag_lib,
                                             &options.flag_bin,
                                             &options.flag_test,
                                             &options.flag_example,
                                             &options.flag_bench),
             mode: ops::CompileMode::Doc { deps: false },
+            message_format: ops::MessageFormat::Human,
+            build_plan: false,
+            explain_rebuilds: false,
+            dry_run: false,
             target_rustdoc_args: Some(&options.arg_opts),
             target_rustc_args: None,
         },
@@ -6,9 +6,13 @@ use std::cmp;
 #[derive(RustcDecodable)]
 pub struct Options {
     flag_host: Option<String>,
+    flag_registry: Option<String>,
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
     flag_limit: Option<u32>,
     arg_query: Vec<String>
 }
@@ -23,23 +27,32 @@ Usage:
 Options:
     -h, --help               Print this message
     --host HOST              Host of a registry to search in
+    --registry REGISTRY      Name of the registry to search in
     -v, --verbose            Use verbose output
     -q, --quiet              No output printed to stdout
     --color WHEN             Coloring: auto, always, never
+    --frozen                 Require Cargo.lock and cache are up to date
+    --locked                 Require Cargo.lock is up to date
+    --offline                Run without accessing the network
     --limit LIMIT            Limit the number of results (default: 10, max: 100)
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
     let Options {
         flag_host: host,
+        flag_registry: registry,
         flag_limit: limit,
         arg_query: query,
         ..
     } = options;
 
-    try!(ops::search(&query.join("+"), config, host, cmp::min(100, limit.unwrap_or(10)) as u8));
+    try!(ops::search(&query.join("+"), config, host, registry,
+                     cmp::min(100, limit.unwrap_or(10)) as u8));
     Ok(None)
 }
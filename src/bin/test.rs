@@ -21,6 +21,11 @@ pub struct Options {
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+    flag_message_format: ops::MessageFormat,
+    flag_profile: Option<String>,
     flag_release: bool,
     flag_no_fail_fast: bool,
 }
@@ -43,6 +48,7 @@ Options:
     -p SPEC, --package SPEC ...  Package to run tests for
     -j N, --jobs N               The number of jobs to run in parallel
     --release                    Build artifacts in release mode, with optimizations
+    --profile NAME               Build artifacts with the specified custom profile
     --features FEATURES          Space-separated list of features to also build
     --no-default-features        Do not build the `default` feature
     --target TRIPLE              Build for the target triple
@@ -50,6 +56,10 @@ Options:
     -v, --verbose                Use verbose output
     -q, --quiet                  No output printed to stdout
     --color WHEN                 Coloring: auto, always, never
+    --frozen                     Require Cargo.lock and cache are up to date
+    --locked                     Require Cargo.lock is up to date
+    --offline                    Run without accessing the network
+    --message-format FMT         Error format: human, json [default: human]
     --no-fail-fast               Run all tests regardless of failure
 
 All of the trailing arguments are passed to the test binaries generated for
@@ -80,9 +90,12 @@ To get the list of all options available for the test binaries use this:
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
     let root = try!(find_root_manifest_for_wd(options.flag_manifest_path, config.cwd()));
 
     let empty = Vec::new();
@@ -112,7 +125,12 @@ pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
             spec: &options.flag_package,
             exec_engine: None,
             release: options.flag_release,
+            profile: options.flag_profile.as_ref().map(|s| &s[..]),
             mode: mode,
+            message_format: options.flag_message_format,
+            build_plan: false,
+            explain_rebuilds: false,
+            dry_run: false,
             filter: filter,
             target_rustdoc_args: None,
             target_rustc_args: None,
@@ -0,0 +1,63 @@
+use cargo::ops;
+use cargo::util::{CliResult, Config};
+use cargo::util::important_paths::find_root_manifest_for_wd;
+
+#[derive(RustcDecodable)]
+pub struct Options {
+    arg_path: Option<String>,
+    flag_manifest_path: Option<String>,
+    flag_no_delete: bool,
+    flag_verbose: Option<bool>,
+    flag_quiet: Option<bool>,
+    flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
+}
+
+pub const USAGE: &'static str = "
+Vendor all dependencies of a package into a local directory.
+
+Usage:
+    cargo vendor [options] [<path>]
+
+Options:
+    -h, --help               Print this message
+    --manifest-path PATH     Path to the manifest to vendor dependencies for
+    --no-delete              Don't delete older crates in the vendor directory
+    -v, --verbose            Use verbose output
+    -q, --quiet              No output printed to stdout
+    --color WHEN             Coloring: auto, always, never
+    --frozen                 Require Cargo.lock and cache are up to date
+    --locked                 Require Cargo.lock is up to date
+    --offline                Run without accessing the network
+
+This command will resolve and download all dependencies of the package and
+then copy them into <path> (which defaults to `vendor`). Each crate is placed
+in its own directory along with a `.cargo-checksum.json` file so it can be
+used as a directory source.
+
+After the vendoring is complete, the `[source]` configuration needed to build
+from the vendored copies instead of the network is printed to stdout.
+";
+
+pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
+    let root = try!(find_root_manifest_for_wd(options.flag_manifest_path, config.cwd()));
+    let path = options.arg_path.unwrap_or("vendor".to_string());
+    let destination = config.cwd().join(path);
+    let opts = ops::VendorOptions {
+        config: config,
+        destination: &destination,
+        no_delete: options.flag_no_delete,
+    };
+    let source_config = try!(ops::vendor(&root, &opts));
+    println!("To use vendored sources, add this to your .cargo/config for \
+              this project:\n\n{}", source_config);
+    Ok(None)
+}
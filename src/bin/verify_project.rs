@@ -16,6 +16,9 @@ pub struct Flags {
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
 }
 
 pub const USAGE: &'static str = "
@@ -31,12 +34,18 @@ Options:
     -v, --verbose           Use verbose output
     -q, --quiet             No output printed to stdout
     --color WHEN            Coloring: auto, always, never
+    --frozen                Require Cargo.lock and cache are up to date
+    --locked                Require Cargo.lock is up to date
+    --offline               Run without accessing the network
 ";
 
 pub fn execute(args: Flags, config: &Config) -> CliResult<Option<Error>> {
-    try!(config.configure_shell(args.flag_verbose,
-                                args.flag_quiet,
-                                &args.flag_color));
+    try!(config.configure(args.flag_verbose,
+                          args.flag_quiet,
+                          &args.flag_color,
+                          args.flag_frozen,
+                          args.flag_locked,
+                          args.flag_offline));
 
     let mut contents = String::new();
     let filename = args.flag_manifest_path.unwrap_or("Cargo.toml".into());
@@ -7,9 +7,13 @@ pub struct Options {
     flag_token: Option<String>,
     flag_vers: Option<String>,
     flag_index: Option<String>,
+    flag_registry: Option<String>,
     flag_verbose: Option<bool>,
     flag_quiet: Option<bool>,
     flag_color: Option<String>,
+    flag_frozen: bool,
+    flag_locked: bool,
+    flag_offline: bool,
     flag_undo: bool,
 }
 
@@ -20,14 +24,18 @@ Usage:
     cargo yank [options] [<crate>]
 
 Options:
-    -h, --help          Print this message
-    --vers VERSION      The version to yank or un-yank
-    --undo              Undo a yank, putting a version back into the index
-    --index INDEX       Registry index to yank from
-    --token TOKEN       API token to use when authenticating
-    -v, --verbose       Use verbose output
-    -q, --quiet         No output printed to stdout
-    --color WHEN        Coloring: auto, always, never
+    -h, --help           Print this message
+    --vers VERSION       The version to yank or un-yank
+    --undo               Undo a yank, putting a version back into the index
+    --index INDEX        Registry index to yank from
+    --registry REGISTRY  Name of the registry to yank from
+    --token TOKEN        API token to use when authenticating
+    -v, --verbose        Use verbose output
+    -q, --quiet          No output printed to stdout
+    --color WHEN         Coloring: auto, always, never
+    --frozen             Require Cargo.lock and cache are up to date
+    --locked             Require Cargo.lock is up to date
+    --offline            Run without accessing the network
 
 The yank command removes a previously pushed crate's version from the server's
 index. This command does not delete any data, and the crate will still be
@@ -39,14 +47,18 @@ crates to be locked to any yanked version.
 ";
 
 pub fn execute(options: Options, config: &Config) -> CliResult<Option<()>> {
-    try!(config.configure_shell(options.flag_verbose,
-                                options.flag_quiet,
-                                &options.flag_color));
+    try!(config.configure(options.flag_verbose,
+                          options.flag_quiet,
+                          &options.flag_color,
+                          options.flag_frozen,
+                          options.flag_locked,
+                          options.flag_offline));
     try!(ops::yank(config,
                    options.arg_crate,
                    options.flag_vers,
                    options.flag_token,
                    options.flag_index,
+                   options.flag_registry,
                    options.flag_undo));
     Ok(None)
 }
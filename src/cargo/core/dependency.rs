@@ -212,6 +212,17 @@ impl DependencyInner {
     pub fn into_dependency(self) -> Dependency {
         Dependency {inner: Rc::new(self)}
     }
+
+    /// Rewrites this dependency to point at `replace_with` if it currently
+    /// points at `to_replace`.
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> DependencyInner {
+        if self.source_id != *to_replace {
+            self
+        } else {
+            self.set_source_id(replace_with.clone())
+        }
+    }
 }
 
 impl Dependency {
@@ -265,6 +276,16 @@ impl Dependency {
     pub fn matches_id(&self, id: &PackageId) -> bool {
         self.inner.matches_id(id)
     }
+
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Dependency {
+        if self.source_id() != to_replace {
+            self
+        } else {
+            self.clone_inner().map_source(to_replace, replace_with)
+                .into_dependency()
+        }
+    }
 }
 
 impl Platform {
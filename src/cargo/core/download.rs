@@ -0,0 +1,195 @@
+use std::cmp;
+use std::io::SeekFrom;
+use std::io::prelude::*;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender, Receiver};
+
+use crossbeam;
+use curl;
+use curl::easy::{Easy, List};
+use rustc_serialize::hex::ToHex;
+use url::Url;
+
+use core::PackageId;
+use ops;
+use util::{CargoResult, ChainError, Config, FileLock, Sha256, internal};
+use util::errors::NetworkError;
+use util::network::Retry;
+
+/// A crate tarball which a source needs downloaded over HTTP before the
+/// package can be loaded, see `Source::prepare_download`.
+///
+/// Downloads are performed by `download_all`, which runs many of them at once.
+pub struct Download {
+    pub id: PackageId,
+    pub url: Url,
+    /// URLs to fall back to, in order, if `url` can't be reached.
+    pub mirrors: Vec<Url>,
+    /// The SHA-256 the tarball is expected to have.
+    pub checksum: String,
+    /// The exclusively locked file the tarball is written to.
+    pub dst: FileLock,
+}
+
+/// Performs all of `downloads` concurrently, returning the file each tarball
+/// was written to, positioned at its start, in the same order.
+///
+/// At most `net.download-jobs` transfers are run at once, each on its own
+/// thread with its own curl handle. Spurious failures of each transfer are
+/// retried according to `net.retry`, just like `network::with_retry`, after
+/// which the next of its mirrors is tried.
+pub fn download_all(config: &Config, mut downloads: Vec<Download>)
+                    -> CargoResult<Vec<FileLock>> {
+    if downloads.is_empty() {
+        return Ok(Vec::new())
+    }
+
+    let jobs = try!(config.net_download_jobs()) as usize;
+    let jobs = cmp::min(jobs, downloads.len());
+    let mut handles = Vec::new();
+    for _ in 0..jobs {
+        handles.push(try!(ops::http_handle(config)));
+    }
+    let mut retries = Vec::new();
+    for _ in downloads.iter() {
+        retries.push(try!(Retry::new(config)));
+    }
+
+    let (work_tx, work_rx) = channel::<(usize, String)>();
+    let (done_tx, done_rx) = channel();
+    let work_rx = Arc::new(Mutex::new(work_rx));
+    let abort = Arc::new(AtomicBool::new(false));
+
+    let res = crossbeam::scope(|scope| {
+        for mut handle in handles {
+            let work_rx = work_rx.clone();
+            let done_tx = done_tx.clone();
+            let abort = abort.clone();
+            scope.spawn(move || {
+                loop {
+                    let next = work_rx.lock().unwrap().recv();
+                    let (i, url) = match next {
+                        Ok(next) => next,
+                        Err(..) => break,
+                    };
+                    if abort.load(Ordering::SeqCst) {
+                        break
+                    }
+                    let res = transfer(&mut handle, &url);
+                    if done_tx.send((i, res)).is_err() {
+                        break
+                    }
+                }
+            });
+        }
+
+        let res = run(config, &mut downloads, &mut retries, &work_tx,
+                      &done_rx);
+        if res.is_err() {
+            abort.store(true, Ordering::SeqCst);
+        }
+        // Hanging up the queue lets the workers exit.
+        drop(work_tx);
+        res
+    });
+    try!(config.shell().clear_progress());
+    try!(res);
+
+    Ok(downloads.into_iter().map(|d| d.dst).collect())
+}
+
+/// Queues up every download and then waits for them all to finish, retrying
+/// them as needed.
+fn run<'a>(config: &'a Config,
+           downloads: &mut [Download],
+           retries: &mut [Retry<'a>],
+           work: &Sender<(usize, String)>,
+           done: &Receiver<(usize, Result<(u32, Vec<u8>), curl::Error>)>)
+           -> CargoResult<()> {
+    for (i, download) in downloads.iter().enumerate() {
+        try!(config.shell().status("Downloading", &download.id));
+        work.send((i, download.url.to_string())).unwrap();
+    }
+
+    let total = downloads.len();
+    let mut finished = 0;
+    while finished < total {
+        try!(config.shell().progress("Downloading",
+                                     format!("{} of {} crates",
+                                             finished, total)));
+        let (i, res) = done.recv().unwrap();
+        try!(config.shell().clear_progress());
+        let spurious = match res {
+            Err(ref e) => e.maybe_spurious(),
+            Ok(..) => false,
+        };
+        let res = match retries[i].check(res) {
+            Ok(Some((code, body))) => {
+                finish(&mut downloads[i], code, body).map(Some)
+            }
+            Ok(None) => Ok(None),
+            Err(ref e) if spurious && !downloads[i].mirrors.is_empty() => {
+                let download = &mut downloads[i];
+                let mirror = download.mirrors.remove(0);
+                try!(config.shell().warn(format!(
+                    "failed to download `{}` from {}, trying mirror {}: {}",
+                    download.id, download.url, mirror, e)));
+                download.url = mirror;
+                retries[i] = try!(Retry::new(config));
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        };
+        let download = &downloads[i];
+        let res = res.chain_error(|| {
+            internal(format!("failed to download package `{}` from {}",
+                             download.id, download.url))
+        });
+        match try!(res) {
+            Some(()) => finished += 1,
+            None => work.send((i, download.url.to_string())).unwrap(),
+        }
+    }
+    Ok(())
+}
+
+/// Performs the HTTP request for `url`, returning the response code and
+/// body.
+fn transfer(handle: &mut Easy, url: &str)
+            -> Result<(u32, Vec<u8>), curl::Error> {
+    try!(handle.get(true));
+    try!(handle.url(url));
+    try!(handle.follow_location(true));
+    try!(handle.http_headers(List::new()));
+    let mut body = Vec::new();
+    {
+        let mut handle = handle.transfer();
+        try!(handle.write_function(|buf| {
+            body.extend_from_slice(buf);
+            Ok(buf.len())
+        }));
+        try!(handle.perform());
+    }
+    let code = try!(handle.response_code());
+    Ok((code, body))
+}
+
+/// Verifies a tarball which was just downloaded and writes it out.
+fn finish(download: &mut Download, code: u32, body: Vec<u8>)
+          -> CargoResult<()> {
+    if code != 200 && code != 0 {
+        bail!("failed to get 200 response from `{}`, got {}",
+              download.url, code)
+    }
+
+    let mut state = Sha256::new();
+    state.update(&body);
+    if state.finish().to_hex() != download.checksum {
+        bail!("failed to verify the checksum of `{}`", download.id)
+    }
+
+    try!(download.dst.write_all(&body));
+    try!(download.dst.seek(SeekFrom::Start(0)));
+    Ok(())
+}
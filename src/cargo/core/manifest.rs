@@ -1,12 +1,13 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::path::{PathBuf, Path};
 
 use semver::Version;
 use rustc_serialize::{Encoder, Encodable};
 
-use core::{Dependency, PackageId, PackageIdSpec, Summary};
+use core::{Dependency, PackageId, PackageIdSpec, SourceId, Summary};
 use core::package_id::Metadata;
-use util::{CargoResult, human};
+use util::{CargoError, CargoResult, ChainError, human, internal};
 
 /// Contains all the information about a package, as loaded from a Cargo.toml.
 #[derive(Clone, Debug)]
@@ -15,6 +16,7 @@ pub struct Manifest {
     targets: Vec<Target>,
     links: Option<String>,
     warnings: Vec<String>,
+    profile_error: Option<(PathBuf, Vec<(String, bool)>)>,
     exclude: Vec<String>,
     include: Vec<String>,
     metadata: ManifestMetadata,
@@ -102,17 +104,24 @@ impl Encodable for TargetKind {
 
 #[derive(RustcEncodable, RustcDecodable, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Profile {
-    pub opt_level: u32,
+    pub opt_level: String,
     pub lto: bool,
     pub codegen_units: Option<u32>,    // None = use rustc default
     pub rustc_args: Option<Vec<String>>,
     pub rustdoc_args: Option<Vec<String>>,
-    pub debuginfo: bool,
+    pub debuginfo: Option<u32>,        // None = no debuginfo, 1 = line tables
     pub debug_assertions: bool,
+    pub overflow_checks: bool,
     pub rpath: bool,
+    pub incremental: bool,
+    pub strip: Option<String>,         // "symbols" or "debuginfo"
     pub test: bool,
     pub doc: bool,
     pub run_custom_build: bool,
+    pub check: bool,
+    /// Whether the `build-override` settings are applied, which is the case
+    /// for build scripts, plugins and their dependencies.
+    pub build_override: bool,
     pub panic: Option<String>,
 }
 
@@ -126,6 +135,29 @@ pub struct Profiles {
     pub bench_deps: Profile,
     pub doc: Profile,
     pub custom_build: Profile,
+    pub check: Profile,
+    pub custom: HashMap<String, CustomProfile>,
+    /// The profiles to use instead for the packages matched by
+    /// `[profile.*.package.<spec>]` overrides, most specific spec first,
+    /// which is the order they're checked in.
+    pub package: Vec<(PackageIdSpec, Profiles)>,
+    /// The profiles to use instead for units compiled for the host, if any
+    /// `build-override` is set.
+    pub build_override: Option<Box<Profiles>>,
+}
+
+/// A profile defined with a `[profile.<name>]` table of its own, which can be
+/// selected with `--profile <name>`.
+#[derive(Clone, Debug)]
+pub struct CustomProfile {
+    pub profile: Profile,
+    /// Whether the profile ultimately inherits from `release` rather than
+    /// `dev`.
+    pub release: bool,
+    /// The profile with its `build-override` applied, if it has one
+    pub build_override: Option<Profile>,
+    /// The profile with each of its package overrides applied
+    pub package: Vec<(PackageIdSpec, CustomProfile)>,
 }
 
 /// Information about a binary, a library, an example, etc. that is part of the
@@ -174,6 +206,7 @@ impl Manifest {
             summary: summary,
             targets: targets,
             warnings: Vec::new(),
+            profile_error: None,
             exclude: exclude,
             include: include,
             links: links,
@@ -191,10 +224,13 @@ impl Manifest {
     pub fn name(&self) -> &str { self.package_id().name() }
     pub fn package_id(&self) -> &PackageId { self.summary.package_id() }
     pub fn summary(&self) -> &Summary { &self.summary }
+    pub fn summary_mut(&mut self) -> &mut Summary { &mut self.summary }
     pub fn targets(&self) -> &[Target] { &self.targets }
     pub fn version(&self) -> &Version { self.package_id().version() }
     pub fn warnings(&self) -> &[String] { &self.warnings }
-    pub fn profiles(&self) -> &Profiles { &self.profiles }
+    pub fn profile_error(&self) -> Option<&str> {
+        self.profile_error.as_ref().map(|&(_, ref chain)| &chain[0].0[..])
+    }
     pub fn publish(&self) -> bool { self.publish }
     pub fn replace(&self) -> &[(PackageIdSpec, Dependency)] { &self.replace }
     pub fn links(&self) -> Option<&str> {
@@ -205,9 +241,58 @@ impl Manifest {
         self.warnings.push(s)
     }
 
+    /// Returns the profiles of this manifest, which are only used when it's
+    /// the root of a build, and an error if they're invalid.
+    pub fn profiles(&self) -> CargoResult<&Profiles> {
+        let (path, chain) = match self.profile_error {
+            Some((ref path, ref chain)) => (path, chain),
+            None => return Ok(&self.profiles),
+        };
+        let error = |&(ref e, is_human): &(String, bool)| {
+            if is_human {human(e)} else {internal(e)}
+        };
+        let mut chain = chain.iter().rev();
+        let cause = error(chain.next().unwrap());
+        let mut ret: CargoResult<&Profiles> = Err(cause);
+        for e in chain {
+            ret = ret.chain_error(|| error(e));
+        }
+        ret.chain_error(|| {
+            human(format!("failed to parse manifest at `{}`", path.display()))
+        })
+    }
+
+    /// Records that the `[profile]` tables of the manifest at `path` are
+    /// invalid, in which case the default profiles are used in their place.
+    ///
+    /// Manifests are cloned, which errors can't be, so the messages of the
+    /// error and its causes are kept to rebuild it with.
+    pub fn set_profile_error(&mut self, path: PathBuf, error: &CargoError) {
+        let mut chain = vec![(error.to_string(), error.is_human())];
+        let mut cargo_err = error;
+        while let Some(cause) = cargo_err.cargo_cause() {
+            chain.push((cause.to_string(), cause.is_human()));
+            cargo_err = cause;
+        }
+        let mut err = cargo_err.cause();
+        while let Some(cause) = err {
+            chain.push((cause.to_string(), false));
+            err = cause.cause();
+        }
+        self.profile_error = Some((path, chain));
+    }
+
     pub fn set_summary(&mut self, summary: Summary) {
         self.summary = summary;
     }
+
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Manifest {
+        Manifest {
+            summary: self.summary.map_source(to_replace, replace_with),
+            ..self
+        }
+    }
 }
 
 impl Target {
@@ -415,16 +500,17 @@ impl fmt::Display for Target {
 impl Profile {
     pub fn default_dev() -> Profile {
         Profile {
-            debuginfo: true,
+            debuginfo: Some(2),
             debug_assertions: true,
+            overflow_checks: true,
             ..Profile::default()
         }
     }
 
     pub fn default_release() -> Profile {
         Profile {
-            opt_level: 3,
-            debuginfo: false,
+            opt_level: "3".to_string(),
+            debuginfo: None,
             ..Profile::default()
         }
     }
@@ -456,22 +542,76 @@ impl Profile {
             ..Profile::default_dev()
         }
     }
+
+    pub fn default_check() -> Profile {
+        Profile {
+            check: true,
+            ..Profile::default_dev()
+        }
+    }
+}
+
+impl Profiles {
+    /// Returns the profiles to compile with when the custom profile `custom`
+    /// is selected. It takes the place of both `dev` and `release`, and tests,
+    /// benchmarks, documentation and checks are compiled with its settings.
+    pub fn for_custom(&self, custom: &CustomProfile) -> Profiles {
+        let mut profiles = self.from_custom_profile(&custom.profile);
+        profiles.build_override = custom.build_override.as_ref().map(|p| {
+            Box::new(self.from_custom_profile(p))
+        });
+        profiles.package = custom.package.iter().map(|&(ref spec, ref c)| {
+            (spec.clone(), self.for_custom(c))
+        }).collect();
+        profiles
+    }
+
+    fn from_custom_profile(&self, profile: &Profile) -> Profiles {
+        let deps = Profile { panic: None, ..profile.clone() };
+        Profiles {
+            release: profile.clone(),
+            dev: profile.clone(),
+            test: Profile { test: true, ..profile.clone() },
+            test_deps: deps.clone(),
+            bench: Profile { test: true, ..profile.clone() },
+            bench_deps: deps,
+            doc: Profile { doc: true, ..profile.clone() },
+            custom_build: self.custom_build.clone(),
+            check: Profile { check: true, ..profile.clone() },
+            custom: HashMap::new(),
+            package: Vec::new(),
+            build_override: None,
+        }
+    }
+
+    /// Returns the profiles to compile the package `id` with, taking the
+    /// most specific package override that matches it into account.
+    pub fn for_package(&self, id: &PackageId) -> &Profiles {
+        self.package.iter().find(|&&(ref spec, _)| {
+            spec.matches(id)
+        }).map(|&(_, ref profiles)| profiles).unwrap_or(self)
+    }
 }
 
 impl Default for Profile {
     fn default() -> Profile {
         Profile {
-            opt_level: 0,
+            opt_level: "0".to_string(),
             lto: false,
             codegen_units: None,
             rustc_args: None,
             rustdoc_args: None,
-            debuginfo: false,
+            debuginfo: None,
             debug_assertions: false,
+            overflow_checks: false,
             rpath: false,
+            incremental: false,
+            strip: None,
             test: false,
             doc: false,
             run_custom_build: false,
+            check: false,
+            build_override: false,
             panic: None,
         }
     }
@@ -485,6 +625,8 @@ impl fmt::Display for Profile {
             write!(f, "Profile(doc)")
         } else if self.run_custom_build {
             write!(f, "Profile(run)")
+        } else if self.check {
+            write!(f, "Profile(check)")
         } else {
             write!(f, "Profile(build)")
         }
@@ -1,4 +1,5 @@
 pub use self::dependency::{Dependency, DependencyInner};
+pub use self::download::Download;
 pub use self::manifest::{Manifest, Target, TargetKind, Profile, LibKind, Profiles};
 pub use self::package::{Package, PackageSet};
 pub use self::package_id::{PackageId, Metadata};
@@ -18,4 +19,5 @@ pub mod resolver;
 pub mod summary;
 pub mod shell;
 pub mod registry;
+pub mod download;
 mod package_id_spec;
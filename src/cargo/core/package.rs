@@ -1,5 +1,5 @@
 use std::cell::{Ref, RefCell};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash;
 use std::path::{Path, PathBuf};
@@ -8,6 +8,7 @@ use semver::Version;
 
 use core::{Dependency, Manifest, PackageId, SourceId, Target, TargetKind};
 use core::{Summary, Metadata, SourceMap};
+use core::download;
 use ops;
 use util::{CargoResult, Config, LazyCell, ChainError, internal, human, lev_distance};
 use rustc_serialize::{Encoder,Encodable};
@@ -73,6 +74,7 @@ impl Package {
 
     pub fn dependencies(&self) -> &[Dependency] { self.manifest.dependencies() }
     pub fn manifest(&self) -> &Manifest { &self.manifest }
+    pub fn manifest_mut(&mut self) -> &mut Manifest { &mut self.manifest }
     pub fn manifest_path(&self) -> &Path { &self.manifest_path }
     pub fn name(&self) -> &str { self.package_id().name() }
     pub fn package_id(&self) -> &PackageId { self.manifest.package_id() }
@@ -91,6 +93,14 @@ impl Package {
         self.package_id().generate_metadata()
     }
 
+    pub fn map_source(self, to_replace: &SourceId, replace_with: &SourceId)
+                      -> Package {
+        Package {
+            manifest: self.manifest.map_source(to_replace, replace_with),
+            manifest_path: self.manifest_path,
+        }
+    }
+
     pub fn find_closest_target(&self, target: &str, kind: TargetKind) -> Option<&Target> {
         let targets = self.targets();
 
@@ -124,16 +134,19 @@ impl hash::Hash for Package {
 pub struct PackageSet<'cfg> {
     packages: Vec<(PackageId, LazyCell<Package>)>,
     sources: RefCell<SourceMap<'cfg>>,
+    config: &'cfg Config,
 }
 
 impl<'cfg> PackageSet<'cfg> {
     pub fn new(package_ids: &[PackageId],
-               sources: SourceMap<'cfg>) -> PackageSet<'cfg> {
+               sources: SourceMap<'cfg>,
+               config: &'cfg Config) -> PackageSet<'cfg> {
         PackageSet {
             packages: package_ids.iter().map(|id| {
                 (id.clone(), LazyCell::new(None))
             }).collect(),
             sources: RefCell::new(sources),
+            config: config,
         }
     }
 
@@ -141,11 +154,16 @@ impl<'cfg> PackageSet<'cfg> {
         Box::new(self.packages.iter().map(|&(ref p, _)| p))
     }
 
+    /// Returns the packages which have been loaded so far, without
+    /// downloading any others.
+    pub fn loaded<'a>(&'a self) -> Box<Iterator<Item=&'a Package> + 'a> {
+        Box::new(self.packages.iter().filter_map(|&(_, ref slot)| {
+            slot.borrow()
+        }))
+    }
+
     pub fn get(&self, id: &PackageId) -> CargoResult<&Package> {
-        let slot = try!(self.packages.iter().find(|p| p.0 == *id).chain_error(|| {
-            internal(format!("couldn't find `{}` in package set", id))
-        }));
-        let slot = &slot.1;
+        let slot = try!(self.slot(id));
         if let Some(pkg) = slot.borrow() {
             return Ok(pkg)
         }
@@ -160,6 +178,42 @@ impl<'cfg> PackageSet<'cfg> {
         Ok(slot.borrow().unwrap())
     }
 
+    /// Gets many packages at once, downloading any tarballs they need
+    /// concurrently rather than one after another as `get` would.
+    pub fn get_many<'a, I>(&self, ids: I) -> CargoResult<Vec<&Package>>
+        where I: IntoIterator<Item=&'a PackageId>
+    {
+        let ids = ids.into_iter().collect::<Vec<_>>();
+        let mut downloads = Vec::new();
+        {
+            let mut seen = HashSet::new();
+            let mut sources = self.sources.borrow_mut();
+            for &id in ids.iter() {
+                if try!(self.slot(id)).borrow().is_some() || !seen.insert(id) {
+                    continue
+                }
+                let source = try!(sources.get_mut(id.source_id()).chain_error(|| {
+                    internal(format!("couldn't find source for `{}`", id))
+                }));
+                let download = try!(source.prepare_download(id).chain_error(|| {
+                    human("unable to get packages from source")
+                }));
+                downloads.extend(download);
+            }
+        }
+        try!(download::download_all(self.config, downloads).chain_error(|| {
+            human("unable to get packages from source")
+        }));
+        ids.into_iter().map(|id| self.get(id)).collect()
+    }
+
+    fn slot(&self, id: &PackageId) -> CargoResult<&LazyCell<Package>> {
+        let slot = try!(self.packages.iter().find(|p| p.0 == *id).chain_error(|| {
+            internal(format!("couldn't find `{}` in package set", id))
+        }));
+        Ok(&slot.1)
+    }
+
     pub fn sources(&self) -> Ref<SourceMap<'cfg>> {
         self.sources.borrow()
     }
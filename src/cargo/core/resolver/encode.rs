@@ -1,10 +1,12 @@
 use std::collections::{HashMap, BTreeMap};
+use std::fmt;
+use std::str::FromStr;
 
 use regex::Regex;
 use rustc_serialize::{Encodable, Encoder, Decodable, Decoder};
 
 use core::{Package, PackageId, SourceId};
-use util::{CargoResult, Graph, Config};
+use util::{CargoResult, CargoError, Graph, Config, internal, ChainError};
 
 use super::Resolve;
 
@@ -93,12 +95,38 @@ impl EncodableResolve {
             }
         }
 
+        let mut metadata = self.metadata.clone().unwrap_or(BTreeMap::new());
+
+        // Checksums of packages are stored in the metadata table as
+        // `"checksum <package id>" = "<sha256>"`. Old lock files may not list
+        // them at all, and lock files touched by older versions of Cargo may
+        // list too few or too many, so this is best effort: we slurp up all
+        // the ones we can find and the rest are filled in when resolving.
+        let mut checksums = HashMap::new();
+        let prefix = "checksum ";
+        let mut to_remove = Vec::new();
+        for (k, v) in metadata.iter().filter(|p| p.0.starts_with(prefix)) {
+            to_remove.push(k.to_string());
+            let k = &k[prefix.len()..];
+            let id: EncodablePackageId = try!(k.parse().chain_error(|| {
+                internal("invalid encoding of checksum in lockfile")
+            }));
+            let id = try!(to_package_id(&id.name, &id.version,
+                                        id.source.as_ref(), default,
+                                        &path_deps));
+            checksums.insert(id, v.to_string());
+        }
+        for k in to_remove {
+            metadata.remove(&k);
+        }
+
         Ok(Resolve {
             graph: g,
             root: root,
             features: HashMap::new(),
-            metadata: self.metadata.clone(),
+            metadata: if metadata.is_empty() {None} else {Some(metadata)},
             replacements: replacements,
+            checksums: checksums,
         })
     }
 }
@@ -159,22 +187,24 @@ pub struct EncodablePackageId {
     source: Option<SourceId>
 }
 
-impl Encodable for EncodablePackageId {
-    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
-        let mut out = format!("{} {}", self.name, self.version);
+impl fmt::Display for EncodablePackageId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(write!(f, "{} {}", self.name, self.version));
         if let Some(ref s) = self.source {
-            out.push_str(&format!(" ({})", s.to_url()));
+            try!(write!(f, " ({})", s.to_url()));
         }
-        out.encode(s)
+        Ok(())
     }
 }
 
-impl Decodable for EncodablePackageId {
-    fn decode<D: Decoder>(d: &mut D) -> Result<EncodablePackageId, D::Error> {
-        let string: String = try!(Decodable::decode(d));
+impl FromStr for EncodablePackageId {
+    type Err = Box<CargoError>;
+
+    fn from_str(s: &str) -> CargoResult<EncodablePackageId> {
         let regex = Regex::new(r"^([^ ]+) ([^ ]+)(?: \(([^\)]+)\))?$").unwrap();
-        let captures = regex.captures(&string)
-                            .expect("invalid serialized PackageId");
+        let captures = try!(regex.captures(s).ok_or_else(|| {
+            internal("invalid serialized PackageId")
+        }));
 
         let name = captures.at(1).unwrap();
         let version = captures.at(2).unwrap();
@@ -191,6 +221,19 @@ impl Decodable for EncodablePackageId {
     }
 }
 
+impl Encodable for EncodablePackageId {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        self.to_string().encode(s)
+    }
+}
+
+impl Decodable for EncodablePackageId {
+    fn decode<D: Decoder>(d: &mut D) -> Result<EncodablePackageId, D::Error> {
+        let string: String = try!(Decodable::decode(d));
+        string.parse().map_err(|e: Box<CargoError>| d.error(&e.to_string()))
+    }
+}
+
 impl Encodable for Resolve {
     fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
         let mut ids: Vec<&PackageId> = self.graph.iter().collect();
@@ -202,10 +245,19 @@ impl Encodable for Resolve {
             Some(encodable_resolve_node(id, self))
         }).collect::<Vec<EncodableDependency>>();
 
+        let mut metadata = self.metadata.clone().unwrap_or(BTreeMap::new());
+        for id in ids.iter().filter(|id| !id.source_id().is_path()) {
+            if let Some(cksum) = self.checksums.get(*id) {
+                let key = format!("checksum {}", encodable_package_id(id));
+                metadata.insert(key, cksum.to_string());
+            }
+        }
+        let metadata = if metadata.is_empty() {None} else {Some(metadata)};
+
         EncodableResolve {
             package: Some(encodable),
             root: encodable_resolve_node(&self.root, self),
-            metadata: self.metadata.clone(),
+            metadata: metadata,
         }.encode(s)
     }
 }
@@ -77,6 +77,7 @@ pub struct Resolve {
     features: HashMap<PackageId, HashSet<String>>,
     root: PackageId,
     metadata: Option<Metadata>,
+    checksums: HashMap<PackageId, String>,
 }
 
 pub struct Deps<'a> {
@@ -124,11 +125,60 @@ impl Resolve {
             replacements: HashMap::new(),
             features: HashMap::new(),
             metadata: None,
+            checksums: HashMap::new(),
         }
     }
 
-    pub fn copy_metadata(&mut self, other: &Resolve) {
-        self.metadata = other.metadata.clone();
+    /// Carries over the metadata of `previous`, typically the resolve loaded
+    /// from the lock file, into this resolve.
+    ///
+    /// The checksum of a package must never change once it's been recorded,
+    /// so it's an error for a package to have a different checksum here than
+    /// in `previous`. That would indicate that something has gone wrong, for
+    /// example a corrupt lock file or a "mirror" which isn't actually a
+    /// mirror.
+    pub fn merge_from(&mut self, previous: &Resolve) -> CargoResult<()> {
+        for (id, cksum) in previous.checksums.iter() {
+            match self.checksums.get(id) {
+                Some(mine) if mine == cksum => {}
+                Some(_) => {
+                    bail!("checksum for `{}` changed between lock files\n\n\
+                           this could be indicative of a few possible \
+                           errors:\n\n    \
+                           * the lock file is corrupt\n    \
+                           * a replacement source in use (e.g. a mirror) \
+                           returned a different checksum\n    \
+                           * the source itself may be corrupt in one way or \
+                           another\n\n\
+                           unable to verify that `{0}` is the same as when \
+                           the lockfile was generated", id)
+                }
+                // Packages which are no longer part of the graph are fine,
+                // but a package which can't be checksummed any more is not.
+                None if !self.graph.get_nodes().contains_key(id) => {}
+                None => {
+                    bail!("checksum for `{}` could not be calculated, but a \
+                           checksum is listed in the existing lock file\n\n\
+                           this could be indicative of a few possible \
+                           situations:\n\n    \
+                           * the source `{}` supports checksums, but was \
+                           replaced with one that doesn't\n    \
+                           * the lock file is corrupt\n\n\
+                           unable to verify that `{0}` is the same as when \
+                           the lockfile was generated", id, id.source_id())
+                }
+            }
+        }
+
+        // Be sure to just copy over any unknown metadata.
+        self.metadata = previous.metadata.clone();
+        Ok(())
+    }
+
+    /// Returns the checksums of the packages in this resolve, keyed by
+    /// package. Packages whose source can't checksum them aren't listed.
+    pub fn checksums(&self) -> &HashMap<PackageId, String> {
+        &self.checksums
     }
 
     pub fn iter(&self) -> Nodes<PackageId> {
@@ -214,7 +264,15 @@ pub fn resolve(summary: &Summary,
     let _p = profile::start(format!("resolving: {}", summary.package_id()));
     let cx = try!(activate_deps_loop(cx, registry, summary, method));
     try!(check_cycles(&cx));
-    Ok(cx.resolve)
+
+    let mut resolve = cx.resolve;
+    for summary in cx.activations.values().flat_map(|v| v.iter()) {
+        if let Some(cksum) = summary.checksum() {
+            resolve.checksums.insert(summary.package_id().clone(),
+                                     cksum.to_string());
+        }
+    }
+    Ok(resolve)
 }
 
 /// Attempts to activate the summary `candidate` in the context `cx`.
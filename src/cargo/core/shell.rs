@@ -77,6 +77,37 @@ impl MultiShell {
         }
     }
 
+    /// Draws a status line on stderr which is overwritten by the next call,
+    /// for reporting the progress of a long-running operation.
+    ///
+    /// Nothing is drawn unless stderr is a terminal. The line must be removed
+    /// with `clear_progress` before anything else is printed.
+    pub fn progress<T, U>(&mut self, status: T, message: U) -> CargoResult<()>
+        where T: fmt::Display, U: fmt::Display
+    {
+        match self.verbosity {
+            Quiet => Ok(()),
+            _ if !self.err.config.tty => Ok(()),
+            _ => {
+                try!(write!(self.err(), "\r"));
+                self.err().say_status_inline(status, message, GREEN)
+            }
+        }
+    }
+
+    /// Removes the line drawn by `progress`.
+    pub fn clear_progress(&mut self) -> CargoResult<()> {
+        match self.verbosity {
+            Quiet => Ok(()),
+            _ if !self.err.config.tty => Ok(()),
+            _ => {
+                try!(write!(self.err(), "\r{:79}\r", ""));
+                try!(self.err().flush());
+                Ok(())
+            }
+        }
+    }
+
     pub fn verbose<F>(&mut self, mut callback: F) -> CargoResult<()>
         where F: FnMut(&mut MultiShell) -> CargoResult<()>
     {
@@ -191,6 +222,21 @@ impl Shell {
         Ok(())
     }
 
+    /// Like `say_status`, but without ending the line, so it can be redrawn.
+    fn say_status_inline<T, U>(&mut self, status: T, message: U, color: Color)
+                               -> CargoResult<()>
+        where T: fmt::Display, U: fmt::Display
+    {
+        try!(self.reset());
+        if color != BLACK { try!(self.fg(color)); }
+        if self.supports_attr(Attr::Bold) { try!(self.attr(Attr::Bold)); }
+        try!(write!(self, "{:>12}", status.to_string()));
+        try!(self.reset());
+        try!(write!(self, " {:<66}", message.to_string()));
+        try!(self.flush());
+        Ok(())
+    }
+
     fn fg(&mut self, color: color::Color) -> CargoResult<bool> {
         let colored = self.colored();
 
@@ -2,14 +2,14 @@ use std::cmp::{self, Ordering};
 use std::collections::hash_map::{HashMap, Values, IterMut};
 use std::fmt::{self, Formatter};
 use std::hash;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
 
 use url::Url;
 
-use core::{Package, PackageId, Registry};
-use sources::{PathSource, GitSource, RegistrySource};
+use core::{Download, Package, PackageId, Registry};
+use sources::{PathSource, GitSource, RegistrySource, DirectorySource};
 use sources::git;
 use util::{human, Config, CargoResult, ToUrl};
 
@@ -25,6 +25,17 @@ pub trait Source: Registry {
     /// version specified.
     fn download(&mut self, package: &PackageId) -> CargoResult<Package>;
 
+    /// Prepares the network transfer which `download` would otherwise perform
+    /// for `package`, so the transfers for many packages can be run at once.
+    ///
+    /// Returns `None` if nothing needs to be fetched over the network, which
+    /// is always the case for sources which don't download tarballs. Once the
+    /// returned `Download` is complete, `download` will find it locally.
+    fn prepare_download(&mut self, _package: &PackageId)
+                        -> CargoResult<Option<Download>> {
+        Ok(None)
+    }
+
     /// Generates a unique string which represents the fingerprint of the
     /// current state of the source.
     ///
@@ -35,6 +46,38 @@ pub trait Source: Registry {
     /// The `pkg` argument is the package which this fingerprint should only be
     /// interested in for when this source may contain multiple packages.
     fn fingerprint(&self, pkg: &Package) -> CargoResult<String>;
+
+    /// Like `fingerprint`, but also returns the file of `pkg` which changed
+    /// last if the fingerprint follows the files of the package, which is
+    /// used to explain why it's rebuilt.
+    fn fingerprint_with_file(&self, pkg: &Package)
+                             -> CargoResult<(String, Option<PathBuf>)> {
+        Ok((try!(self.fingerprint(pkg)), None))
+    }
+}
+
+impl<'a, T: Source + ?Sized + 'a> Source for Box<T> {
+    fn update(&mut self) -> CargoResult<()> {
+        (**self).update()
+    }
+
+    fn download(&mut self, id: &PackageId) -> CargoResult<Package> {
+        (**self).download(id)
+    }
+
+    fn prepare_download(&mut self, id: &PackageId)
+                        -> CargoResult<Option<Download>> {
+        (**self).prepare_download(id)
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        (**self).fingerprint(pkg)
+    }
+
+    fn fingerprint_with_file(&self, pkg: &Package)
+                             -> CargoResult<(String, Option<PathBuf>)> {
+        (**self).fingerprint_with_file(pkg)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -45,6 +88,10 @@ enum Kind {
     Path,
     /// represents the central registry
     Registry,
+    /// represents a local filesystem-based registry
+    LocalRegistry,
+    /// represents a directory of unpacked crates
+    Directory,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -67,6 +114,8 @@ struct SourceIdInner {
     kind: Kind,
     // e.g. the exact git revision of the specified branch for a Git Source
     precise: Option<String>,
+    // whether the submodules of a Git Source are checked out along with it
+    submodules: bool,
 }
 
 impl SourceId {
@@ -77,6 +126,7 @@ impl SourceId {
                 canonical_url: git::canonicalize_url(&url),
                 url: url,
                 precise: None,
+                submodules: true,
             }),
         }
     }
@@ -100,6 +150,7 @@ impl SourceId {
             "git" => {
                 let mut url = url.to_url().unwrap();
                 let mut reference = GitReference::Branch("master".to_string());
+                let mut submodules = true;
                 for (k, v) in url.query_pairs() {
                     match &k[..] {
                         // map older 'ref' to branch
@@ -108,6 +159,7 @@ impl SourceId {
 
                         "rev" => reference = GitReference::Rev(v.into_owned()),
                         "tag" => reference = GitReference::Tag(v.into_owned()),
+                        "submodules" => submodules = v != "false",
                         _ => {}
                     }
                 }
@@ -115,6 +167,7 @@ impl SourceId {
                 url.set_fragment(None);
                 url.set_query(None);
                 SourceId::for_git(&url, reference).with_precise(precise)
+                                                  .with_submodules(submodules)
             }
             "registry" => {
                 let url = url.to_url().unwrap();
@@ -125,6 +178,15 @@ impl SourceId {
                 let url = url.to_url().unwrap();
                 SourceId::new(Kind::Path, url)
             }
+            "local-registry" => {
+                let url = url.to_url().unwrap();
+                SourceId::new(Kind::LocalRegistry, url)
+                    .with_precise(Some("locked".to_string()))
+            }
+            "directory" => {
+                let url = url.to_url().unwrap();
+                SourceId::new(Kind::Directory, url)
+            }
             _ => panic!("Unsupported serialized SourceId"),
         }
     }
@@ -135,9 +197,14 @@ impl SourceId {
                 format!("path+{}", url)
             }
             SourceIdInner {
-                kind: Kind::Git(ref reference), ref url, ref precise, ..
+                kind: Kind::Git(ref reference), ref url, ref precise,
+                submodules, ..
             } => {
-                let ref_str = reference.url_ref();
+                let mut ref_str = reference.url_ref();
+                if !submodules {
+                    ref_str.push_str(if ref_str.is_empty() {"?"} else {"&"});
+                    ref_str.push_str("submodules=false");
+                }
 
                 let precise_str = if precise.is_some() {
                     format!("#{}", precise.as_ref().unwrap())
@@ -150,6 +217,12 @@ impl SourceId {
             SourceIdInner { kind: Kind::Registry, ref url, .. } => {
                 format!("registry+{}", url)
             }
+            SourceIdInner { kind: Kind::LocalRegistry, ref url, .. } => {
+                format!("local-registry+{}", url)
+            }
+            SourceIdInner { kind: Kind::Directory, ref url, .. } => {
+                format!("directory+{}", url)
+            }
         }
     }
 
@@ -167,6 +240,20 @@ impl SourceId {
         SourceId::new(Kind::Registry, url.clone())
     }
 
+    /// Creates a `SourceId` for a registry which lives in a directory on the
+    /// local filesystem.
+    pub fn for_local_registry(path: &Path) -> CargoResult<SourceId> {
+        let url = try!(path.to_url().map_err(human));
+        Ok(SourceId::new(Kind::LocalRegistry, url))
+    }
+
+    /// Creates a `SourceId` for a directory of unpacked crates, such as one
+    /// populated with vendored dependencies.
+    pub fn for_directory(path: &Path) -> CargoResult<SourceId> {
+        let url = try!(path.to_url().map_err(human));
+        Ok(SourceId::new(Kind::Directory, url))
+    }
+
     /// Returns the `SourceId` corresponding to the main repository.
     ///
     /// This is the main cargo registry by default, but it can be overridden in
@@ -175,6 +262,18 @@ impl SourceId {
         Ok(SourceId::for_registry(&try!(RegistrySource::url(config))))
     }
 
+    /// Returns the `SourceId` of the registry named `name` in the
+    /// `[registries]` table of `.cargo/config`.
+    ///
+    /// The name `crates-io` always refers to the main repository.
+    pub fn alt_registry(config: &Config, name: &str) -> CargoResult<SourceId> {
+        if name == "crates-io" {
+            return SourceId::for_central(config)
+        }
+        let url = try!(RegistrySource::alt_url(config, name));
+        Ok(SourceId::for_registry(&url))
+    }
+
     pub fn url(&self) -> &Url {
         &self.inner.url
     }
@@ -184,6 +283,12 @@ impl SourceId {
     pub fn is_registry(&self) -> bool {
         self.inner.kind == Kind::Registry
     }
+    pub fn is_local_registry(&self) -> bool {
+        self.inner.kind == Kind::LocalRegistry
+    }
+    pub fn is_directory(&self) -> bool {
+        self.inner.kind == Kind::Directory
+    }
 
     pub fn is_git(&self) -> bool {
         match self.inner.kind {
@@ -204,7 +309,21 @@ impl SourceId {
                 };
                 Box::new(PathSource::new(&path, self, config))
             }
-            Kind::Registry => Box::new(RegistrySource::new(self, config)),
+            Kind::Registry => Box::new(RegistrySource::remote(self, config)),
+            Kind::LocalRegistry => {
+                let path = match self.inner.url.to_file_path() {
+                    Ok(p) => p,
+                    Err(()) => panic!("local registry sources cannot be remote"),
+                };
+                Box::new(RegistrySource::local(self, &path, config))
+            }
+            Kind::Directory => {
+                let path = match self.inner.url.to_file_path() {
+                    Ok(p) => p,
+                    Err(()) => panic!("directory sources cannot be remote"),
+                };
+                Box::new(DirectorySource::new(&path, self, config))
+            }
         }
     }
 
@@ -228,6 +347,21 @@ impl SourceId {
         }
     }
 
+    /// Returns whether the submodules of this git source should be checked
+    /// out, which is the default.
+    pub fn submodules(&self) -> bool {
+        self.inner.submodules
+    }
+
+    pub fn with_submodules(&self, submodules: bool) -> SourceId {
+        SourceId {
+            inner: Arc::new(SourceIdInner {
+                submodules: submodules,
+                ..(*self.inner).clone()
+            })
+        }
+    }
+
     pub fn is_default_registry(&self) -> bool {
         match self.inner.kind {
             Kind::Registry => {}
@@ -291,6 +425,12 @@ impl fmt::Display for SourceId {
             SourceIdInner { kind: Kind::Registry, ref url, .. } => {
                 write!(f, "registry {}", url)
             }
+            SourceIdInner { kind: Kind::LocalRegistry, ref url, .. } => {
+                write!(f, "registry {}", url)
+            }
+            SourceIdInner { kind: Kind::Directory, ref url, .. } => {
+                write!(f, "dir {}", url)
+            }
         }
     }
 }
@@ -300,7 +440,7 @@ impl fmt::Display for SourceId {
 // to the same repository.
 impl PartialEq for SourceIdInner {
     fn eq(&self, other: &SourceIdInner) -> bool {
-        if self.kind != other.kind {
+        if self.kind != other.kind || self.submodules != other.submodules {
             return false;
         }
         if self.url == other.url {
@@ -328,6 +468,10 @@ impl Ord for SourceIdInner {
             Ordering::Equal => {}
             ord => return ord,
         }
+        match self.submodules.cmp(&other.submodules) {
+            Ordering::Equal => {}
+            ord => return ord,
+        }
         match self.url.cmp(&other.url) {
             Ordering::Equal => {}
             ord => return ord,
@@ -344,6 +488,8 @@ impl Ord for SourceIdInner {
 // The hash of SourceId is used in the name of some Cargo folders, so shouldn't
 // vary. `as_str` gives the serialisation of a url (which has a spec) and so
 // insulates against possible changes in how the url crate does hashing.
+// Sources which skip their submodules are the only ones to hash the flag, so
+// the folder names of existing sources stay the same.
 impl hash::Hash for SourceId {
     fn hash<S: hash::Hasher>(&self, into: &mut S) {
         self.inner.kind.hash(into);
@@ -353,6 +499,9 @@ impl hash::Hash for SourceId {
             }
             _ => self.inner.url.as_str().hash(into),
         }
+        if !self.inner.submodules {
+            self.inner.submodules.hash(into);
+        }
     }
 }
 
@@ -466,4 +615,43 @@ mod tests {
         let s3 = SourceId::new(foo, loc);
         assert!(s1 != s3);
     }
+
+    #[test]
+    fn git_submodules_round_trip() {
+        let loc = "https://github.com/foo/bar".to_url().unwrap();
+        let tag = GitReference::Tag("v1".to_string());
+        let s1 = SourceId::for_git(&loc, tag).with_submodules(false);
+        assert_eq!(s1.to_url(),
+                   "git+https://github.com/foo/bar?tag=v1&submodules=false");
+        let s2 = SourceId::from_url(&s1.to_url());
+        assert_eq!(s1, s2);
+        assert!(!s2.submodules());
+
+        let master = GitReference::Branch("master".to_string());
+        let s3 = SourceId::for_git(&loc, master).with_submodules(false);
+        assert_eq!(s3.to_url(),
+                   "git+https://github.com/foo/bar?submodules=false");
+        assert!(SourceId::from_url(&s3.to_url()).submodules() == false);
+        assert!(s3 != s3.with_submodules(true));
+    }
+
+    #[test]
+    fn directory_sources_round_trip() {
+        let loc = "file:///tmp/vendor".to_url().unwrap();
+        let s1 = SourceId::new(Kind::Directory, loc);
+        let s2 = SourceId::from_url(&s1.to_url());
+        assert_eq!(s1, s2);
+        assert!(s2.is_directory());
+        assert_eq!(s2.to_url(), "directory+file:///tmp/vendor");
+    }
+
+    #[test]
+    fn local_registry_sources_round_trip() {
+        let loc = "file:///tmp/registry".to_url().unwrap();
+        let s1 = SourceId::new(Kind::LocalRegistry, loc);
+        let s2 = SourceId::from_url(&s1.to_url());
+        assert_eq!(s1, s2);
+        assert!(s2.is_local_registry());
+        assert_eq!(s2.to_url(), "local-registry+file:///tmp/registry");
+    }
 }
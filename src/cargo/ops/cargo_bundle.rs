@@ -0,0 +1,365 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::prelude::*;
+use std::path::{Component, Path, PathBuf};
+
+use git2;
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json::Json;
+use tar::{Archive, Builder, Header};
+
+use core::Resolve;
+use sources::SourceConfigMap;
+use sources::git;
+use sources::registry::{self, INDEX_LOCK};
+use util::{self, CargoResult, ChainError, Config, Sha256, ToUrl};
+use util::{human, internal};
+use util::paths;
+
+/// Writes a bundle of everything in `$CARGO_HOME` needed to build `resolve`
+/// without the network to `dst`, for `cargo fetch --bundle`.
+///
+/// The bundle is a tar archive laid out just like `$CARGO_HOME` itself. For
+/// registries it contains the `.crate` tarballs and the index entries of each
+/// package, and for git repositories the whole database. Packages from local
+/// registries and directories are left out as they're already on disk.
+///
+/// All packages must already have been downloaded, see `ops::fetch`.
+pub fn bundle(resolve: &Resolve, config: &Config, dst: &Path)
+              -> CargoResult<()> {
+    let home = config.home().clone().into_path_unlocked();
+    let source_map = try!(SourceConfigMap::new(config));
+
+    // Locks on everything being archived, held until the archive is written.
+    let mut locks = Vec::new();
+    let mut locked = HashSet::new();
+    // Index files are archived first so tarballs can be verified against
+    // them as they're imported.
+    let mut index_files = BTreeSet::new();
+    let mut files = BTreeSet::new();
+    for id in resolve.iter() {
+        let source_id = id.source_id();
+        if source_id.is_path() {
+            continue
+        }
+        let source_id = try!(source_map.replacement(source_id))
+                            .unwrap_or(source_id.clone());
+
+        if source_id.is_registry() {
+            let name = registry::short_name(&source_id);
+            let index = Path::new("registry/index").join(&name);
+            if locked.insert(index.clone()) {
+                let lock = try!(config.registry_index_path().join(&name)
+                                      .open_ro(Path::new(INDEX_LOCK), config,
+                                               "the registry index"));
+                locks.push(lock);
+                index_files.insert(index.join("config.json"));
+            }
+            index_files.insert(index.join(registry::index_file_path(id.name())));
+
+            let krate = format!("{}-{}.crate", id.name(), id.version());
+            let cache = config.registry_cache_path().join(&name);
+            locks.push(try!(cache.open_ro(Path::new(&krate), config, &krate)));
+            files.insert(Path::new("registry/cache").join(&name).join(&krate));
+        } else if source_id.is_git() {
+            let ident = git::ident(source_id.url());
+            let db = Path::new("git/db").join(&ident);
+            if locked.insert(db.clone()) {
+                let lock = format!(".cargo-lock-{}", ident);
+                let lock = try!(config.git_db_path().open_rw(&lock, config,
+                                                             "the git database"));
+                locks.push(lock);
+                try!(walk(&home.join(&db), &mut |path| {
+                    let relative = util::without_prefix(path, &home).unwrap();
+                    files.insert(relative.to_path_buf());
+                }));
+            }
+        } else {
+            try!(config.shell().warn(format!("`{}` is not included in the \
+                                              bundle as it comes from the \
+                                              local filesystem", id)));
+        }
+    }
+
+    let file = try!(File::create(dst).chain_error(|| {
+        human(format!("failed to create bundle `{}`", dst.display()))
+    }));
+    let mut ar = Builder::new(file);
+    for relative in index_files.iter().chain(files.iter()) {
+        let path = home.join(relative);
+        let mut file = try!(File::open(&path).chain_error(|| {
+            human(format!("failed to open `{}`, has it been fetched?",
+                          path.display()))
+        }));
+        try!(config.shell().verbose(|shell| {
+            shell.status("Archiving", relative.display())
+        }));
+        let name = try!(relative.to_str().chain_error(|| {
+            human(format!("non-utf8 path in cargo home: {}",
+                          relative.display()))
+        }));
+        // Bundles are meant to be moved between machines, so always use `/`.
+        let name = name.replace("\\", "/");
+        let mut header = Header::new_ustar();
+        try!(header.set_path(&name).chain_error(|| {
+            human(format!("failed to add to bundle: `{}`", name))
+        }));
+        header.set_metadata(&try!(file.metadata()));
+        header.set_cksum();
+        try!(ar.append(&header, &mut file).chain_error(|| {
+            internal(format!("could not add `{}` to bundle", name))
+        }));
+    }
+    try!(ar.into_inner());
+    Ok(())
+}
+
+/// Populates `$CARGO_HOME` from a bundle created by `bundle`, for
+/// `cargo fetch --import`.
+///
+/// Nothing already in the cache is overwritten: index files are merged with
+/// any existing ones, tarballs already present are kept, and git databases
+/// which already exist fetch the branches and tags of the bundled ones.
+/// The same locks as a build are taken on everything being written to.
+pub fn import_bundle(config: &Config, src: &Path) -> CargoResult<()> {
+    let file = try!(File::open(src).chain_error(|| {
+        human(format!("failed to open bundle `{}`", src.display()))
+    }));
+    let home = config.home().clone().into_path_unlocked();
+    let mut locks = HashMap::new();
+    let mut git_dbs = HashMap::new();
+    let mut ar = Archive::new(file);
+    for entry in try!(ar.entries()) {
+        let mut entry = try!(entry);
+        let relative = try!(entry.path()).into_owned();
+        let parts = try!(bundle_path(&relative).chain_error(|| {
+            human(format!("invalid path in bundle: `{}`", relative.display()))
+        }));
+        let mut contents = Vec::new();
+        try!(entry.read_to_end(&mut contents));
+        let dst = home.join(&relative);
+
+        match (&parts[0][..], &parts[1][..]) {
+            ("registry", "index") => {
+                let index = config.registry_index_path().join(&parts[2]);
+                let key = Path::new("registry/index").join(&parts[2]);
+                if !locks.contains_key(&key) {
+                    try!(index.create_dir());
+                    let lock = try!(index.open_rw(Path::new(INDEX_LOCK),
+                                                  config,
+                                                  "the registry index"));
+                    locks.insert(key, lock);
+                }
+                try!(merge_index_file(&dst, &contents).chain_error(|| {
+                    human(format!("failed to import `{}`", dst.display()))
+                }));
+                // The index cache is keyed by the commit the index is at,
+                // which importing doesn't change, so drop the stale entry.
+                let rel = parts[3..].iter().collect::<PathBuf>();
+                let _ = fs::remove_file(home.join(&key).join(".cache")
+                                            .join(rel));
+            }
+            ("registry", "cache") => {
+                let index = home.join("registry/index").join(&parts[2]);
+                try!(verify_crate(&index, &parts[3], &contents));
+                let cache = config.registry_cache_path().join(&parts[2]);
+                let mut lock = try!(cache.open_rw(Path::new(&parts[3]), config,
+                                                  &parts[3]));
+                if try!(lock.file().metadata()).len() == 0 {
+                    try!(lock.write_all(&contents));
+                }
+            }
+            ("git", "db") => {
+                let key = Path::new("git/db").join(&parts[2]);
+                if !locks.contains_key(&key) {
+                    let lock = format!(".cargo-lock-{}", parts[2]);
+                    let lock = try!(config.git_db_path().open_rw(&lock, config,
+                                                                 "the git database"));
+                    locks.insert(key, lock);
+                }
+                // A database which already exists is unpacked next to it, to
+                // be fetched from once everything has been imported.
+                if !git_dbs.contains_key(&parts[2]) {
+                    let db = home.join("git/db").join(&parts[2]);
+                    let staging = if db.exists() {
+                        let staging = home.join("git/db").join(
+                            format!(".cargo-import-{}", parts[2]));
+                        if staging.exists() {
+                            try!(fs::remove_dir_all(&staging));
+                        }
+                        Some((db, staging))
+                    } else {
+                        None
+                    };
+                    git_dbs.insert(parts[2].clone(), staging);
+                }
+                let dst = match git_dbs[&parts[2]] {
+                    Some((_, ref staging)) => {
+                        staging.join(parts[3..].iter().collect::<PathBuf>())
+                    }
+                    None => dst,
+                };
+                if !dst.exists() {
+                    try!(fs::create_dir_all(dst.parent().unwrap()));
+                    try!(paths::write(&dst, &contents));
+                }
+            }
+            _ => bail!("invalid path in bundle: `{}`", relative.display()),
+        }
+        try!(config.shell().verbose(|shell| {
+            shell.status("Importing", relative.display())
+        }));
+    }
+
+    for (_, staging) in git_dbs {
+        if let Some((db, staging)) = staging {
+            try!(merge_git_db(config, &db, &staging).chain_error(|| {
+                human(format!("failed to import into the git database `{}`",
+                              db.display()))
+            }));
+            try!(fs::remove_dir_all(&staging));
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the branches and tags of the git database at `bundled` into the
+/// existing one at `db`.
+fn merge_git_db(config: &Config, db: &Path, bundled: &Path)
+                -> CargoResult<()> {
+    let repo = try!(git2::Repository::open(db));
+    let url = try!(bundled.to_url().map_err(human)).to_string();
+    let refspecs = ["refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*",
+                    "+refs/commit/*:refs/commit/*"];
+    git::fetch_local(&repo, &url, &refspecs, config)
+}
+
+/// Splits a path in a bundle into its components, making sure it stays
+/// within the parts of `$CARGO_HOME` a bundle may write to.
+fn bundle_path(path: &Path) -> CargoResult<Vec<String>> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(s) => {
+                let s = try!(s.to_str().chain_error(|| {
+                    human("non-utf8 path")
+                }));
+                parts.push(s.to_string());
+            }
+            _ => bail!("paths must be relative and may not contain `..`"),
+        }
+    }
+    let valid = match (parts.get(0).map(|s| &s[..]),
+                       parts.get(1).map(|s| &s[..])) {
+        (Some("registry"), Some("index")) => parts.len() > 3,
+        (Some("registry"), Some("cache")) => {
+            parts.len() == 4 && parts[3].ends_with(".crate")
+        }
+        (Some("git"), Some("db")) => parts.len() > 3,
+        _ => false,
+    };
+    let is_private = |p: &String| p.starts_with(".cargo-") || p == ".cache";
+    if !valid || parts.iter().any(is_private) {
+        bail!("path is not part of a registry or git database")
+    }
+    Ok(parts)
+}
+
+/// Adds the versions listed in the index file `contents` to the index file
+/// at `dst`, keeping any which are already there.
+fn merge_index_file(dst: &Path, contents: &[u8]) -> CargoResult<()> {
+    let contents = try!(String::from_utf8(contents.to_vec()).map_err(|_| {
+        human("index file is not utf-8")
+    }));
+    let mut existing = if dst.exists() {
+        try!(paths::read(dst))
+    } else {
+        try!(fs::create_dir_all(dst.parent().unwrap()));
+        String::new()
+    };
+    // `config.json` and the like aren't lists of versions.
+    if !existing.is_empty() && !is_version_list(dst) {
+        return Ok(())
+    }
+    let versions = existing.lines().filter_map(version).collect::<Vec<_>>();
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        match version(line) {
+            Some(ref v) if versions.contains(v) => continue,
+            _ => {}
+        }
+        if !existing.is_empty() && !existing.ends_with('\n') {
+            existing.push('\n');
+        }
+        existing.push_str(line);
+        existing.push('\n');
+    }
+
+    // Write through a file of our own so an interrupted import never leaves
+    // a truncated index file behind.
+    let tmp = dst.with_extension("cargo-import");
+    {
+        let mut f = try!(OpenOptions::new().write(true).create(true)
+                                           .truncate(true).open(&tmp));
+        try!(f.write_all(existing.as_bytes()));
+    }
+    try!(fs::rename(&tmp, dst));
+    Ok(())
+}
+
+/// Checks the tarball `filename` against the checksum listed in the index
+/// rooted at `index`.
+fn verify_crate(index: &Path, filename: &str, contents: &[u8])
+                -> CargoResult<()> {
+    let stem = &filename[..filename.len() - ".crate".len()];
+    // Both names and versions may contain `-`, so try every split.
+    for (i, _) in stem.match_indices('-') {
+        let (name, vers) = (&stem[..i], &stem[i + 1..]);
+        let path = index.join(registry::index_file_path(name));
+        let file = match paths::read(&path) {
+            Ok(file) => file,
+            Err(..) => continue,
+        };
+        for line in file.lines() {
+            let json = match Json::from_str(line) {
+                Ok(json) => json,
+                Err(..) => continue,
+            };
+            if field(&json, "name") != Some(name) ||
+               field(&json, "vers") != Some(vers) {
+                continue
+            }
+            let mut state = Sha256::new();
+            state.update(contents);
+            if Some(&state.finish().to_hex()[..]) != field(&json, "cksum") {
+                bail!("failed to verify the checksum of `{}`", filename)
+            }
+            return Ok(())
+        }
+    }
+    bail!("`{}` is not listed in the registry index of the bundle", filename)
+}
+
+fn is_version_list(path: &Path) -> bool {
+    path.file_name().map(|n| n != "config.json").unwrap_or(false)
+}
+
+fn version(line: &str) -> Option<String> {
+    Json::from_str(line).ok().and_then(|json| {
+        field(&json, "vers").map(|s| s.to_string())
+    })
+}
+
+fn field<'a>(json: &'a Json, key: &str) -> Option<&'a str> {
+    json.find(key).and_then(|v| v.as_string())
+}
+
+fn walk(path: &Path, f: &mut FnMut(&Path)) -> CargoResult<()> {
+    if !path.is_dir() {
+        f(path);
+        return Ok(())
+    }
+    for entry in try!(fs::read_dir(path)) {
+        try!(walk(&try!(entry).path(), f));
+    }
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use term::color::BLACK;
+
+use sources::registry::SRC_LOCK;
+use util::{CargoResult, ChainError, Config, Filesystem, human, last_use};
+
+pub struct CacheOptions<'a> {
+    pub config: &'a Config,
+    /// Remove unpacked sources and checkouts which haven't been used in this
+    /// many days.
+    pub remove_unused: Option<u32>,
+}
+
+/// Executes `cargo cache`.
+///
+/// If requested, unpacked registry sources and git checkouts which no build
+/// has used recently are removed first. Afterwards the disk space used by
+/// each registry and git repository in `$CARGO_HOME` is printed.
+pub fn cache(opts: &CacheOptions) -> CargoResult<()> {
+    let config = opts.config;
+
+    if let Some(days) = opts.remove_unused {
+        let now = last_use::now();
+        let cutoff = now.saturating_sub(days as u64 * 24 * 60 * 60);
+        try!(remove_registry_sources(config, cutoff));
+        try!(remove_git_checkouts(config, cutoff));
+    }
+
+    let mut usage = BTreeMap::new();
+    for (kind, root) in vec![("registry", config.registry_index_path()),
+                             ("registry", config.registry_cache_path()),
+                             ("registry", config.registry_source_path()),
+                             ("git", config.git_db_path()),
+                             ("git", config.git_checkout_path())] {
+        for (name, path) in try!(subdirectories(&root)) {
+            let size = try!(disk_usage(&path).chain_error(|| {
+                human(format!("failed to read `{}`", path.display()))
+            }));
+            *usage.entry((kind, name)).or_insert(0) += size;
+        }
+    }
+
+    let mut total = 0;
+    for (&(kind, ref name), &size) in usage.iter() {
+        try!(config.shell().say(format!("{:>10} {}/{}", format_size(size),
+                                         kind, name), BLACK));
+        total += size;
+    }
+    try!(config.shell().say(format!("{:>10} total", format_size(total)),
+                            BLACK));
+    Ok(())
+}
+
+/// Removes the unpacked sources of registry crates last used before
+/// `cutoff`.
+///
+/// Builds hold a shared lock on the sources of each registry they use, so
+/// taking the lock exclusively here waits for them to finish.
+fn remove_registry_sources(config: &Config, cutoff: u64) -> CargoResult<()> {
+    let root = config.registry_source_path();
+    for (name, _) in try!(subdirectories(&root)) {
+        let lock = try!(root.join(&name).open_rw(Path::new(SRC_LOCK), config,
+                                                 "the registry sources"));
+        for (_, path) in try!(subdirectories_of(lock.parent())) {
+            try!(remove_if_unused(config, &path, cutoff));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the git checkouts last used before `cutoff`.
+///
+/// Each checkout is locked exclusively by the build using it, so this takes
+/// the same lock before removing it.
+fn remove_git_checkouts(config: &Config, cutoff: u64) -> CargoResult<()> {
+    let root = config.git_checkout_path();
+    for (ident, _) in try!(subdirectories(&root)) {
+        let dir = root.join(&ident);
+        for (reference, _) in try!(subdirectories(&dir)) {
+            let lock = format!(".cargo-lock-{}-{}", ident, reference);
+            let lock = try!(dir.open_rw(&lock, config, "the git checkout"));
+            let path = lock.parent().join(&reference);
+            try!(remove_if_unused(config, &path, cutoff));
+        }
+    }
+    Ok(())
+}
+
+fn remove_if_unused(config: &Config, path: &Path, cutoff: u64)
+                    -> CargoResult<()> {
+    match last_use::get(path) {
+        Some(time) if time > cutoff => return Ok(()),
+        _ => {}
+    }
+    try!(config.shell().status("Removing", path.display()));
+    fs::remove_dir_all(path).chain_error(|| {
+        human(format!("failed to remove `{}`", path.display()))
+    })
+}
+
+/// Returns the name and path of each directory in `root`, skipping the
+/// dot-directories Cargo uses for bookkeeping.
+fn subdirectories(root: &Filesystem) -> CargoResult<Vec<(String, PathBuf)>> {
+    // Only the names are looked at here; anything inside is read or removed
+    // under the appropriate lock.
+    subdirectories_of(&root.clone().into_path_unlocked())
+}
+
+fn subdirectories_of(root: &Path) -> CargoResult<Vec<(String, PathBuf)>> {
+    let mut ret = Vec::new();
+    if !root.is_dir() {
+        return Ok(ret)
+    }
+    for entry in try!(fs::read_dir(root)) {
+        let entry = try!(entry);
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') || !try!(entry.file_type()).is_dir() {
+            continue
+        }
+        ret.push((name, entry.path()));
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+fn disk_usage(path: &Path) -> CargoResult<u64> {
+    let meta = try!(fs::symlink_metadata(path));
+    if !meta.is_dir() {
+        return Ok(meta.len())
+    }
+    let mut size = 0;
+    for entry in try!(fs::read_dir(path)) {
+        size += try!(disk_usage(&try!(entry).path()));
+    }
+    Ok(size)
+}
+
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, units[0])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
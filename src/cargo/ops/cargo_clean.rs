@@ -2,7 +2,7 @@ use std::default::Default;
 use std::fs;
 use std::path::Path;
 
-use core::{Package, Profiles};
+use core::{Package, PackageSet, Profiles, Resolve};
 use util::{CargoResult, human, ChainError, Config};
 use ops::{self, Layout, Context, BuildConfig, Kind, Unit};
 
@@ -29,20 +29,47 @@ pub fn clean(manifest_path: &Path, opts: &CleanOptions) -> CargoResult<()> {
     }
 
     let (resolve, packages) = try!(ops::fetch(manifest_path, opts.config));
+    let profiles = try!(root.manifest().profiles());
 
     let dest = if opts.release {"release"} else {"debug"};
-    let host_layout = try!(Layout::new(opts.config, &root, None, dest));
+    try!(clean_profile(opts, &root, &resolve, &packages, Path::new(dest),
+                       profiles));
+
+    // Custom profiles each have a directory of their own, which is only
+    // cleaned if they've been used.
+    let target_dir = target_dir.into_path_unlocked();
+    for (name, custom) in profiles.custom.iter() {
+        let dest = Path::new("profiles").join(name);
+        if !target_dir.join(&dest).exists() {
+            continue
+        }
+        try!(clean_profile(opts, &root, &resolve, &packages, &dest,
+                           &profiles.for_custom(custom)));
+    }
+
+    Ok(())
+}
+
+/// Removes the output of the packages in `opts.spec` from the directory of
+/// the profile `dest`, compiled with `profiles`.
+fn clean_profile<'cfg>(opts: &CleanOptions<'cfg>,
+                       root: &Package,
+                       resolve: &Resolve,
+                       packages: &PackageSet<'cfg>,
+                       dest: &Path,
+                       profiles: &Profiles) -> CargoResult<()> {
+    let host_layout = try!(Layout::new(opts.config, root, None, dest));
     let target_layout = match opts.target {
         Some(target) => {
-            Some(try!(Layout::new(opts.config, &root, Some(target), dest)))
+            Some(try!(Layout::new(opts.config, root, Some(target), dest)))
         }
         None => None,
     };
 
-    let cx = try!(Context::new(&resolve, &packages, opts.config,
+    let cx = try!(Context::new(resolve, packages, opts.config,
                                host_layout, target_layout,
                                BuildConfig::default(),
-                               root.manifest().profiles()));
+                               profiles));
 
     // resolve package specs and remove the corresponding packages
     for spec in opts.spec {
@@ -56,12 +83,14 @@ pub fn clean(manifest_path: &Path, opts: &CleanOptions) -> CargoResult<()> {
                 let layout = cx.layout(&pkg, *kind);
                 try!(rm_rf(&layout.proxy().fingerprint(&pkg)));
                 try!(rm_rf(&layout.build(&pkg)));
+                try!(rm_rf(&layout.proxy().incremental(&pkg)));
                 let Profiles {
                     ref release, ref dev, ref test, ref bench, ref doc,
                     ref custom_build, ref test_deps, ref bench_deps,
-                } = *root.manifest().profiles();
+                    ref check, ..
+                } = *profiles;
                 let profiles = [release, dev, test, bench, doc, custom_build,
-                                test_deps, bench_deps];
+                                test_deps, bench_deps, check];
                 for profile in profiles.iter() {
                     let unit = Unit {
                         pkg: &pkg,
@@ -56,8 +56,18 @@ pub struct CompileOptions<'a> {
     pub exec_engine: Option<Arc<Box<ExecEngine>>>,
     /// Whether this is a release build or not
     pub release: bool,
+    /// Name of the profile to compile with, if not `dev` or `release`
+    pub profile: Option<&'a str>,
     /// Mode for this compile.
     pub mode: CompileMode,
+    /// How messages about the compile are printed
+    pub message_format: MessageFormat,
+    /// Whether to print the commands of the build instead of running them
+    pub build_plan: bool,
+    /// Whether to explain why each unit is rebuilt
+    pub explain_rebuilds: bool,
+    /// Whether to only explain what would be rebuilt, without building it
+    pub dry_run: bool,
     /// Extra arguments to be passed to rustdoc (for main crate and dependencies)
     pub target_rustdoc_args: Option<&'a [String]>,
     /// The specified target will be compiled with all the available arguments,
@@ -69,10 +79,17 @@ pub struct CompileOptions<'a> {
 pub enum CompileMode {
     Test,
     Build,
+    Check,
     Bench,
     Doc { deps: bool },
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, RustcDecodable)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
 pub enum CompileFilter<'a> {
     Everything,
     Only {
@@ -105,7 +122,7 @@ pub fn resolve_dependencies<'a>(root_package: &Package,
                                 no_default_features: bool)
                                 -> CargoResult<(PackageSet<'a>, Resolve)> {
 
-    let mut registry = PackageRegistry::new(config);
+    let mut registry = try!(PackageRegistry::new(config));
 
     if let Some(source) = source {
         registry.add_preloaded(root_package.package_id().source_id(), source);
@@ -143,7 +160,9 @@ pub fn compile_pkg<'a>(root_package: &Package,
                        options: &CompileOptions<'a>)
                        -> CargoResult<ops::Compilation<'a>> {
     let CompileOptions { config, jobs, target, spec, features,
-                         no_default_features, release, mode,
+                         no_default_features, release, mode, message_format,
+                         build_plan, explain_rebuilds, dry_run,
+                         profile: profile_name,
                          ref filter, ref exec_engine,
                          ref target_rustdoc_args,
                          ref target_rustc_args } = *options;
@@ -157,7 +176,31 @@ pub fn compile_pkg<'a>(root_package: &Package,
         bail!("jobs must be at least 1")
     }
 
-    let profiles = root_package.manifest().profiles();
+    // A custom profile takes the place of both `dev` and `release`, and is
+    // compiled in release mode if it inherits from `release`
+    let custom_profiles;
+    let (profiles, release) = match profile_name {
+        None => (try!(root_package.manifest().profiles()), release),
+        Some(name) => {
+            if release {
+                bail!("`--release` and `--profile` can't be used together")
+            }
+            let profiles = try!(root_package.manifest().profiles());
+            match name {
+                "dev" => (profiles, false),
+                "release" => (profiles, true),
+                _ => {
+                    let custom = try!(profiles.custom.get(name).chain_error(|| {
+                        human(format!("profile `{}` is not defined in `{}`",
+                                      name,
+                                      root_package.manifest_path().display()))
+                    }));
+                    custom_profiles = profiles.for_custom(custom);
+                    (&custom_profiles, custom.release)
+                }
+            }
+        }
+    };
     if spec.len() == 0 {
         try!(generate_targets(root_package, profiles, mode, filter, release));
     }
@@ -236,7 +279,15 @@ pub fn compile_pkg<'a>(root_package: &Package,
         let mut build_config = try!(scrape_build_config(config, jobs, target));
         build_config.exec_engine = exec_engine.clone();
         build_config.release = release;
+        build_config.profile = match profile_name {
+            Some("dev") | Some("release") | None => None,
+            Some(name) => Some(name.to_string()),
+        };
         build_config.test = mode == CompileMode::Test;
+        build_config.json_messages = message_format == MessageFormat::Json;
+        build_config.build_plan = build_plan;
+        build_config.explain_rebuilds = explain_rebuilds || dry_run;
+        build_config.dry_run = dry_run;
         if let CompileMode::Doc { deps } = mode {
             build_config.doc_all = deps;
         }
@@ -246,10 +297,23 @@ pub fn compile_pkg<'a>(root_package: &Package,
                                   &resolve_with_overrides,
                                   config,
                                   build_config,
-                                  root_package.manifest().profiles(),
-                                  ))
+                                  profiles))
     };
 
+    // Only the profiles of the root package are used, so invalid ones in the
+    // packages built along with it don't stop the build.
+    for pkg in packages.loaded() {
+        if pkg.package_id() == root_package.package_id() {
+            continue
+        }
+        if let Some(e) = pkg.manifest().profile_error() {
+            try!(config.shell().warn(format!("ignoring the invalid profiles \
+                                              of `{}`, only those of the \
+                                              root package are used: {}",
+                                             pkg.package_id(), e)));
+        }
+    }
+
     ret.to_doc_test = to_builds.iter().map(|&p| p.clone()).collect();
 
     Ok(ret)
@@ -298,12 +362,18 @@ fn generate_targets<'a>(pkg: &'a Package,
                         filter: &CompileFilter,
                         release: bool)
                         -> CargoResult<Vec<(&'a Target, &'a Profile)>> {
-    let build = if release {&profiles.release} else {&profiles.dev};
+    let profiles = profiles.for_package(pkg.package_id());
+    let build = match mode {
+        CompileMode::Check => &profiles.check,
+        _ if release => &profiles.release,
+        _ => &profiles.dev,
+    };
     let test = if release {&profiles.bench} else {&profiles.test};
     let profile = match mode {
         CompileMode::Test => test,
         CompileMode::Bench => &profiles.bench,
-        CompileMode::Build => build,
+        CompileMode::Build |
+        CompileMode::Check => build,
         CompileMode::Doc { .. } => &profiles.doc,
     };
     match *filter {
@@ -330,7 +400,7 @@ fn generate_targets<'a>(pkg: &'a Package,
                     }
                     Ok(base)
                 }
-                CompileMode::Build => {
+                CompileMode::Build | CompileMode::Check => {
                     Ok(pkg.targets().iter().filter(|t| {
                         t.is_bin() || t.is_lib()
                     }).map(|t| (t, profile)).collect())
@@ -449,9 +519,19 @@ fn scrape_build_config(config: &Config,
     let jobs = jobs.or(cfg_jobs).unwrap_or(::num_cpus::get() as u32);
     let cfg_target = try!(config.get_string("build.target")).map(|s| s.val);
     let target = target.or(cfg_target);
+    let content_hash = match try!(config.get_string("build.freshness")) {
+        Some(ref v) if v.val == "content-hash" => true,
+        Some(ref v) if v.val == "mtime" => false,
+        Some(v) => {
+            bail!("build.freshness must be `mtime` or `content-hash`, but \
+                   found `{}` in {}", v.val, v.definition)
+        }
+        None => false,
+    };
     let mut base = ops::BuildConfig {
         jobs: jobs,
         requested_target: target.clone(),
+        content_hash: content_hash,
         ..Default::default()
     };
     base.host = try!(scrape_target_config(config, &config.rustc_info().host));
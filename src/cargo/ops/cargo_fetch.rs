@@ -10,12 +10,10 @@ pub fn fetch<'a>(manifest_path: &Path,
                  config: &'a Config)
                  -> CargoResult<(Resolve, PackageSet<'a>)> {
     let package = try!(Package::for_path(manifest_path, config));
-    let mut registry = PackageRegistry::new(config);
+    let mut registry = try!(PackageRegistry::new(config));
     let resolve = try!(ops::resolve_pkg(&mut registry, &package, config));
     let packages = get_resolved_packages(&resolve, registry);
-    for id in resolve.iter() {
-        try!(packages.get(id));
-    }
+    try!(packages.get_many(resolve.iter()));
     Ok((resolve, packages))
 }
 
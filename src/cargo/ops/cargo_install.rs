@@ -13,7 +13,7 @@ use toml;
 use core::{SourceId, Source, Package, Dependency, PackageIdSpec};
 use core::PackageId;
 use ops::{self, CompileFilter};
-use sources::{GitSource, PathSource, RegistrySource};
+use sources::{GitSource, PathSource, SourceConfigMap};
 use util::{CargoResult, ChainError, Config, human, internal};
 use util::{Filesystem, FileLock};
 
@@ -70,7 +70,8 @@ pub fn install(root: Option<&str>,
                         source_id, krate, vers,
                         &mut |path| path.read_packages()))
     } else {
-        try!(select_pkg(RegistrySource::new(source_id, config),
+        let map = try!(SourceConfigMap::new(config));
+        try!(select_pkg(try!(map.load(source_id)),
                         source_id, krate, vers,
                         &mut |_| Err(human("must specify a crate to install from \
                                             crates.io, or use --path or --git to \
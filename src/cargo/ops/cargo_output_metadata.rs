@@ -49,9 +49,8 @@ fn metadata_full(opt: OutputMetadataOptions, config: &Config) -> CargoResult<Exp
                                          opt.no_default_features));
     let (packages, resolve) = deps;
 
-    let packages = try!(packages.package_ids()
-                                .map(|i| packages.get(i).map(|p| p.clone()))
-                                .collect());
+    let packages = try!(packages.get_many(packages.package_ids()))
+                       .into_iter().cloned().collect();
 
     Ok(ExportInfo {
         packages: packages,
@@ -220,7 +220,12 @@ fn run_verify(config: &Config,
         filter: ops::CompileFilter::Everything,
         exec_engine: None,
         release: false,
+        profile: None,
         mode: ops::CompileMode::Build,
+        message_format: ops::MessageFormat::Human,
+        build_plan: false,
+        explain_rebuilds: false,
+        dry_run: false,
         target_rustdoc_args: None,
         target_rustc_args: None,
     }));
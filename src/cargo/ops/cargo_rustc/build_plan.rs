@@ -0,0 +1,160 @@
+//! Support for `cargo build --build-plan`, which prints every unit of a build
+//! along with the command that would build it, instead of running anything.
+//!
+//! The plan is a single JSON object:
+//!
+//! ```ignore
+//! {
+//!     "invocations": [{
+//!         "package_name": "foo",
+//!         "package_version": "0.1.0",
+//!         "target_kind": ["lib"],
+//!         "kind": "host",
+//!         "deps": [0, 1],
+//!         "outputs": ["/path/to/target/debug/deps/libfoo-xxx.rlib"],
+//!         "links": {},
+//!         "program": "rustc",
+//!         "args": ["src/lib.rs", "--crate-name", "foo", ...],
+//!         "env": {"CARGO_PKG_NAME": "foo", ...},
+//!         "cwd": "/path/to/foo"
+//!     }, ...],
+//!     "inputs": ["/path/to/foo/Cargo.toml", ...]
+//! }
+//! ```
+//!
+//! `deps` are indices of the invocations which have to run first, and always
+//! refer to earlier invocations. `outputs` are the files written by the
+//! command, and `links` maps the paths Cargo would move some of them to
+//! afterwards onto the output they come from. `inputs` are the manifests the
+//! plan was derived from.
+//!
+//! Flags which are only discovered by running build scripts, like `-l` and
+//! `-L` for native libraries, are not part of the plan.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use rustc_serialize::json;
+
+use core::TargetKind;
+use util::CargoResult;
+
+use super::{Context, Unit, Kind, custom_build};
+
+#[derive(RustcEncodable)]
+struct Invocation {
+    package_name: String,
+    package_version: String,
+    target_kind: TargetKind,
+    kind: &'static str,
+    deps: Vec<usize>,
+    outputs: Vec<String>,
+    links: BTreeMap<String, String>,
+    program: String,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    cwd: Option<String>,
+}
+
+#[derive(RustcEncodable)]
+struct BuildPlan {
+    invocations: Vec<Invocation>,
+    inputs: BTreeSet<String>,
+}
+
+pub fn print<'a, 'cfg>(cx: &Context<'a, 'cfg>, units: &[Unit<'a>])
+                       -> CargoResult<()> {
+    let mut plan = BuildPlan {
+        invocations: Vec::new(),
+        inputs: BTreeSet::new(),
+    };
+    let mut indices = HashMap::new();
+    for unit in units {
+        try!(add(cx, unit, &mut plan, &mut indices));
+    }
+    println!("{}", json::encode(&plan).unwrap());
+    Ok(())
+}
+
+// Adds `unit` to the plan after all of its dependencies, returning its index.
+// `None` is returned for build scripts which are overridden, as there's
+// nothing to run for them.
+fn add<'a, 'cfg>(cx: &Context<'a, 'cfg>,
+                 unit: &Unit<'a>,
+                 plan: &mut BuildPlan,
+                 indices: &mut HashMap<Unit<'a>, Option<usize>>)
+                 -> CargoResult<Option<usize>> {
+    if let Some(&index) = indices.get(unit) {
+        return Ok(index)
+    }
+
+    let mut deps = Vec::new();
+    for dep in try!(cx.dep_targets(unit)).iter() {
+        if let Some(index) = try!(add(cx, dep, plan, indices)) {
+            deps.push(index);
+        }
+    }
+    deps.sort();
+    deps.dedup();
+
+    let mut outputs = Vec::new();
+    let mut links = BTreeMap::new();
+    let cmd = if unit.profile.run_custom_build {
+        if cx.build_state.has_override(unit) {
+            indices.insert(*unit, None);
+            return Ok(None)
+        }
+        try!(custom_build::command(cx, unit))
+    } else if unit.profile.doc {
+        bail!("documenting `{}` can't be part of a build plan", unit.pkg)
+    } else {
+        let crate_types = unit.target.rustc_crate_types();
+        let mut cmd = try!(super::prepare_rustc(cx, crate_types, unit));
+        cmd.args(&try!(cx.rustflags_args(unit)));
+
+        // Binaries are written under their crate name by rustc, and renamed
+        // by Cargo once it's done.
+        let real_name = unit.target.name();
+        let crate_name = unit.target.crate_name();
+        let renamed = unit.target.allows_underscores() &&
+                      !unit.profile.test && !unit.profile.check &&
+                      real_name != crate_name;
+        let out_dir = cx.out_dir(unit);
+        for (i, filename) in try!(cx.target_filenames(unit)).iter().enumerate() {
+            let dst = out_dir.join(filename).display().to_string();
+            if renamed && i == 0 {
+                let src = out_dir.join(filename.replace(real_name, &crate_name));
+                let src = src.display().to_string();
+                outputs.push(src.clone());
+                links.insert(dst, src);
+            } else {
+                outputs.push(dst);
+            }
+        }
+        cmd
+    };
+
+    plan.inputs.insert(unit.pkg.manifest_path().display().to_string());
+    plan.invocations.push(Invocation {
+        package_name: unit.pkg.name().to_string(),
+        package_version: unit.pkg.version().to_string(),
+        target_kind: unit.target.kind().clone(),
+        kind: match unit.kind {
+            Kind::Host => "host",
+            Kind::Target => "target",
+        },
+        deps: deps,
+        outputs: outputs,
+        links: links,
+        program: cmd.get_program().to_string_lossy().into_owned(),
+        args: cmd.get_args().iter().map(|a| {
+            a.to_string_lossy().into_owned()
+        }).collect(),
+        env: cmd.get_envs().iter().filter_map(|(k, v)| {
+            v.as_ref().map(|v| (k.clone(), v.to_string_lossy().into_owned()))
+        }).collect(),
+        cwd: cmd.get_cwd().map(|p| p.display().to_string()),
+    });
+    let index = plan.invocations.len() - 1;
+    indices.insert(*unit, Some(index));
+    Ok(Some(index))
+}
@@ -36,6 +36,8 @@ pub struct Context<'a, 'cfg: 'a> {
     pub build_explicit_deps: HashMap<Unit<'a>, (PathBuf, Vec<String>)>,
     pub exec_engine: Arc<Box<ExecEngine>>,
     pub fingerprints: HashMap<Unit<'a>, Arc<Fingerprint>>,
+    /// Why each unit that was built before has to be rebuilt
+    pub rebuild_reasons: HashMap<Unit<'a>, String>,
     pub compiled: HashSet<Unit<'a>>,
     pub build_config: BuildConfig,
     pub build_scripts: HashMap<Unit<'a>, Arc<BuildScripts>>,
@@ -47,6 +49,9 @@ pub struct Context<'a, 'cfg: 'a> {
     target_info: TargetInfo,
     host_info: TargetInfo,
     profiles: &'a Profiles,
+    /// Whether `rustc` accepts `-C strip`, probed the first time a profile
+    /// asks for it
+    strip_supported: Option<bool>,
 }
 
 #[derive(Clone)]
@@ -93,11 +98,13 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             build_config: build_config,
             exec_engine: engine,
             fingerprints: HashMap::new(),
+            rebuild_reasons: HashMap::new(),
             profiles: profiles,
             compiled: HashSet::new(),
             build_scripts: HashMap::new(),
             build_explicit_deps: HashMap::new(),
             links: Links::new(),
+            strip_supported: None,
         })
     }
 
@@ -179,21 +186,59 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         })
     }
 
+    /// Ensure that `rustc` can strip the binaries of `unit` if its profile
+    /// asks for it, rather than letting every compilation fail on an unknown
+    /// codegen option.
+    pub fn check_strip(&mut self, unit: &Unit) -> CargoResult<()> {
+        let strip = match unit.profile.strip {
+            Some(ref strip) => strip,
+            None => return Ok(()),
+        };
+        let supported = match self.strip_supported {
+            Some(supported) => supported,
+            None => {
+                let mut process = util::process(self.config.rustc());
+                process.arg("-")
+                       .arg("--crate-name").arg("_")
+                       .arg("--print=file-names")
+                       .arg("-C").arg(format!("strip={}", strip))
+                       .env_remove("RUST_LOG");
+                let supported = process.exec_with_output().is_ok();
+                self.strip_supported = Some(supported);
+                supported
+            }
+        };
+        if supported {
+            return Ok(())
+        }
+        let version = self.config.rustc_info().verbose_version.lines()
+                          .next().unwrap_or("").to_string();
+        bail!("the profile of `{}` sets `strip = \"{}\"`, but `{}` doesn't \
+               support `-C strip`\n\
+               compiler: {}\n\
+               remove `strip` from the profile or use a newer compiler",
+              unit.pkg, strip, self.config.rustc().display(), version)
+    }
+
     /// Prepare this context, ensuring that all filesystem directories are in
     /// place.
     pub fn prepare(&mut self, root: &Package) -> CargoResult<()> {
         let _p = profile::start("preparing layout");
 
-        try!(self.host.prepare().chain_error(|| {
-            internal(format!("couldn't prepare build directories"))
-        }));
-        match self.target {
-            Some(ref mut target) => {
-                try!(target.prepare().chain_error(|| {
-                    internal(format!("couldn't prepare build directories"))
-                }));
+        // A dry run only looks at what's already been built, so it has no use
+        // for the build directories.
+        if !self.build_config.dry_run {
+            try!(self.host.prepare().chain_error(|| {
+                internal(format!("couldn't prepare build directories"))
+            }));
+            match self.target {
+                Some(ref mut target) => {
+                    try!(target.prepare().chain_error(|| {
+                        internal(format!("couldn't prepare build directories"))
+                    }));
+                }
+                None => {}
             }
-            None => {}
         }
 
         self.compilation.root_output =
@@ -204,6 +249,54 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         Ok(())
     }
 
+    /// Downloads every package which the build of `units` may need, running
+    /// the transfers concurrently.
+    ///
+    /// Which dependencies of a package are needed isn't known until its
+    /// manifest is available, so this works through the dependency graph one
+    /// level at a time. Packages are otherwise downloaded one by one as the
+    /// graph of units is walked.
+    pub fn download_deps(&self, units: &[Unit<'a>]) -> CargoResult<()> {
+        let mut visited = HashSet::new();
+        let mut frontier = Vec::new();
+        for unit in units {
+            if visited.insert(unit.pkg.package_id()) {
+                frontier.push(unit.pkg);
+            }
+        }
+
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for pkg in frontier {
+                // Dev-dependencies are only needed by the tests and examples
+                // of the packages being built.
+                let dev = units.iter().any(|u| {
+                    u.pkg.package_id() == pkg.package_id() &&
+                        (u.target.is_test() || u.target.is_example() ||
+                         u.profile.test)
+                });
+                for id in self.resolve.deps(pkg.package_id()) {
+                    if visited.contains(id) {
+                        continue
+                    }
+                    let used = pkg.dependencies().iter().filter(|d| {
+                        d.name() == id.name()
+                    }).any(|d| {
+                        (d.is_transitive() || dev) &&
+                            (self.dep_platform_activated(d, Kind::Host) ||
+                             self.dep_platform_activated(d, Kind::Target))
+                    });
+                    if used {
+                        visited.insert(id);
+                        next.push(id);
+                    }
+                }
+            }
+            frontier = try!(self.packages.get_many(next));
+        }
+        Ok(())
+    }
+
     /// Returns the appropriate directory layout for either a plugin or not.
     pub fn layout(&self, pkg: &Package, kind: Kind) -> LayoutProxy {
         let primary = pkg.package_id() == self.resolve.root();
@@ -220,6 +313,8 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
     pub fn out_dir(&self, unit: &Unit) -> PathBuf {
         if unit.profile.doc {
             self.layout(unit.pkg, unit.kind).doc_root()
+        } else if unit.profile.check {
+            self.layout(unit.pkg, unit.kind).check().to_path_buf()
         } else {
             self.layout(unit.pkg, unit.kind).out_dir(unit.pkg, unit.target)
         }
@@ -265,7 +360,26 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
     /// Get the metadata for a target in a specific profile
     pub fn target_metadata(&self, unit: &Unit) -> Option<Metadata> {
         let metadata = unit.target.metadata();
-        if unit.target.is_lib() && unit.profile.test {
+        if unit.profile.check {
+            // Everything `cargo check` emits lands in one directory, so bins
+            // and libs of the same name (and the root package) need metadata
+            // to tell them apart.
+            let mut metadata = metadata.cloned().unwrap_or_else(|| {
+                unit.pkg.generate_metadata()
+            });
+            metadata.mix(&format!("check-{}", unit.target.name()));
+            metadata.mix(unit.target.kind());
+            Some(metadata)
+        } else if unit.profile.build_override &&
+                  !unit.target.is_custom_build() {
+            // Libraries compiled for the host with a `build-override` may
+            // also be compiled with the normal profile for the same platform.
+            let mut metadata = metadata.cloned().unwrap_or_else(|| {
+                unit.pkg.generate_metadata()
+            });
+            metadata.mix(&"build-override");
+            Some(metadata)
+        } else if unit.target.is_lib() && unit.profile.test {
             // Libs and their tests are built in parallel, so we need to make
             // sure that their metadata is different.
             metadata.cloned().map(|mut m| {
@@ -314,6 +428,11 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         };
 
         let mut ret = Vec::new();
+        if unit.profile.check {
+            // Only metadata is emitted, whatever the kind of target
+            ret.push(format!("lib{}.rmeta", stem));
+            return Ok(ret)
+        }
         match *unit.target.kind() {
             TargetKind::Example |
             TargetKind::Bin |
@@ -412,7 +531,7 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                         Ok(Unit {
                             pkg: pkg,
                             target: t,
-                            profile: self.lib_profile(id),
+                            profile: self.lib_or_check_profile(unit, pkg, t),
                             kind: unit.kind.for_target(t),
                         })
                     })
@@ -529,7 +648,7 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
                 ret.push(Unit {
                     pkg: dep,
                     target: lib,
-                    profile: &self.profiles.doc,
+                    profile: &self.profiles.for_package(dep.package_id()).doc,
                     kind: unit.kind.for_target(lib),
                 });
             }
@@ -568,7 +687,7 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
             Unit {
                 pkg: unit.pkg,
                 target: t,
-                profile: self.lib_profile(unit.pkg.package_id()),
+                profile: self.lib_or_check_profile(unit, unit.pkg, t),
                 kind: unit.kind.for_target(t),
             }
         })
@@ -619,11 +738,26 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         self.build_config.requested_target.as_ref().map(|s| &s[..])
     }
 
-    pub fn lib_profile(&self, _pkg: &PackageId) -> &'a Profile {
+    pub fn lib_profile(&self, pkg: &PackageId) -> &'a Profile {
+        self.lib_profile_in(self.profiles.for_package(pkg))
+    }
+
+    /// Returns the profile for the package `pkg` when it's compiled for the
+    /// host as a build script, a plugin or a dependency of either, which is
+    /// its library profile unless a `build-override` applies.
+    pub fn host_profile(&self, pkg: &PackageId) -> &'a Profile {
+        let profiles = self.profiles.for_package(pkg);
+        match profiles.build_override {
+            Some(ref host) => self.lib_profile_in(host),
+            None => self.lib_profile_in(profiles),
+        }
+    }
+
+    fn lib_profile_in(&self, profiles: &'a Profiles) -> &'a Profile {
         let (normal, test) = if self.build_config.release {
-            (&self.profiles.release, &self.profiles.bench_deps)
+            (&profiles.release, &profiles.bench_deps)
         } else {
-            (&self.profiles.dev, &self.profiles.test_deps)
+            (&profiles.dev, &profiles.test_deps)
         };
         if self.build_config.test {
             test
@@ -632,10 +766,27 @@ impl<'a, 'cfg> Context<'a, 'cfg> {
         }
     }
 
+    /// Returns the profile for the library `target` of `pkg` that `unit`
+    /// depends on.
+    ///
+    /// Plugins, and everything build scripts and plugins depend on, are
+    /// compiled for the host. Dependencies of a unit being checked are only
+    /// checked themselves, except for plugins which have to be built to be
+    /// loaded by the compiler.
+    pub fn lib_or_check_profile(&self, unit: &Unit, pkg: &Package,
+                                target: &Target) -> &'a Profile {
+        if target.for_host() || unit.target.for_host() ||
+           unit.profile.build_override {
+            self.host_profile(pkg.package_id())
+        } else if unit.profile.check {
+            &self.profiles.for_package(pkg.package_id()).check
+        } else {
+            self.lib_profile(pkg.package_id())
+        }
+    }
+
     pub fn build_script_profile(&self, pkg: &PackageId) -> &'a Profile {
-        // TODO: should build scripts always be built with the same library
-        //       profile? How is this controlled at the CLI layer?
-        self.lib_profile(pkg)
+        self.host_profile(pkg)
     }
 
     pub fn rustflags_args(&self, unit: &Unit) -> CargoResult<Vec<String>> {
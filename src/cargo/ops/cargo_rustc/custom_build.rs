@@ -8,10 +8,11 @@ use core::PackageId;
 use util::{CargoResult, Human};
 use util::{internal, ChainError, profile, paths};
 use util::Freshness;
+use util::machine_message;
 
 use super::job::Work;
 use super::{fingerprint, Kind, Context, Unit};
-use super::CommandType;
+use super::{CommandType, CommandPrototype};
 
 /// Contains the parsed output of a custom build script.
 #[derive(Clone, Debug, Hash)]
@@ -80,8 +81,9 @@ pub fn prepare<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
     Ok((work_dirty.then(dirty), work_fresh.then(fresh), freshness))
 }
 
-fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
-                        -> CargoResult<(Work, Work)> {
+/// Returns the command which runs the build script of `unit`, with all of the
+/// environment that's known before any other build script has run.
+pub fn command(cx: &Context, unit: &Unit) -> CargoResult<CommandPrototype> {
     let (script_output, build_output) = {
         (cx.layout(unit.pkg, Kind::Host).build(unit.pkg),
          cx.layout(unit.pkg, unit.kind).build_out(unit.pkg))
@@ -104,7 +106,7 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
          Kind::Host => &cx.config.rustc_info().host[..],
          Kind::Target => cx.target_triple(),
      })
-     .env("DEBUG", &profile.debuginfo.to_string())
+     .env("DEBUG", &profile.debuginfo.is_some().to_string())
      .env("OPT_LEVEL", &profile.opt_level.to_string())
      .env("PROFILE", if cx.build_config.release {"release"} else {"debug"})
      .env("HOST", &cx.config.rustc_info().host);
@@ -120,6 +122,13 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
             p.env(&format!("CARGO_FEATURE_{}", super::envify(feat)), "1");
         }
     }
+    Ok(p)
+}
+
+fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
+                        -> CargoResult<(Work, Work)> {
+    let build_output = cx.layout(unit.pkg, unit.kind).build_out(unit.pkg);
+    let mut p = try!(command(cx, unit));
 
     // Gather the set of native dependencies that this package has along with
     // some other variables to close over.
@@ -158,6 +167,7 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
     try!(fs::create_dir_all(&cx.layout(unit.pkg, unit.kind).build(unit.pkg)));
 
     let exec_engine = cx.exec_engine.clone();
+    let json_messages = cx.build_config.json_messages;
 
     // Prepare the unit of "dirty work" which will actually run the custom build
     // command.
@@ -217,6 +227,9 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
         // state informing what variables were discovered via our script as
         // well.
         let parsed_output = try!(BuildOutput::parse(&output.stdout, &pkg_name));
+        if json_messages {
+            emit_build_output(&parsed_output, &id, false);
+        }
         build_state.insert(id, kind, parsed_output);
         Ok(())
     });
@@ -230,6 +243,9 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
             Some(output) => output,
             None => try!(BuildOutput::parse_file(&output_file, &pkg_name)),
         };
+        if json_messages {
+            emit_build_output(&output, &id, true);
+        }
         build_state.insert(id, kind, output);
         Ok(())
     });
@@ -237,6 +253,16 @@ fn build_work<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
     Ok((dirty, fresh))
 }
 
+fn emit_build_output(output: &BuildOutput, id: &PackageId, fresh: bool) {
+    machine_message::emit(machine_message::BuildScript {
+        package_id: id,
+        linked_libs: &output.library_links,
+        linked_paths: &output.library_paths,
+        cfgs: &output.cfgs,
+        fresh: fresh,
+    });
+}
+
 impl BuildState {
     pub fn new(config: &super::BuildConfig) -> BuildState {
         let mut overrides = HashMap::new();
@@ -255,7 +281,7 @@ impl BuildState {
         self.outputs.lock().unwrap().insert((id, kind), output);
     }
 
-    fn has_override(&self, unit: &Unit) -> bool {
+    pub fn has_override(&self, unit: &Unit) -> bool {
         let key = unit.pkg.manifest().links().map(|l| (l.to_string(), unit.kind));
         match key.and_then(|k| self.overrides.get(&k)) {
             Some(output) => {
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::{self, File, OpenOptions};
 use std::hash::{self, Hasher};
 use std::io::prelude::*;
@@ -6,16 +7,23 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 use filetime::FileTime;
+use rustc_serialize::hex::ToHex;
 use rustc_serialize::{json, Encodable, Decodable, Encoder, Decoder};
 
 use core::{Package, TargetKind};
+use sources::PathSource;
 use util;
-use util::{CargoResult, Fresh, Dirty, Freshness, internal, profile, ChainError};
+use util::{CargoResult, Fresh, Dirty, Freshness, internal, human, profile};
+use util::{ChainError, Sha256};
 use util::paths;
 
 use super::job::Work;
 use super::context::{Context, Unit};
 
+/// Prefix of the fingerprint of a build script whose output is overridden.
+const OVERRIDDEN_BUILD_STATE: &'static str =
+    "overridden build state with hash: ";
+
 /// A tuple result of the `prepare_foo` functions in this module.
 ///
 /// The first element of the triple is whether the target in question is
@@ -58,15 +66,16 @@ pub fn prepare_target<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
     log_compare(unit, &compare);
 
     let root = cx.out_dir(unit);
-    let mut missing_outputs = false;
-    if unit.profile.doc {
-        missing_outputs = !root.join(unit.target.crate_name())
-                               .join("index.html").exists();
+    let outputs = if unit.profile.doc {
+        vec![root.join(unit.target.crate_name()).join("index.html")]
     } else {
-        for filename in try!(cx.target_filenames(unit)).iter() {
-            missing_outputs |= fs::metadata(root.join(filename)).is_err();
-        }
-    }
+        try!(cx.target_filenames(unit)).iter().map(|f| root.join(f)).collect()
+    };
+    let missing_output = outputs.into_iter().find(|output| {
+        fs::metadata(output).is_err()
+    });
+    let missing_outputs = missing_output.is_some();
+    note_rebuild_reason(cx, unit, &loc, &compare, missing_output);
 
     let allow_failure = unit.profile.rustc_args.is_some();
     let write_fingerprint = Work::new(move |_| {
@@ -82,6 +91,26 @@ pub fn prepare_target<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
     Ok((if fresh {Fresh} else {Dirty}, write_fingerprint, Work::noop()))
 }
 
+/// Records why `unit` has to be rebuilt, if it was built before, so that the
+/// job queue can explain it.
+fn note_rebuild_reason<'a, 'cfg>(cx: &mut Context<'a, 'cfg>,
+                                 unit: &Unit<'a>,
+                                 loc: &Path,
+                                 compare: &CargoResult<()>,
+                                 missing_output: Option<PathBuf>) {
+    if fs::metadata(loc).is_err() {
+        return
+    }
+    let reason = match (compare, missing_output) {
+        (&Err(ref e), _) => e.to_string(),
+        (&Ok(()), Some(output)) => {
+            format!("the output `{}` is missing", output.display())
+        }
+        (&Ok(()), None) => return,
+    };
+    cx.rebuild_reasons.insert(*unit, reason);
+}
+
 /// A fingerprint can be considered to be a "short string" representing the
 /// state of a world for a package.
 ///
@@ -112,16 +141,36 @@ pub struct Fingerprint {
     local: LocalFingerprint,
     memoized_hash: Mutex<Option<u64>>,
     rustflags: Vec<String>,
+    /// Why the local fingerprint is out of date, if that's known up front
+    stale: Option<String>,
 }
 
 #[derive(RustcEncodable, RustcDecodable, Hash)]
 enum LocalFingerprint {
-    Precalculated(String),
+    /// A fingerprint from the package's source, along with the file of the
+    /// package which changed last if it's derived from its files
+    Precalculated(String, Option<PathBuf>),
     MtimeBased(MtimeSlot, PathBuf),
+    ContentHashBased(HashSlot, PathBuf),
+    /// The hashes of a list of files, cached at the path, used for build
+    /// scripts in content-hash mode
+    FileHashes(HashSlot, PathBuf, Vec<PathBuf>),
 }
 
 struct MtimeSlot(Mutex<Option<FileTime>>);
 
+/// The sha256 of each source file listed in a dep-info file
+struct HashSlot(Mutex<Option<Vec<(PathBuf, String)>>>);
+
+/// An entry of the cache of source file hashes kept next to a dep-info file
+#[derive(RustcEncodable, RustcDecodable, Clone)]
+struct CachedHash {
+    path: PathBuf,
+    mtime: (u64, u32),
+    size: u64,
+    hash: String,
+}
+
 impl Fingerprint {
     fn update_local(&self) -> CargoResult<()> {
         match self.local {
@@ -132,6 +181,14 @@ impl Fingerprint {
                 let mtime = FileTime::from_last_modification_time(&meta);
                 *slot.0.lock().unwrap() = Some(mtime);
             }
+            LocalFingerprint::ContentHashBased(ref slot, ref path) => {
+                let hashes = try!(dep_info_hashes(path));
+                *slot.0.lock().unwrap() = hashes.ok();
+            }
+            LocalFingerprint::FileHashes(ref slot, ref cache, ref files) => {
+                let hashes = try!(hash_files(files, cache));
+                *slot.0.lock().unwrap() = hashes.ok();
+            }
             LocalFingerprint::Precalculated(..) => return Ok(())
         }
 
@@ -150,52 +207,99 @@ impl Fingerprint {
 
     fn compare(&self, old: &Fingerprint) -> CargoResult<()> {
         if self.rustc != old.rustc {
-            bail!("rust compiler has changed")
+            bail!("the compiler has changed")
         }
         if self.features != old.features {
-            bail!("features have changed: {} != {}", self.features, old.features)
+            bail!("the features have changed from {} to {}",
+                  old.features, self.features)
         }
         if self.target != old.target {
-            bail!("target configuration has changed")
+            bail!("the target's configuration has changed")
         }
         if self.profile != old.profile {
-            bail!("profile configuration has changed")
+            bail!("the profile has changed")
         }
         if self.rustflags != old.rustflags {
-            return Err(internal("RUSTFLAGS has changed"))
+            bail!("the flags from RUSTFLAGS or `build.rustflags` have changed")
         }
         match (&self.local, &old.local) {
-            (&LocalFingerprint::Precalculated(ref a),
-             &LocalFingerprint::Precalculated(ref b)) => {
-                if a != b {
-                    bail!("precalculated components have changed: {} != {}",
-                          a, b)
+            (&LocalFingerprint::Precalculated(ref a, ref ap),
+             &LocalFingerprint::Precalculated(ref b, ref bp)) => {
+                if a != b || ap != bp {
+                    bail!("{}", describe_precalculated(a, ap.as_ref()))
                 }
             }
             (&LocalFingerprint::MtimeBased(ref a, ref ap),
-             &LocalFingerprint::MtimeBased(ref b, ref bp)) => {
+             &LocalFingerprint::MtimeBased(ref b, _)) => {
                 let a = a.0.lock().unwrap();
                 let b = b.0.lock().unwrap();
                 if *a != *b {
-                    bail!("mtime based comopnents have changed: {:?} != {:?}, \
-                           paths are {:?} and {:?}", *a, *b, ap, bp)
+                    if let Some(ref stale) = self.stale {
+                        bail!("{}", stale)
+                    }
+                    bail!("the files `{}` depends on have changed",
+                          ap.display())
                 }
             }
-            _ => bail!("local fingerprint type has changed"),
+            (&LocalFingerprint::ContentHashBased(ref a, ref ap),
+             &LocalFingerprint::ContentHashBased(ref b, _)) => {
+                try!(self.compare_hashes(a, b, || {
+                    format!("the source files listed in `{}` have changed",
+                            ap.display())
+                }))
+            }
+            (&LocalFingerprint::FileHashes(ref a, _, _),
+             &LocalFingerprint::FileHashes(ref b, _, _)) => {
+                try!(self.compare_hashes(a, b, || {
+                    "the files it depends on have changed".to_string()
+                }))
+            }
+            _ => bail!("the files it depends on are tracked differently now"),
         }
 
         if self.deps.len() != old.deps.len() {
-            bail!("number of dependencies has changed")
+            bail!("the set of dependencies has changed")
         }
         for (a, b) in self.deps.iter().zip(old.deps.iter()) {
+            if a.0 != b.0 {
+                bail!("the dependency `{}` has been replaced by `{}`", b.0, a.0)
+            }
             if a.1.hash() != b.1.hash() {
-                bail!("new ({}) != old ({})", a.0, b.0)
+                bail!("the dependency `{}` has changed", a.0)
             }
         }
         Ok(())
     }
 }
 
+impl Fingerprint {
+    /// Compares the hashes `a` of this fingerprint with those of an older
+    /// one, `b`, falling back to `changed` to explain a difference which
+    /// can't be pinned on a single file.
+    fn compare_hashes<F>(&self, a: &HashSlot, b: &HashSlot, changed: F)
+                         -> CargoResult<()>
+        where F: FnOnce() -> String
+    {
+        let a = a.0.lock().unwrap();
+        let b = b.0.lock().unwrap();
+        if *a == *b {
+            return Ok(())
+        }
+        if let Some(ref stale) = self.stale {
+            bail!("{}", stale)
+        }
+        let file = a.iter().flat_map(|a| a).find(|a| {
+            b.iter().flat_map(|b| b).any(|b| a.0 == b.0 && a.1 != b.1)
+        });
+        match file {
+            Some(&(ref path, _)) => {
+                bail!("the file `{}` has changed", path.display())
+            }
+            None => bail!("{}", changed()),
+        }
+    }
+}
+
 impl hash::Hash for Fingerprint {
     fn hash<H: Hasher>(&self, h: &mut H) {
         let Fingerprint {
@@ -207,6 +311,7 @@ impl hash::Hash for Fingerprint {
             ref local,
             memoized_hash: _,
             ref rustflags,
+            stale: _,
         } = *self;
         (rustc, features, target, profile, deps, local, rustflags).hash(h)
     }
@@ -250,19 +355,23 @@ impl Decodable for Fingerprint {
                     let decode = decode::<Vec<(String, u64)>, D>;
                     let v = try!(d.read_struct_field("deps", 5, decode));
                     v.into_iter().map(|(name, hash)| {
+                        let local = LocalFingerprint::Precalculated(
+                            String::new(), None);
                         (name, Arc::new(Fingerprint {
                             rustc: 0,
                             target: 0,
                             profile: 0,
-                            local: LocalFingerprint::Precalculated(String::new()),
+                            local: local,
                             features: String::new(),
                             deps: Vec::new(),
                             memoized_hash: Mutex::new(Some(hash)),
                             rustflags: Vec::new(),
+                            stale: None,
                         }))
                     }).collect()
                 },
                 rustflags: try!(d.read_struct_field("rustflags", 6, decode)),
+                stale: None,
             })
         })
     }
@@ -282,6 +391,24 @@ impl Encodable for MtimeSlot {
     }
 }
 
+impl hash::Hash for HashSlot {
+    fn hash<H: Hasher>(&self, h: &mut H) {
+        self.0.lock().unwrap().hash(h)
+    }
+}
+
+impl Encodable for HashSlot {
+    fn encode<E: Encoder>(&self, e: &mut E) -> Result<(), E::Error> {
+        self.0.lock().unwrap().encode(e)
+    }
+}
+
+impl Decodable for HashSlot {
+    fn decode<D: Decoder>(d: &mut D) -> Result<HashSlot, D::Error> {
+        Ok(HashSlot(Mutex::new(try!(Decodable::decode(d)))))
+    }
+}
+
 impl Decodable for MtimeSlot {
     fn decode<D: Decoder>(e: &mut D) -> Result<MtimeSlot, D::Error> {
         let kind: Option<(u64, u32)> = try!(Decodable::decode(e));
@@ -336,13 +463,22 @@ fn calculate<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
     }).collect::<CargoResult<Vec<_>>>());
 
     // And finally, calculate what our own local fingerprint is
-    let local = if use_dep_info(unit) {
+    let (local, stale) = if use_dep_info(unit) {
         let dep_info = dep_info_loc(cx, unit);
-        let mtime = try!(dep_info_mtime_if_fresh(&dep_info));
-        LocalFingerprint::MtimeBased(MtimeSlot(Mutex::new(mtime)), dep_info)
+        if cx.build_config.content_hash {
+            let hashes = try!(dep_info_hashes(&dep_info));
+            let (hashes, stale) = split_stale(hashes);
+            let slot = HashSlot(Mutex::new(hashes));
+            (LocalFingerprint::ContentHashBased(slot, dep_info), stale)
+        } else {
+            let mtime = try!(dep_info_mtime_if_fresh(&dep_info));
+            let (mtime, stale) = split_stale(mtime);
+            let slot = MtimeSlot(Mutex::new(mtime));
+            (LocalFingerprint::MtimeBased(slot, dep_info), stale)
+        }
     } else {
-        let fingerprint = try!(pkg_fingerprint(cx, unit.pkg));
-        LocalFingerprint::Precalculated(fingerprint)
+        let (fingerprint, file) = try!(pkg_fingerprint(cx, unit.pkg));
+        (LocalFingerprint::Precalculated(fingerprint, file), None)
     };
     let mut deps = deps;
     deps.sort_by(|&(ref a, _), &(ref b, _)| a.cmp(b));
@@ -355,6 +491,7 @@ fn calculate<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
         local: local,
         memoized_hash: Mutex::new(None),
         rustflags: try!(cx.rustflags_args(unit)),
+        stale: stale,
     });
     cx.fingerprints.insert(*unit, fingerprint.clone());
     Ok(fingerprint)
@@ -400,28 +537,45 @@ pub fn prepare_build_cmd<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
     // is just a hash of what it was overridden with. Otherwise the fingerprint
     // is that of the entire package itself as we just consider everything as
     // input to the build script.
-    let (local, output_path) = {
+    let (local, stale, output_path) = {
         let state = cx.build_state.outputs.lock().unwrap();
         match state.get(&(unit.pkg.package_id().clone(), unit.kind)) {
             Some(output) => {
-                let s = format!("overridden build state with hash: {}",
+                let s = format!("{}{}", OVERRIDDEN_BUILD_STATE,
                                 util::hash_u64(output));
-                (LocalFingerprint::Precalculated(s), None)
+                (LocalFingerprint::Precalculated(s, None), None, None)
             }
             None => {
                 let &(ref output, ref deps) = &cx.build_explicit_deps[unit];
-
-                let local = if deps.is_empty() {
-                    let s = try!(pkg_fingerprint(cx, unit.pkg));
-                    LocalFingerprint::Precalculated(s)
+                let content_hash = cx.build_config.content_hash;
+                let is_path = unit.pkg.package_id().source_id().is_path();
+
+                let (local, stale) = if deps.is_empty() {
+                    if content_hash && is_path {
+                        let src = PathSource::new(unit.pkg.root(),
+                                                  unit.pkg.package_id()
+                                                      .source_id(),
+                                                  cx.config);
+                        let files = try!(src.list_files(unit.pkg));
+                        try!(file_hashes(files, loc.with_extension("hashes")))
+                    } else {
+                        let (s, file) = try!(pkg_fingerprint(cx, unit.pkg));
+                        (LocalFingerprint::Precalculated(s, file), None)
+                    }
+                } else if content_hash {
+                    let files = deps.iter().map(|p| {
+                        unit.pkg.root().join(p)
+                    }).collect();
+                    try!(file_hashes(files, loc.with_extension("hashes")))
                 } else {
                     let deps = deps.iter().map(|p| unit.pkg.root().join(p));
-                    let mtime = mtime_if_fresh(output, deps);
+                    let (mtime, stale) = split_stale(mtime_if_fresh(output,
+                                                                    deps));
                     let mtime = MtimeSlot(Mutex::new(mtime));
-                    LocalFingerprint::MtimeBased(mtime, output.clone())
+                    (LocalFingerprint::MtimeBased(mtime, output.clone()), stale)
                 };
 
-                (local, Some(output.clone()))
+                (local, stale, Some(output.clone()))
             }
         }
     };
@@ -435,9 +589,11 @@ pub fn prepare_build_cmd<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
         local: local,
         memoized_hash: Mutex::new(None),
         rustflags: Vec::new(),
+        stale: stale,
     };
     let compare = compare_old_fingerprint(&loc, &fingerprint);
     log_compare(unit, &compare);
+    note_rebuild_reason(cx, unit, &loc, &compare, None);
 
     // When we write out the fingerprint, we may want to actually change the
     // kind of fingerprint being recorded. If we started out, then the previous
@@ -448,16 +604,29 @@ pub fn prepare_build_cmd<'a, 'cfg>(cx: &mut Context<'a, 'cfg>, unit: &Unit<'a>)
     //
     // Hence, if there were some `rerun-if-changed` directives forcibly change
     // the kind of fingerprint over to the `MtimeBased` variant where the
-    // relevant mtime is the output path of the build script.
+    // relevant mtime is the output path of the build script, or to the hashes
+    // of the files listed in content-hash mode.
     let state = cx.build_state.clone();
     let key = (unit.pkg.package_id().clone(), unit.kind);
+    let content_hash = cx.build_config.content_hash;
+    let root = unit.pkg.root().to_path_buf();
     let write_fingerprint = Work::new(move |_| {
         if let Some(output_path) = output_path {
             let outputs = state.outputs.lock().unwrap();
-            if !outputs[&key].rerun_if_changed.is_empty() {
-                let slot = MtimeSlot(Mutex::new(None));
-                fingerprint.local = LocalFingerprint::MtimeBased(slot,
-                                                                 output_path);
+            let rerun_if_changed = &outputs[&key].rerun_if_changed;
+            if !rerun_if_changed.is_empty() {
+                fingerprint.local = if content_hash {
+                    let slot = HashSlot(Mutex::new(None));
+                    let files = rerun_if_changed.iter().map(|p| {
+                        root.join(p)
+                    }).collect();
+                    LocalFingerprint::FileHashes(slot,
+                                                 loc.with_extension("hashes"),
+                                                 files)
+                } else {
+                    let slot = MtimeSlot(Mutex::new(None));
+                    LocalFingerprint::MtimeBased(slot, output_path)
+                };
                 try!(fingerprint.update_local());
             }
         }
@@ -533,20 +702,128 @@ fn log_compare(unit: &Unit, compare: &CargoResult<()>) {
     }
 }
 
-fn dep_info_mtime_if_fresh(dep_info: &Path) -> CargoResult<Option<FileTime>> {
+fn dep_info_mtime_if_fresh(dep_info: &Path)
+                           -> CargoResult<Result<FileTime, String>> {
+    let paths = match try!(dep_info_paths(dep_info)) {
+        Ok(paths) => paths,
+        Err(stale) => return Ok(Err(stale)),
+    };
+    Ok(mtime_if_fresh(&dep_info, paths.iter()))
+}
+
+/// Returns the sha256 of each source file listed in `dep_info`, or why they
+/// can't be hashed.
+///
+/// The hashes are cached next to the dep-info file, see `hash_files`.
+fn dep_info_hashes(dep_info: &Path)
+                   -> CargoResult<Result<Vec<(PathBuf, String)>, String>> {
+    let files = match try!(dep_info_paths(dep_info)) {
+        Ok(files) => files,
+        Err(stale) => return Ok(Err(stale)),
+    };
+    hash_files(&files, &dep_info.with_extension("hashes"))
+}
+
+/// Fingerprints `files` by their contents, with their hashes cached at
+/// `cache_loc`.
+fn file_hashes(files: Vec<PathBuf>, cache_loc: PathBuf)
+               -> CargoResult<(LocalFingerprint, Option<String>)> {
+    let (hashes, stale) = split_stale(try!(hash_files(&files, &cache_loc)));
+    let slot = HashSlot(Mutex::new(hashes));
+    Ok((LocalFingerprint::FileHashes(slot, cache_loc, files), stale))
+}
+
+/// Returns the sha256 of each of `files`, or why they can't be hashed.
+///
+/// The hashes are cached at `cache_loc` along with the mtime and size of each
+/// file, and a file is only read again if either has changed.
+fn hash_files(files: &[PathBuf], cache_loc: &Path)
+              -> CargoResult<Result<Vec<(PathBuf, String)>, String>> {
+    let mut cache = paths::read(cache_loc).ok().and_then(|s| {
+        json::decode::<Vec<CachedHash>>(&s).ok()
+    }).unwrap_or(Vec::new()).into_iter().map(|c| {
+        (c.path.clone(), c)
+    }).collect::<HashMap<_, _>>();
+
+    let mut hashes = Vec::new();
+    let mut entries = Vec::new();
+    let mut rehashed = false;
+    for path in files {
+        let meta = match fs::metadata(path) {
+            Ok(meta) => meta,
+            Err(..) => {
+                info!("stale: {} -- missing", path.display());
+                return Ok(Err(format!("the file `{}` is missing",
+                                      path.display())))
+            }
+        };
+        let mtime = FileTime::from_last_modification_time(&meta);
+        let mtime = (mtime.seconds_relative_to_1970(), mtime.nanoseconds());
+        let entry = match cache.remove(path) {
+            Some(ref c) if c.mtime == mtime && c.size == meta.len() => {
+                c.clone()
+            }
+            _ => {
+                rehashed = true;
+                CachedHash {
+                    hash: try!(hash_file(path)),
+                    path: path.clone(),
+                    mtime: mtime,
+                    size: meta.len(),
+                }
+            }
+        };
+        hashes.push((path.clone(), entry.hash.clone()));
+        entries.push(entry);
+    }
+
+    if rehashed || !cache.is_empty() {
+        let entries = json::encode(&entries).unwrap();
+        try!(paths::write(cache_loc, entries.as_bytes()));
+    }
+    Ok(Ok(hashes))
+}
+
+fn hash_file(path: &Path) -> CargoResult<String> {
+    let mut h = Sha256::new();
+    let mut buf = [0; 64 * 1024];
+    try!((|| -> CargoResult<()> {
+        let mut f = try!(File::open(path));
+        loop {
+            match try!(f.read(&mut buf)) {
+                0 => return Ok(()),
+                n => h.update(&buf[..n]),
+            }
+        }
+    })().chain_error(|| {
+        human(format!("failed to hash `{}`", path.display()))
+    }));
+    Ok(h.finish().to_hex())
+}
+
+/// Parses the paths of the source files out of a dep-info file, or returns
+/// why it can't be read.
+fn dep_info_paths(dep_info: &Path)
+                  -> CargoResult<Result<Vec<PathBuf>, String>> {
+    let unreadable = || {
+        format!("the dep-info file `{}` can't be read", dep_info.display())
+    };
     macro_rules! fs_try {
-        ($e:expr) => (match $e { Ok(e) => e, Err(..) => return Ok(None) })
+        ($e:expr) => (match $e {
+            Ok(e) => e,
+            Err(..) => return Ok(Err(unreadable())),
+        })
     }
     let mut f = BufReader::new(fs_try!(File::open(dep_info)));
     // see comments in append_current_dir for where this cwd is manifested from.
     let mut cwd = Vec::new();
     if fs_try!(f.read_until(0, &mut cwd)) == 0 {
-        return Ok(None)
+        return Ok(Err(unreadable()))
     }
     let cwd = try!(util::bytes2path(&cwd[..cwd.len()-1]));
     let line = match f.lines().next() {
         Some(Ok(line)) => line,
-        _ => return Ok(None),
+        _ => return Ok(Err(unreadable())),
     };
     let pos = try!(line.find(": ").chain_error(|| {
         internal(format!("dep-info not in an understood format: {}",
@@ -571,54 +848,71 @@ fn dep_info_mtime_if_fresh(dep_info: &Path) -> CargoResult<Option<FileTime>> {
         paths.push(cwd.join(&file));
     }
 
-    Ok(mtime_if_fresh(&dep_info, paths.iter()))
+    Ok(Ok(paths))
+}
+
+/// Explains a change of the precalculated fingerprint `new`, which follows
+/// the files of the package if `file` is the one which changed last. Those of
+/// overridden build scripts are marked as such.
+fn describe_precalculated(new: &str, file: Option<&PathBuf>) -> String {
+    if new.starts_with(OVERRIDDEN_BUILD_STATE) {
+        return "the overridden output of the build script has changed"
+                   .to_string()
+    }
+    match file {
+        Some(file) => format!("the file `{}` has changed", file.display()),
+        None => "the source of the package has changed".to_string(),
+    }
 }
 
-fn pkg_fingerprint(cx: &Context, pkg: &Package) -> CargoResult<String> {
+fn pkg_fingerprint(cx: &Context, pkg: &Package)
+                   -> CargoResult<(String, Option<PathBuf>)> {
     let source_id = pkg.package_id().source_id();
     let sources = cx.packages.sources();
     let source = try!(sources.get(source_id).chain_error(|| {
         internal("missing package source")
     }));
-    source.fingerprint(pkg)
+    source.fingerprint_with_file(pkg)
 }
 
-fn mtime_if_fresh<I>(output: &Path, paths: I) -> Option<FileTime>
+/// Returns the mtime of `output` if none of `paths` is newer than it, or why
+/// it's out of date otherwise.
+fn mtime_if_fresh<I>(output: &Path, paths: I) -> Result<FileTime, String>
     where I: IntoIterator,
           I::Item: AsRef<Path>,
 {
     let meta = match fs::metadata(output) {
         Ok(meta) => meta,
-        Err(..) => return None,
+        Err(..) => return Err(format!("`{}` is missing", output.display())),
     };
     let mtime = FileTime::from_last_modification_time(&meta);
 
-    let any_stale = paths.into_iter().any(|path| {
+    for path in paths {
         let path = path.as_ref();
         let meta = match fs::metadata(path) {
             Ok(meta) => meta,
             Err(..) => {
                 info!("stale: {} -- missing", path.display());
-                return true
+                return Err(format!("the file `{}` is missing", path.display()))
             }
         };
         let mtime2 = FileTime::from_last_modification_time(&meta);
         if mtime2 > mtime {
             info!("stale: {} -- {} vs {}", path.display(), mtime2, mtime);
-            true
-        } else {
-            false
+            return Err(format!("the file `{}` has changed", path.display()))
         }
-    });
+    }
+    Ok(mtime)
+}
 
-    if any_stale {
-        None
-    } else {
-        Some(mtime)
+fn split_stale<T>(local: Result<T, String>) -> (Option<T>, Option<String>) {
+    match local {
+        Ok(local) => (Some(local), None),
+        Err(stale) => (None, Some(stale)),
     }
 }
 
-fn filename(unit: &Unit) -> String {
+pub fn filename(unit: &Unit) -> String {
     let kind = match *unit.target.kind() {
         TargetKind::Lib(..) => "lib",
         TargetKind::Bin => "bin",
@@ -631,6 +925,10 @@ fn filename(unit: &Unit) -> String {
         "test-"
     } else if unit.profile.doc {
         "doc-"
+    } else if unit.profile.check {
+        "check-"
+    } else if unit.profile.build_override {
+        "host-"
     } else {
         ""
     };
@@ -6,7 +6,7 @@ use std::sync::mpsc::{channel, Sender, Receiver};
 use crossbeam::{self, Scope};
 use term::color::YELLOW;
 
-use core::{PackageId, Target, Profile};
+use core::{PackageId, Target, TargetKind, Profile};
 use util::{Config, DependencyQueue, Fresh, Dirty, Freshness};
 use util::{CargoResult, profile, internal};
 
@@ -28,6 +28,11 @@ pub struct JobQueue<'a> {
     compiled: HashSet<&'a PackageId>,
     documented: HashSet<&'a PackageId>,
     counts: HashMap<&'a PackageId, usize>,
+    /// Whether to explain every rebuild, not only with `-v`
+    explain: bool,
+    reasons: HashMap<Key<'a>, String>,
+    deps: HashMap<Key<'a>, Vec<Key<'a>>>,
+    rebuilt: HashSet<Key<'a>>,
 }
 
 /// A helper structure for metadata about the state of a building package.
@@ -65,6 +70,10 @@ impl<'a> JobQueue<'a> {
             compiled: HashSet::new(),
             documented: HashSet::new(),
             counts: HashMap::new(),
+            explain: cx.build_config.explain_rebuilds,
+            reasons: HashMap::new(),
+            deps: HashMap::new(),
+            rebuilt: HashSet::new(),
         }
     }
 
@@ -77,6 +86,23 @@ impl<'a> JobQueue<'a> {
         let deps = try!(key.dependencies(cx));
         self.queue.queue(Fresh, key, Vec::new(), &deps).push((job, fresh));
         *self.counts.entry(key.pkg).or_insert(0) += 1;
+        if let Some(reason) = cx.rebuild_reasons.get(unit) {
+            self.reasons.insert(key, reason.clone());
+        }
+        self.deps.insert(key, deps);
+        Ok(())
+    }
+
+    /// Goes through the dependency graph like `execute` without running any
+    /// jobs, explaining what would be rebuilt.
+    pub fn dry_run(&mut self, config: &Config) -> CargoResult<()> {
+        while let Some((fresh, key, jobs)) = self.queue.dequeue() {
+            let total_fresh = jobs.iter().fold(fresh, |fresh, &(_, f)| {
+                f.combine(fresh)
+            });
+            try!(self.explain(config, &key, &jobs, total_fresh));
+            self.queue.finish(&key, total_fresh);
+        }
         Ok(())
     }
 
@@ -116,6 +142,7 @@ impl<'a> JobQueue<'a> {
                     let total_fresh = jobs.iter().fold(fresh, |fresh, &(_, f)| {
                         f.combine(fresh)
                     });
+                    try!(self.explain(config, &key, &jobs, total_fresh));
                     self.pending.insert(key, PendingBuild {
                         amt: jobs.len(),
                         fresh: total_fresh,
@@ -197,6 +224,41 @@ impl<'a> JobQueue<'a> {
         Ok(())
     }
 
+    /// Prints why the unit of `key` is rebuilt, if it is. Units which have
+    /// changed since they were last built are explained with `-v`, and all
+    /// of them are when rebuilds are to be explained.
+    fn explain(&mut self,
+               config: &Config,
+               key: &Key<'a>,
+               jobs: &[(Job, Freshness)],
+               fresh: Freshness) -> CargoResult<()> {
+        if fresh == Fresh {
+            return Ok(())
+        }
+        self.rebuilt.insert(*key);
+        let reason = match self.reasons.get(key) {
+            Some(reason) => reason.clone(),
+            None if !self.explain => return Ok(()),
+            None if jobs.iter().any(|&(_, f)| f == Dirty) => {
+                "it hasn't been built before".to_string()
+            }
+            None => {
+                let rebuilt = &self.rebuilt;
+                match self.deps[key].iter().find(|dep| rebuilt.contains(*dep)) {
+                    Some(dep) => format!("the {} of `{}` is being rebuilt",
+                                         dep.describe(), dep.pkg),
+                    None => "a dependency is being rebuilt".to_string(),
+                }
+            }
+        };
+        let msg = format!("{} ({}): {}", key.pkg, key.describe(), reason);
+        if self.explain {
+            config.shell().status("Dirty", msg)
+        } else {
+            config.shell().verbose(|c| c.status("Dirty", &msg))
+        }
+    }
+
     // This isn't super trivial because we don't want to print loads and
     // loads of information to the console, but we also want to produce a
     // faithful representation of what's happening. This is somewhat nuanced
@@ -220,6 +282,9 @@ impl<'a> JobQueue<'a> {
                 if key.profile.doc {
                     self.documented.insert(key.pkg);
                     try!(config.shell().status("Documenting", key.pkg));
+                } else if key.profile.check {
+                    self.compiled.insert(key.pkg);
+                    try!(config.shell().status("Checking", key.pkg));
                 } else {
                     self.compiled.insert(key.pkg);
                     try!(config.shell().status("Compiling", key.pkg));
@@ -266,6 +331,34 @@ impl<'a> Key<'a> {
     }
 }
 
+impl<'a> Key<'a> {
+    /// A short description of the unit, like "lib `foo`"
+    fn describe(&self) -> String {
+        let kind = match *self.target.kind() {
+            TargetKind::CustomBuild if self.profile.run_custom_build => {
+                return "build script execution".to_string()
+            }
+            TargetKind::CustomBuild => return "build script".to_string(),
+            TargetKind::Lib(..) => "lib",
+            TargetKind::Bin => "bin",
+            TargetKind::Test => "test",
+            TargetKind::Bench => "bench",
+            TargetKind::Example => "example",
+        };
+        let flavor = if self.profile.doc {
+            "docs of "
+        } else if self.profile.check {
+            "check of "
+        } else if self.profile.test &&
+                  (self.target.is_lib() || self.target.is_bin()) {
+            "tests of "
+        } else {
+            ""
+        };
+        format!("{}{} `{}`", flavor, kind, self.target.name())
+    }
+}
+
 impl<'a> fmt::Debug for Key<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{} => {}/{} => {:?}", self.pkg, self.target, self.profile,
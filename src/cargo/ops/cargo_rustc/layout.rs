@@ -4,8 +4,11 @@
 //! house this logic. The current layout looks like this:
 //!
 //! ```ignore
-//! # This is the root directory for all output, the top-level package
-//! # places all of its output here.
+//! # This is the root directory for all output of a profile, the top-level
+//! # package places all of its output here. It's `debug` or `release` in the
+//! # target directory, or `profiles/$name` for a custom profile, all of which
+//! # are nested in a directory named after the target triple when cross
+//! # compiling.
 //! target/
 //!
 //!     # This is the root directory for all output of *dependencies*
@@ -43,6 +46,17 @@
 //!     # Hidden directory that holds all of the fingerprint files for all
 //!     # packages
 //!     .fingerprint/
+//!
+//!     # Hidden directory for the metadata emitted by `cargo check`, kept
+//!     # apart so it's never picked up by a real build
+//!     .check/
+//!
+//!     # The incremental compilation state of the targets of each package,
+//!     # created once a profile with `incremental` enabled is used
+//!     incremental/
+//!
+//!         $pkg1/
+//!         $pkg2/
 //! ```
 
 use std::fs;
@@ -60,6 +74,8 @@ pub struct Layout {
     build: PathBuf,
     fingerprint: PathBuf,
     examples: PathBuf,
+    check: PathBuf,
+    incremental: PathBuf,
     _lock: FileLock,
 }
 
@@ -72,7 +88,7 @@ impl Layout {
     pub fn new(config: &Config,
                pkg: &Package,
                triple: Option<&str>,
-               dest: &str) -> CargoResult<Layout> {
+               dest: &Path) -> CargoResult<Layout> {
         let mut path = config.target_dir(pkg);
         // Flexible target specifications often point at filenames, so interpret
         // the target triple as a Path and then just use the file stem as the
@@ -97,6 +113,8 @@ impl Layout {
             build: root.join("build"),
             fingerprint: root.join(".fingerprint"),
             examples: root.join("examples"),
+            check: root.join(".check"),
+            incremental: root.join("incremental"),
             root: root,
             _lock: lock,
         })
@@ -112,6 +130,7 @@ impl Layout {
         try!(mkdir(&self.fingerprint));
         try!(mkdir(&self.examples));
         try!(mkdir(&self.build));
+        try!(mkdir(&self.check));
 
         return Ok(());
 
@@ -126,6 +145,7 @@ impl Layout {
     pub fn dest(&self) -> &Path { &self.root }
     pub fn deps(&self) -> &Path { &self.deps }
     pub fn examples(&self) -> &Path { &self.examples }
+    pub fn check(&self) -> &Path { &self.check }
     pub fn root(&self) -> &Path { &self.root }
 
     pub fn fingerprint(&self, package: &Package) -> PathBuf {
@@ -140,6 +160,10 @@ impl Layout {
         self.build(package).join("out")
     }
 
+    pub fn incremental(&self, package: &Package) -> PathBuf {
+        self.incremental.join(&self.pkg_dir(package))
+    }
+
     fn pkg_dir(&self, pkg: &Package) -> String {
         format!("{}-{}", pkg.name(), short_hash(pkg))
     }
@@ -160,6 +184,8 @@ impl<'a> LayoutProxy<'a> {
 
     pub fn examples(&self) -> &'a Path { self.root.examples() }
 
+    pub fn check(&self) -> &'a Path { self.root.check() }
+
     pub fn build(&self, pkg: &Package) -> PathBuf { self.root.build(pkg) }
 
     pub fn build_out(&self, pkg: &Package) -> PathBuf { self.root.build_out(pkg) }
@@ -2,13 +2,18 @@ use std::collections::HashMap;
 use std::env;
 use std::ffi::{OsStr, OsString};
 use std::fs;
-use std::path::{self, PathBuf};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use rustc_serialize::json::Json;
+
 use core::{Package, PackageId, PackageSet, Target, Resolve};
 use core::{Profile, Profiles};
 use util::{self, CargoResult, human};
 use util::{Config, internal, ChainError, profile, join_paths};
+use util::{ProcessError, process_error};
+use util::machine_message;
 
 use self::job::{Job, Work};
 use self::job_queue::JobQueue;
@@ -19,6 +24,7 @@ pub use self::engine::{CommandPrototype, CommandType, ExecEngine, ProcessEngine}
 pub use self::layout::{Layout, LayoutProxy};
 pub use self::custom_build::{BuildOutput, BuildMap, BuildScripts};
 
+mod build_plan;
 mod context;
 mod compilation;
 mod custom_build;
@@ -40,8 +46,17 @@ pub struct BuildConfig {
     pub requested_target: Option<String>,
     pub exec_engine: Option<Arc<Box<ExecEngine>>>,
     pub release: bool,
+    /// Name of the custom profile being compiled with, if any
+    pub profile: Option<String>,
     pub test: bool,
     pub doc_all: bool,
+    pub json_messages: bool,
+    pub build_plan: bool,
+    pub explain_rebuilds: bool,
+    pub dry_run: bool,
+    /// Whether path dependencies are considered fresh based on the contents
+    /// of their source files rather than on mtimes
+    pub content_hash: bool,
 }
 
 #[derive(Clone, Default)]
@@ -78,7 +93,13 @@ pub fn compile_targets<'a, 'cfg: 'a>(pkg_targets: &'a PackagesToBuild<'a>,
         })
     }).collect::<Vec<_>>();
 
-    let dest = if build_config.release {"release"} else {"debug"};
+    // Custom profiles are nested in a directory of their own so their names
+    // can't collide with anything else Cargo places in the target directory.
+    let dest = match build_config.profile {
+        Some(ref name) => Path::new("profiles").join(name),
+        None if build_config.release => PathBuf::from("release"),
+        None => PathBuf::from("debug"),
+    };
     let root = try!(packages.get(resolve.root()));
     let host_layout = try!(Layout::new(config, root, None, &dest));
     let target_layout = match build_config.requested_target.as_ref() {
@@ -95,8 +116,14 @@ pub fn compile_targets<'a, 'cfg: 'a>(pkg_targets: &'a PackagesToBuild<'a>,
     let mut queue = JobQueue::new(&cx);
 
     try!(cx.prepare(root));
+    try!(cx.download_deps(&units));
     try!(custom_build::build_map(&mut cx, &units));
 
+    if cx.build_config.build_plan {
+        try!(build_plan::print(&cx, &units));
+        return Ok(cx.compilation)
+    }
+
     for unit in units.iter() {
         // Build up a list of pending jobs, each of which represent
         // compiling a particular package. No actual work is executed as
@@ -106,6 +133,11 @@ pub fn compile_targets<'a, 'cfg: 'a>(pkg_targets: &'a PackagesToBuild<'a>,
         try!(compile(&mut cx, &mut queue, unit));
     }
 
+    if cx.build_config.dry_run {
+        try!(queue.dry_run(cx.config));
+        return Ok(cx.compilation)
+    }
+
     // Now that we've figured out everything that we're going to do, do it!
     try!(queue.execute(cx.config));
 
@@ -116,6 +148,9 @@ pub fn compile_targets<'a, 'cfg: 'a>(pkg_targets: &'a PackagesToBuild<'a>,
           .or_insert(Vec::new())
           .push(("OUT_DIR".to_string(), out_dir));
 
+        // Checked units only leave metadata behind, nothing to run or link
+        if unit.profile.check { continue }
+
         for filename in try!(cx.target_filenames(unit)).iter() {
             let dst = cx.out_dir(unit).join(filename);
             if unit.profile.test {
@@ -189,9 +224,15 @@ fn compile<'a, 'cfg: 'a>(cx: &mut Context<'a, 'cfg>,
         let work = if unit.profile.doc {
             try!(rustdoc(cx, unit))
         } else {
+            try!(cx.check_strip(unit));
             try!(rustc(cx, unit))
         };
-        let dirty = work.then(dirty);
+        let mut dirty = work.then(dirty);
+        let mut fresh = fresh;
+        if cx.build_config.json_messages && !unit.profile.doc {
+            dirty = dirty.then(try!(artifact(cx, unit, false)));
+            fresh = fresh.then(try!(artifact(cx, unit, true)));
+        }
         (dirty, fresh, freshness)
     };
     try!(jobs.enqueue(cx, unit, Job::new(dirty, fresh), freshness));
@@ -209,16 +250,6 @@ fn rustc(cx: &mut Context, unit: &Unit) -> CargoResult<Work> {
     let mut rustc = try!(prepare_rustc(cx, crate_types, unit));
 
     let name = unit.pkg.name().to_string();
-    let is_path_source = unit.pkg.package_id().source_id().is_path();
-    let allow_warnings = unit.pkg.package_id() == cx.resolve.root() ||
-                         is_path_source;
-    if !allow_warnings {
-        if cx.config.rustc_info().cap_lints {
-            rustc.arg("--cap-lints").arg("allow");
-        } else {
-            rustc.arg("-Awarnings");
-        }
-    }
     let has_custom_args = unit.profile.rustc_args.is_some();
     let exec_engine = cx.exec_engine.clone();
 
@@ -234,7 +265,8 @@ fn rustc(cx: &mut Context, unit: &Unit) -> CargoResult<Work> {
     // don't pass the `-l` flags.
     let pass_l_flag = unit.target.is_lib() ||
                       !unit.pkg.targets().iter().any(|t| t.is_lib());
-    let do_rename = unit.target.allows_underscores() && !unit.profile.test;
+    let do_rename = unit.target.allows_underscores() && !unit.profile.test &&
+                    !unit.profile.check;
     let real_name = unit.target.name().to_string();
     let crate_name = unit.target.crate_name();
 
@@ -247,6 +279,14 @@ fn rustc(cx: &mut Context, unit: &Unit) -> CargoResult<Work> {
     let cwd = cx.config.cwd().to_path_buf();
 
     let rustflags = try!(cx.rustflags_args(unit));
+    let json_messages = cx.build_config.json_messages;
+    let package_id = unit.pkg.package_id().clone();
+    let target = unit.target.clone();
+    let incremental = if unit.profile.incremental {
+        Some(cx.layout(unit.pkg, unit.kind).proxy().incremental(unit.pkg))
+    } else {
+        None
+    };
 
     return Ok(Work::new(move |desc_tx| {
         // Only at runtime have we discovered what the extra -L and -l
@@ -270,13 +310,26 @@ fn rustc(cx: &mut Context, unit: &Unit) -> CargoResult<Work> {
             }
         }
 
+        // The directory for the incremental state is only created once
+        // something is compiled incrementally.
+        if let Some(ref incremental) = incremental {
+            try!(fs::create_dir_all(incremental));
+        }
+
         // Add the arguments from RUSTFLAGS
         rustc.args(&rustflags);
 
         desc_tx.send(rustc.to_string()).ok();
-        try!(exec_engine.exec(rustc).chain_error(|| {
-            human(format!("Could not compile `{}`.", name))
-        }));
+        if json_messages {
+            try!(exec_json(&**exec_engine, rustc, &package_id, &target)
+                     .chain_error(|| {
+                human(format!("Could not compile `{}`.", name))
+            }));
+        } else {
+            try!(exec_engine.exec(rustc).chain_error(|| {
+                human(format!("Could not compile `{}`.", name))
+            }));
+        }
 
         if do_rename && real_name != crate_name {
             let dst = root.join(&filenames[0]);
@@ -366,6 +419,17 @@ fn prepare_rustc(cx: &Context,
     build_base_args(cx, &mut base, unit, &crate_types);
     build_plugin_args(&mut base, cx, unit);
     try!(build_deps_args(&mut base, cx, unit));
+
+    let is_path_source = unit.pkg.package_id().source_id().is_path();
+    let allow_warnings = unit.pkg.package_id() == cx.resolve.root() ||
+                         is_path_source;
+    if !allow_warnings {
+        if cx.config.rustc_info().cap_lints {
+            base.arg("--cap-lints").arg("allow");
+        } else {
+            base.arg("-Awarnings");
+        }
+    }
     Ok(base)
 }
 
@@ -445,14 +509,82 @@ fn root_path(cx: &Context, unit: &Unit) -> PathBuf {
     }
 }
 
+// With `--message-format=json` the compiler's diagnostics are captured and
+// emitted again as messages of our own. Anything else it printed is passed on.
+fn exec_json(exec_engine: &ExecEngine,
+             rustc: CommandPrototype,
+             package_id: &PackageId,
+             target: &Target) -> Result<(), ProcessError> {
+    let cmd = rustc.to_string();
+    let (result, output) = match exec_engine.exec_with_output(rustc) {
+        Ok(output) => (Ok(()), output),
+        Err(e) => {
+            let output = match e.output {
+                Some(output) => output,
+                None => return Err(e),
+            };
+            // The error would otherwise repeat all of the JSON we emit below
+            let e = process_error(&format!("Process didn't exit successfully: \
+                                            `{}`", cmd),
+                                  None, e.exit.as_ref(), None);
+            (Err(e), output)
+        }
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for line in stderr.lines() {
+        match Json::from_str(line) {
+            Ok(message) => {
+                machine_message::emit(machine_message::FromCompiler {
+                    package_id: package_id,
+                    target: target,
+                    message: message,
+                });
+            }
+            Err(..) => { let _ = writeln!(io::stderr(), "{}", line); }
+        }
+    }
+    let _ = io::stdout().write_all(&output.stdout);
+    result
+}
+
+/// Returns the work which reports the files that compiling `unit` produced,
+/// when machine-readable messages were asked for.
+fn artifact(cx: &Context, unit: &Unit, fresh: bool) -> CargoResult<Work> {
+    let out_dir = cx.out_dir(unit);
+    let filenames = try!(cx.target_filenames(unit)).iter().map(|f| {
+        out_dir.join(f).display().to_string()
+    }).collect::<Vec<_>>();
+    let mut features = cx.resolve.features(unit.pkg.package_id()).map(|f| {
+        f.iter().cloned().collect::<Vec<_>>()
+    }).unwrap_or(Vec::new());
+    features.sort();
+    let package_id = unit.pkg.package_id().clone();
+    let target = unit.target.clone();
+    let profile = machine_message::ArtifactProfile::from(unit.profile);
+
+    Ok(Work::new(move |_| {
+        machine_message::emit(machine_message::Artifact {
+            package_id: &package_id,
+            target: &target,
+            profile: profile,
+            features: features,
+            filenames: filenames,
+            fresh: fresh,
+        });
+        Ok(())
+    }))
+}
+
 fn build_base_args(cx: &Context,
                    cmd: &mut CommandPrototype,
                    unit: &Unit,
                    crate_types: &[&str]) {
     let Profile {
-        opt_level, lto, codegen_units, ref rustc_args, debuginfo,
-        debug_assertions, rpath, test, doc: _doc, run_custom_build,
-        ref panic, rustdoc_args: _,
+        ref opt_level, lto, codegen_units, ref rustc_args, debuginfo,
+        debug_assertions, overflow_checks, rpath, incremental, ref strip,
+        test, doc: _doc, run_custom_build, check: _check,
+        build_override: _build_override, ref panic, rustdoc_args: _,
     } = *unit.profile;
     assert!(!run_custom_build);
 
@@ -463,6 +595,10 @@ fn build_base_args(cx: &Context,
 
     cmd.arg("--crate-name").arg(&unit.target.crate_name());
 
+    if cx.build_config.json_messages {
+        cmd.arg("--error-format").arg("json");
+    }
+
     for crate_type in crate_types.iter() {
         cmd.arg("--crate-type").arg(crate_type);
     }
@@ -475,7 +611,7 @@ fn build_base_args(cx: &Context,
         cmd.arg("-C").arg("prefer-dynamic");
     }
 
-    if opt_level != 0 {
+    if opt_level != "0" {
         cmd.arg("-C").arg(&format!("opt-level={}", opt_level));
     }
 
@@ -495,20 +631,40 @@ fn build_base_args(cx: &Context,
         }
     }
 
-    if debuginfo {
-        cmd.arg("-g");
+    match debuginfo {
+        Some(2) => { cmd.arg("-g"); }
+        Some(level) => { cmd.arg("-C").arg(format!("debuginfo={}", level)); }
+        None => {}
     }
 
     if let Some(ref args) = *rustc_args {
         cmd.args(args);
     }
 
-    if debug_assertions && opt_level > 0 {
+    if debug_assertions && opt_level != "0" {
         cmd.args(&["-C", "debug-assertions=on"]);
-    } else if !debug_assertions && opt_level == 0 {
+    } else if !debug_assertions && opt_level == "0" {
         cmd.args(&["-C", "debug-assertions=off"]);
     }
 
+    // rustc turns overflow checks on and off along with debug assertions
+    if overflow_checks != debug_assertions {
+        cmd.arg("-C").arg(format!("overflow-checks={}",
+                                  if overflow_checks {"on"} else {"off"}));
+    }
+
+    if let Some(ref strip) = *strip {
+        cmd.arg("-C").arg(format!("strip={}", strip));
+    }
+
+    if incremental {
+        let dir = cx.layout(unit.pkg, unit.kind).proxy()
+                    .incremental(unit.pkg).join(fingerprint::filename(unit));
+        let mut arg = OsString::from("incremental=");
+        arg.push(dir);
+        cmd.arg("-C").arg(arg);
+    }
+
     if test && unit.target.harness() {
         cmd.arg("--test");
     }
@@ -541,7 +697,11 @@ fn build_plugin_args(cmd: &mut CommandPrototype, cx: &Context, unit: &Unit) {
     }
 
     cmd.arg("--out-dir").arg(&cx.out_dir(unit));
-    cmd.arg("--emit=dep-info,link");
+    if unit.profile.check {
+        cmd.arg("--emit=dep-info,metadata");
+    } else {
+        cmd.arg("--emit=dep-info,link");
+    }
 
     if unit.kind == Kind::Target {
         opt(cmd, "--target", "", cx.requested_target().map(|s| s.as_ref()));
@@ -564,6 +724,13 @@ fn build_deps_args(cmd: &mut CommandPrototype, cx: &Context, unit: &Unit)
         deps.push(layout.deps());
         deps
     });
+    if unit.profile.check {
+        cmd.arg("-L").arg(&{
+            let mut check = OsString::from("dependency=");
+            check.push(layout.check());
+            check
+        });
+    }
 
     if unit.pkg.has_custom_build() {
         cmd.env("OUT_DIR", &layout.build_out(unit.pkg));
@@ -579,7 +746,7 @@ fn build_deps_args(cmd: &mut CommandPrototype, cx: &Context, unit: &Unit)
 
     fn link_to(cmd: &mut CommandPrototype, cx: &Context, unit: &Unit)
                -> CargoResult<()> {
-        let layout = cx.layout(unit.pkg, unit.kind);
+        let out_dir = cx.out_dir(unit);
 
         for filename in try!(cx.target_filenames(unit)) {
             if let Ok((prefix, suffix)) = cx.staticlib(unit.kind) {
@@ -590,9 +757,7 @@ fn build_deps_args(cmd: &mut CommandPrototype, cx: &Context, unit: &Unit)
             let mut v = OsString::new();
             v.push(&unit.target.crate_name());
             v.push("=");
-            v.push(layout.root());
-            v.push(&path::MAIN_SEPARATOR.to_string());
-            v.push(&filename);
+            v.push(&out_dir.join(&filename));
             cmd.arg("--extern").arg(&v);
         }
         Ok(())
@@ -0,0 +1,200 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json;
+
+use core::{Package, SourceId, GitReference};
+use ops;
+use sources::PathSource;
+use sources::directory::Checksum;
+use util::{self, CargoResult, ChainError, Config, Sha256, human};
+use util::paths;
+
+pub struct VendorOptions<'a> {
+    pub config: &'a Config,
+    /// Directory to place all vendored sources in.
+    pub destination: &'a Path,
+    /// Don't delete directories in `destination` which are no longer needed.
+    pub no_delete: bool,
+}
+
+/// Executes `cargo vendor`.
+///
+/// Every package in the resolved dependency graph which does not come from a
+/// path source is copied into `<destination>/<name>-<version>`, along with a
+/// `.cargo-checksum.json` listing the checksum of each file. Returns the
+/// configuration which must be added to `.cargo/config` to build from the
+/// vendored sources.
+pub fn vendor(manifest_path: &Path, opts: &VendorOptions) -> CargoResult<String> {
+    let config = opts.config;
+    let (resolve, packages) = try!(ops::fetch(manifest_path, config));
+
+    try!(fs::create_dir_all(opts.destination).chain_error(|| {
+        human(format!("failed to create vendor directory `{}`",
+                      opts.destination.display()))
+    }));
+
+    let mut vendored = BTreeMap::new();
+    let mut sources = HashSet::new();
+    for id in resolve.iter() {
+        if id.source_id().is_path() {
+            continue
+        }
+        let name = format!("{}-{}", id.name(), id.version());
+        if let Some(prev) = vendored.insert(name.clone(), id) {
+            bail!("found duplicate version of package `{} v{}` vendored \
+                   from two sources:\n\n\
+                   \tsource 1: {}\n\
+                   \tsource 2: {}",
+                  id.name(), id.version(), prev.source_id(), id.source_id())
+        }
+    }
+
+    for (name, &id) in vendored.iter() {
+        let pkg = try!(packages.get(id));
+        let dst = opts.destination.join(name);
+        try!(config.shell().verbose(|s| {
+            s.status("Vendoring", format!("{} to {}", id, dst.display()))
+        }));
+        let checksum = resolve.checksums().get(id).map(|s| &s[..]);
+        try!(vendor_package(pkg, checksum, &dst, config).chain_error(|| {
+            human(format!("failed to vendor `{}`", id))
+        }));
+        sources.insert(id.source_id().with_precise(None));
+    }
+
+    // Clean out anything left over from a previous vendoring which isn't part
+    // of the dependency graph any more. Only directories with a checksum file
+    // were created by us, anything else in the destination is left alone.
+    if !opts.no_delete {
+        for entry in try!(opts.destination.read_dir()) {
+            let entry = try!(entry);
+            let name = entry.file_name();
+            let name = name.to_str();
+            if name.map(|s| vendored.contains_key(s)) == Some(true) ||
+               name.map(|s| s.starts_with(".")) == Some(true) {
+                continue
+            }
+            let path = entry.path();
+            if try!(entry.file_type()).is_dir() &&
+               path.join(".cargo-checksum.json").exists() {
+                try!(fs::remove_dir_all(&path));
+            }
+        }
+    }
+
+    Ok(source_config(&sources, opts.destination, config))
+}
+
+fn vendor_package(pkg: &Package,
+                  checksum: Option<&str>,
+                  dst: &Path,
+                  config: &Config) -> CargoResult<()> {
+    // If this package is already being loaded out of the destination (e.g.
+    // the project is already configured to build from vendored sources) then
+    // there's nothing to copy, the checksums just get regenerated.
+    let src_root = pkg.root().to_path_buf();
+    let in_place = fs::canonicalize(&src_root).ok() ==
+                   fs::canonicalize(dst).ok();
+    if !in_place && dst.exists() {
+        try!(fs::remove_dir_all(dst));
+    }
+
+    let mut src = PathSource::new(&src_root, pkg.package_id().source_id(),
+                                  config);
+    try!(src.update());
+    let mut cksums = HashMap::new();
+    for file in try!(src.list_files(pkg)) {
+        let relative = util::without_prefix(&file, &src_root).unwrap();
+        let key = try!(relative.to_str().chain_error(|| {
+            human(format!("non-utf8 path in package: {}", file.display()))
+        }));
+        // Checksum files always use `/` as a separator so they're portable.
+        let key = key.replace("\\", "/");
+        let dst_file = dst.join(relative);
+        if !in_place {
+            try!(fs::create_dir_all(dst_file.parent().unwrap()));
+            try!(fs::copy(&file, &dst_file).chain_error(|| {
+                human(format!("failed to copy `{}` to `{}`",
+                              file.display(), dst_file.display()))
+            }));
+        }
+        cksums.insert(key, try!(cksum(&dst_file)));
+    }
+
+    let cksum = Checksum {
+        package: checksum.unwrap_or("").to_string(),
+        files: cksums,
+    };
+    let json = try!(json::encode(&cksum));
+    paths::write(&dst.join(".cargo-checksum.json"), json.as_bytes())
+}
+
+fn cksum(path: &Path) -> CargoResult<String> {
+    let mut f = try!(File::open(path));
+    let mut buf = [0; 16 * 1024];
+    let mut h = Sha256::new();
+    loop {
+        match try!(f.read(&mut buf)) {
+            0 => return Ok(h.finish().to_hex()),
+            n => h.update(&buf[..n]),
+        }
+    }
+}
+
+/// Generates the `[source]` configuration which replaces each of `sources`
+/// with the vendored directory.
+fn source_config(sources: &HashSet<SourceId>, dst: &Path, config: &Config)
+                 -> String {
+    let mut out = String::new();
+    let mut tables = BTreeMap::new();
+    for id in sources.iter() {
+        let mut table = String::new();
+        let name = if id.is_default_registry() {
+            "crates-io".to_string()
+        } else {
+            let url = id.url().to_string();
+            if id.is_git() {
+                table.push_str(&format!("git = \"{}\"\n", url));
+                match id.git_reference() {
+                    Some(&GitReference::Branch(ref b)) if b == "master" => {}
+                    Some(&GitReference::Branch(ref b)) => {
+                        table.push_str(&format!("branch = \"{}\"\n", b));
+                    }
+                    Some(&GitReference::Tag(ref t)) => {
+                        table.push_str(&format!("tag = \"{}\"\n", t));
+                    }
+                    Some(&GitReference::Rev(ref r)) => {
+                        table.push_str(&format!("rev = \"{}\"\n", r));
+                    }
+                    None => {}
+                }
+            } else if id.is_registry() {
+                table.push_str(&format!("registry = \"{}\"\n", url));
+            } else if id.is_local_registry() {
+                let path = id.url().to_file_path().unwrap();
+                table.push_str(&format!("local-registry = \"{}\"\n",
+                                        path.display()));
+            }
+            format!("\"{}\"", id.to_url())
+        };
+        table.push_str("replace-with = \"vendored-sources\"\n");
+        tables.insert(name, table);
+    }
+    for (name, table) in tables {
+        out.push_str(&format!("[source.{}]\n{}\n", name, table));
+    }
+
+    // Paths in configuration are relative to the directory containing the
+    // `.cargo` folder, so prefer to print a relative path if we can.
+    let dst = util::without_prefix(dst, config.cwd()).map(|p| {
+        p.to_path_buf()
+    }).unwrap_or(PathBuf::from(dst));
+    let dst = dst.display().to_string().replace("\\", "/");
+    out.push_str(&format!("[source.vendored-sources]\ndirectory = \"{}\"\n",
+                          dst));
+    out
+}
@@ -1,9 +1,10 @@
+use std::collections::BTreeSet;
 use std::io::prelude::*;
 
 use rustc_serialize::{Encodable, Decodable};
 use toml::{self, Encoder, Value};
 
-use core::{Resolve, resolver, Package};
+use core::{Resolve, resolver, Package, PackageId};
 use util::{CargoResult, ChainError, human, Config, Filesystem};
 use util::toml as cargo_toml;
 
@@ -84,6 +85,14 @@ pub fn write_pkg_lockfile(pkg: &Package,
         }
     }
 
+    if !config.lock_update_allowed() {
+        let prev = load_pkg_lockfile(pkg, config).ok().and_then(|r| r);
+        let changes = prev.map(|prev| describe_changes(&prev, resolve));
+        bail!("the lock file needs to be updated but {} was passed to \
+               prevent this{}", lock_flag(config),
+              changes.unwrap_or(String::new()))
+    }
+
     // Ok, if that didn't work just write it out
     root.open_rw("Cargo.lock", config, "Cargo.lock file").and_then(|mut f| {
         try!(f.file().set_len(0));
@@ -95,6 +104,60 @@ pub fn write_pkg_lockfile(pkg: &Package,
     })
 }
 
+/// Verifies that `resolve` is the same as `prev`, the resolve loaded from a
+/// lock file which isn't written back to (e.g. one shipped with a package from
+/// a registry), when `--locked` or `--frozen` is in effect.
+pub fn check_pkg_lockfile(prev: &Resolve,
+                          resolve: &Resolve,
+                          config: &Config) -> CargoResult<()> {
+    if config.lock_update_allowed() {
+        return Ok(())
+    }
+    let changes = describe_changes(prev, resolve);
+    if changes.is_empty() {
+        return Ok(())
+    }
+    bail!("the lock file of `{}` needs to be updated but {} was passed to \
+           prevent this{}", resolve.root(), lock_flag(config), changes)
+}
+
+fn lock_flag(config: &Config) -> &'static str {
+    if config.frozen() {"--frozen"} else {"--locked"}
+}
+
+/// Returns a human readable list of the packages which were added, removed or
+/// changed in `resolve` relative to `prev`, or an empty string if the same
+/// set of packages are in both.
+fn describe_changes(prev: &Resolve, resolve: &Resolve) -> String {
+    let old = prev.iter().collect::<BTreeSet<&PackageId>>();
+    let new = resolve.iter().collect::<BTreeSet<&PackageId>>();
+    let mut added = new.difference(&old).cloned().collect::<Vec<_>>();
+    let removed = old.difference(&new).cloned().collect::<Vec<_>>();
+
+    let mut lines = Vec::new();
+    let mut removed_lines = Vec::new();
+    for id in removed {
+        let update = added.iter().position(|a| {
+            a.name() == id.name() && a.source_id() == id.source_id()
+        });
+        match update {
+            Some(i) => {
+                let new_id = added.remove(i);
+                lines.push(format!("  changed {} v{} -> v{}", id.name(),
+                                   id.version(), new_id.version()));
+            }
+            None => removed_lines.push(format!("  removed {}", id)),
+        }
+    }
+    lines.extend(removed_lines);
+    lines.extend(added.iter().map(|id| format!("  added {}", id)));
+
+    if lines.is_empty() {
+        return String::new()
+    }
+    format!("\n\nchanges to the lock file:\n{}", lines.join("\n"))
+}
+
 fn has_crlf_line_endings(s: &str) -> bool {
     // Only check the first line.
     if let Some(lf) = s.find('\n') {
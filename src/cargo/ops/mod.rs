@@ -1,6 +1,8 @@
+pub use self::cargo_bundle::{bundle, import_bundle};
+pub use self::cargo_cache::{cache, CacheOptions};
 pub use self::cargo_clean::{clean, CleanOptions};
 pub use self::cargo_compile::{compile, compile_pkg, resolve_dependencies, CompileOptions};
-pub use self::cargo_compile::{CompileFilter, CompileMode};
+pub use self::cargo_compile::{CompileFilter, CompileMode, MessageFormat};
 pub use self::cargo_read_manifest::{read_manifest,read_package,read_packages};
 pub use self::cargo_rustc::{compile_targets, Compilation, Layout, Kind, Unit};
 pub use self::cargo_rustc::{Context, LayoutProxy};
@@ -14,16 +16,22 @@ pub use self::cargo_generate_lockfile::{generate_lockfile};
 pub use self::cargo_generate_lockfile::{update_lockfile};
 pub use self::cargo_generate_lockfile::UpdateOptions;
 pub use self::lockfile::{load_pkg_lockfile, write_pkg_lockfile};
+pub use self::lockfile::check_pkg_lockfile;
 pub use self::cargo_test::{run_tests, run_benches, TestOptions};
 pub use self::cargo_package::package;
 pub use self::registry::{publish, registry_configuration, RegistryConfig};
+pub use self::registry::{alt_registry_configuration, registry_mirrors};
+pub use self::registry::RegistryMirrors;
 pub use self::registry::{registry_login, search, http_proxy_exists, http_handle};
 pub use self::registry::{modify_owners, yank, OwnersOptions};
 pub use self::cargo_fetch::{fetch, get_resolved_packages};
+pub use self::cargo_vendor::{vendor, VendorOptions};
 pub use self::cargo_pkgid::pkgid;
 pub use self::resolve::{resolve_pkg, resolve_with_previous};
 pub use self::cargo_output_metadata::{output_metadata, OutputMetadataOptions, ExportInfo};
 
+mod cargo_bundle;
+mod cargo_cache;
 mod cargo_clean;
 mod cargo_compile;
 mod cargo_doc;
@@ -38,6 +46,7 @@ mod cargo_read_manifest;
 mod cargo_run;
 mod cargo_rustc;
 mod cargo_test;
+mod cargo_vendor;
 mod lockfile;
 mod registry;
 mod resolve;
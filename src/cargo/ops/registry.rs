@@ -33,6 +33,7 @@ pub fn publish(manifest_path: &Path,
                config: &Config,
                token: Option<String>,
                index: Option<String>,
+               registry_name: Option<String>,
                verify: bool) -> CargoResult<()> {
     let pkg = try!(Package::for_path(&manifest_path, config));
 
@@ -41,8 +42,9 @@ pub fn publish(manifest_path: &Path,
                `{}` is marked as unpublishable", pkg.name());
     }
 
-    let (mut registry, reg_id) = try!(registry(config, token, index));
-    try!(verify_dependencies(&pkg, &reg_id));
+    let (mut registry, reg_id) = try!(registry(config, token, index,
+                                               registry_name));
+    try!(verify_dependencies(&pkg, &reg_id, config));
 
     // Prepare a tarball, with a non-surpressable warning if metadata
     // is missing since this is being put online.
@@ -51,13 +53,14 @@ pub fn publish(manifest_path: &Path,
 
     // Upload said tarball to the specified destination
     try!(config.shell().status("Uploading", pkg.package_id().to_string()));
-    try!(transmit(&pkg, tarball.file(), &mut registry));
+    try!(transmit(&pkg, tarball.file(), &mut registry, &reg_id));
 
     Ok(())
 }
 
-fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
-                       -> CargoResult<()> {
+fn verify_dependencies(pkg: &Package, registry_src: &SourceId,
+                       config: &Config) -> CargoResult<()> {
+    let central = try!(SourceId::for_central(config));
     for dep in pkg.dependencies().iter() {
         if dep.source_id().is_path() {
             if dep.specified_req().is_none() {
@@ -65,6 +68,10 @@ fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
                        when publishing.\ndependency `{}` does not specify \
                        a version", dep.name())
             }
+        } else if dep.source_id().is_registry() && *registry_src != central {
+            // Crates in alternate registries may depend on crates in any other
+            // registry, but crates.io only allows crates.io dependencies.
+            continue
         } else if dep.source_id() != registry_src {
             bail!("all dependencies must come from the same source.\n\
                    dependency `{}` comes from {} instead",
@@ -74,9 +81,19 @@ fn verify_dependencies(pkg: &Package, registry_src: &SourceId)
     Ok(())
 }
 
-fn transmit(pkg: &Package, tarball: &File, registry: &mut Registry)
-            -> CargoResult<()> {
+fn transmit(pkg: &Package, tarball: &File, registry: &mut Registry,
+            registry_id: &SourceId) -> CargoResult<()> {
     let deps = pkg.dependencies().iter().map(|dep| {
+        // Dependencies from other registries are identified by the URL of
+        // their index, which `registry = "<name>"` in the manifest was resolved
+        // to through the `[registries]` configuration. Dependencies in the
+        // registry being published to don't mention it at all.
+        let dep_registry = if dep.source_id().is_registry() &&
+                              dep.source_id() != registry_id {
+            Some(dep.source_id().url().to_string())
+        } else {
+            None
+        };
         NewCrateDependency {
             optional: dep.is_optional(),
             default_features: dep.uses_default_features(),
@@ -89,6 +106,7 @@ fn transmit(pkg: &Package, tarball: &File, registry: &mut Registry)
                 Kind::Build => "build",
                 Kind::Development => "dev",
             }.to_string(),
+            registry: dep_registry,
         }
     }).collect::<Vec<NewCrateDependency>>();
     let manifest = pkg.manifest();
@@ -133,20 +151,108 @@ pub fn registry_configuration(config: &Config) -> CargoResult<RegistryConfig> {
     Ok(RegistryConfig { index: index, token: token })
 }
 
+/// Loads the configuration of the registry named `name`, which is defined in
+/// `.cargo/config` like so:
+///
+/// ```toml
+/// [registries.my-registry]
+/// index = "https://my-intranet:8080/index"
+/// token = "..."
+/// ```
+pub fn alt_registry_configuration(config: &Config, name: &str)
+                                  -> CargoResult<RegistryConfig> {
+    let key = format!("registries.{}", name);
+    let index = try!(config.get_string(&format!("{}.index", key)));
+    let token = try!(config.get_string(&format!("{}.token", key)));
+    Ok(RegistryConfig {
+        index: index.map(|p| p.val),
+        token: token.map(|p| p.val),
+    })
+}
+
+/// Mirrors of a registry, tried in order when a network error occurs while
+/// talking to the registry itself.
+#[derive(Default)]
+pub struct RegistryMirrors {
+    /// URLs of copies of the git index.
+    pub index: Vec<String>,
+    /// Download endpoints for tarballs, just like the `dl` key of the index's
+    /// `config.json`.
+    pub dl: Vec<String>,
+}
+
+/// Loads the mirrors of the registry `source_id` from the table configuring
+/// it in `.cargo/config`, like so:
+///
+/// ```toml
+/// [registry]
+/// mirrors = ["https://mirror.example.com/crates.io-index"]
+/// dl-mirrors = ["https://mirror.example.com/api/v1/crates"]
+/// ```
+///
+/// Mirrors only change where data is fetched from, packages are still
+/// identified by the URL of the registry itself.
+pub fn registry_mirrors(config: &Config, source_id: &SourceId)
+                        -> CargoResult<RegistryMirrors> {
+    let key = if *source_id.url() == try!(RegistrySource::url(config)) {
+        "registry".to_string()
+    } else {
+        let names = match try!(config.get_table("registries")) {
+            Some(table) => table.val.keys().cloned().collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        let mut key = None;
+        for name in names {
+            if *source_id.url() == try!(RegistrySource::alt_url(config, &name)) {
+                key = Some(format!("registries.{}", name));
+                break
+            }
+        }
+        match key {
+            Some(key) => key,
+            None => return Ok(RegistryMirrors::default()),
+        }
+    };
+    let list = |name: &str| -> CargoResult<Vec<String>> {
+        let list = try!(config.get_list(&format!("{}.{}", key, name)));
+        Ok(list.map(|l| l.val.into_iter().map(|s| s.0).collect())
+               .unwrap_or(Vec::new()))
+    };
+    Ok(RegistryMirrors {
+        index: try!(list("mirrors")),
+        dl: try!(list("dl-mirrors")),
+    })
+}
+
 pub fn registry(config: &Config,
                 token: Option<String>,
-                index: Option<String>) -> CargoResult<(Registry, SourceId)> {
+                index: Option<String>,
+                registry: Option<String>) -> CargoResult<(Registry, SourceId)> {
     // Parse all configuration options
-    let RegistryConfig {
-        token: token_config,
-        index: index_config,
-    } = try!(registry_configuration(config));
-    let token = token.or(token_config);
-    let index = index.or(index_config).unwrap_or(RegistrySource::default_url());
+    let (index, token) = match registry {
+        Some(ref name) if name != "crates-io" => {
+            if index.is_some() {
+                bail!("the `--registry` flag cannot be combined with `--index` \
+                       or `--host`")
+            }
+            let index = try!(RegistrySource::alt_url(config, name));
+            let token_config = try!(alt_registry_configuration(config, name));
+            (index.to_string(), token.or(token_config.token))
+        }
+        _ => {
+            let RegistryConfig {
+                token: token_config,
+                index: index_config,
+            } = try!(registry_configuration(config));
+            let index = index.or(index_config)
+                             .unwrap_or(RegistrySource::default_url());
+            (index, token.or(token_config))
+        }
+    };
     let index = try!(index.to_url().map_err(human));
     let sid = SourceId::for_registry(&index);
     let api_host = {
-        let mut src = RegistrySource::new(&sid, config);
+        let mut src = RegistrySource::remote(&sid, config);
         try!(src.update().chain_error(|| {
             human(format!("failed to update registry {}", index))
         }));
@@ -158,6 +264,11 @@ pub fn registry(config: &Config,
 
 /// Create a new HTTP handle with appropriate global configuration for cargo.
 pub fn http_handle(config: &Config) -> CargoResult<Easy> {
+    if !config.network_allowed() {
+        bail!("attempting to make an HTTP request, but the network is \
+               disabled (--offline/--frozen)")
+    }
+
     // The timeout option for libcurl by default times out the entire transfer,
     // but we probably don't want this. Instead we only set timeouts for the
     // connect phase as well as a "low speed" timeout so if we don't receive
@@ -244,6 +355,7 @@ pub struct OwnersOptions {
     pub krate: Option<String>,
     pub token: Option<String>,
     pub index: Option<String>,
+    pub registry: Option<String>,
     pub to_add: Option<Vec<String>>,
     pub to_remove: Option<Vec<String>>,
     pub list: bool,
@@ -260,7 +372,8 @@ pub fn modify_owners(config: &Config, opts: &OwnersOptions) -> CargoResult<()> {
     };
 
     let (mut registry, _) = try!(registry(config, opts.token.clone(),
-                                          opts.index.clone()));
+                                          opts.index.clone(),
+                                          opts.registry.clone()));
 
     match opts.to_add {
         Some(ref v) => {
@@ -309,6 +422,7 @@ pub fn yank(config: &Config,
             version: Option<String>,
             token: Option<String>,
             index: Option<String>,
+            registry_name: Option<String>,
             undo: bool) -> CargoResult<()> {
     let name = match krate {
         Some(name) => name,
@@ -323,7 +437,8 @@ pub fn yank(config: &Config,
         None => bail!("a version must be specified to yank")
     };
 
-    let (mut registry, _) = try!(registry(config, token, index));
+    let (mut registry, _) = try!(registry(config, token, index,
+                                          registry_name));
 
     if undo {
         try!(config.shell().status("Unyank", format!("{}:{}", name, version)));
@@ -343,6 +458,7 @@ pub fn yank(config: &Config,
 pub fn search(query: &str,
               config: &Config,
               index: Option<String>,
+              registry_name: Option<String>,
               limit: u8) -> CargoResult<()> {
     fn truncate_with_ellipsis(s: &str, max_length: usize) -> String {
         if s.len() < max_length {
@@ -352,7 +468,8 @@ pub fn search(query: &str,
         }
     }
 
-    let (mut registry, _) = try!(registry(config, None, index));
+    let (mut registry, _) = try!(registry(config, None, index,
+                                          registry_name));
     let (crates, total_crates) = try!(registry.search(query, limit).map_err(|e| {
         human(format!("failed to retrieve search results from the registry: {}", e))
     }));
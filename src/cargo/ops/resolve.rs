@@ -21,6 +21,10 @@ pub fn resolve_pkg(registry: &mut PackageRegistry,
                                              prev.as_ref(), None));
     if package.package_id().source_id().is_path() {
         try!(ops::write_pkg_lockfile(package, &resolve, config));
+    } else if let Some(ref prev) = prev {
+        // Packages from elsewhere (e.g. `cargo install` from a registry) never
+        // have their lock file rewritten, but it still must be honored.
+        try!(ops::check_pkg_lockfile(prev, &resolve, config));
     }
     Ok(resolve)
 }
@@ -121,7 +125,7 @@ pub fn resolve_with_previous<'a>(registry: &mut PackageRegistry,
     let mut resolved = try!(resolver::resolve(&summary, &method, &replace,
                                               registry));
     if let Some(previous) = previous {
-        resolved.copy_metadata(previous);
+        try!(resolved.merge_from(previous));
     }
     return Ok(resolved);
 
@@ -0,0 +1,227 @@
+//! Implementation of configuration for various sources
+//!
+//! This module will parse the various `source.*` TOML configuration keys into a
+//! structure usable by Cargo itself. Currently this is primarily used to map
+//! sources to one another via the `replace-with` key in `.cargo/config`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use url::Url;
+
+use core::{Source, SourceId, GitReference};
+use sources::ReplacedSource;
+use util::{CargoResult, Config, ChainError, human, ToUrl};
+use util::config::ConfigValue;
+
+pub struct SourceConfigMap<'cfg> {
+    cfgs: HashMap<String, SourceConfig>,
+    id2name: HashMap<SourceId, String>,
+    config: &'cfg Config,
+}
+
+/// Configuration for a particular source, found in TOML looking like:
+///
+/// ```toml
+/// [source.crates-io]
+/// registry = 'https://github.com/rust-lang/crates.io-index'
+/// replace-with = 'foo'    # optional
+/// ```
+struct SourceConfig {
+    // id this source corresponds to, inferred from the various defined keys in
+    // the configuration
+    id: SourceId,
+
+    // Name of the source that this source should be replaced with. This field
+    // is a tuple of (name, path) where path is where this configuration key was
+    // defined (the literal `.cargo/config` file).
+    replace_with: Option<(String, PathBuf)>,
+}
+
+impl<'cfg> SourceConfigMap<'cfg> {
+    pub fn new(config: &'cfg Config) -> CargoResult<SourceConfigMap<'cfg>> {
+        let mut base = try!(SourceConfigMap::empty(config));
+        if let Some(table) = try!(config.get_table("source")) {
+            for (key, value) in table.val.iter() {
+                try!(base.add_config(key, value));
+            }
+        }
+        Ok(base)
+    }
+
+    pub fn empty(config: &'cfg Config) -> CargoResult<SourceConfigMap<'cfg>> {
+        let mut base = SourceConfigMap {
+            cfgs: HashMap::new(),
+            id2name: HashMap::new(),
+            config: config,
+        };
+        base.add("crates-io", SourceConfig {
+            id: try!(SourceId::for_central(config)),
+            replace_with: None,
+        });
+        Ok(base)
+    }
+
+    pub fn config(&self) -> &'cfg Config {
+        self.config
+    }
+
+    /// Creates the `Source` which should be used to load packages for `id`.
+    ///
+    /// If `id` has been replaced (transitively) through `replace-with` then
+    /// the returned source will load packages from the replacement, but all
+    /// packages and summaries it returns will still be listed as coming from
+    /// `id`. This way the lock file is unaffected by the replacement.
+    pub fn load(&self, id: &SourceId) -> CargoResult<Box<Source + 'cfg>> {
+        debug!("loading: {}", id);
+        match try!(self.replacement(id)) {
+            Some(new_id) => {
+                let new_src = new_id.load(self.config);
+                Ok(Box::new(ReplacedSource::new(id, &new_id, new_src)))
+            }
+            None => Ok(id.load(self.config)),
+        }
+    }
+
+    /// Returns the source which packages for `id` are actually loaded from
+    /// if it has been replaced (transitively) through `replace-with`.
+    pub fn replacement(&self, id: &SourceId) -> CargoResult<Option<SourceId>> {
+        let mut name = match self.id2name.get(id) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let mut path = Path::new("/");
+        let orig_name = name;
+        let mut visited = HashSet::new();
+        loop {
+            if !visited.insert(name) {
+                bail!("detected a cycle of `replace-with` sources, the source \
+                       `{}` is eventually replaced with itself \
+                       (configuration in `{}`)", name, path.display())
+            }
+            let cfg = match self.cfgs.get(name) {
+                Some(cfg) => cfg,
+                None => bail!("could not find a configured source with the \
+                               name `{}` when attempting to lookup `{}` \
+                               (configuration in `{}`)",
+                              name, orig_name, path.display()),
+            };
+            match cfg.replace_with {
+                Some((ref s, ref p)) => {
+                    name = s;
+                    path = p.as_path();
+                }
+                None if *id == cfg.id => return Ok(None),
+                None => {
+                    return Ok(Some(cfg.id.with_precise(id.precise().map(|s| {
+                        s.to_string()
+                    }))))
+                }
+            }
+            debug!("following pointer to {}", name);
+        }
+    }
+
+    fn add(&mut self, name: &str, cfg: SourceConfig) {
+        self.id2name.insert(cfg.id.clone(), name.to_string());
+        self.cfgs.insert(name.to_string(), cfg);
+    }
+
+    fn add_config(&mut self, name: &str, cfg: &ConfigValue) -> CargoResult<()> {
+        let (table, _path) = try!(get(cfg.table(), &format!("source.{}", name)));
+        let mut srcs = Vec::new();
+        if let Some(val) = table.get("registry") {
+            let url = try!(url(val, &format!("source.{}.registry", name)));
+            srcs.push(SourceId::for_registry(&url));
+        }
+        if let Some(val) = table.get("local-registry") {
+            let path = try!(path(val, &format!("source.{}.local-registry",
+                                               name)));
+            srcs.push(try!(SourceId::for_local_registry(&path)));
+        }
+        if let Some(val) = table.get("directory") {
+            let path = try!(path(val, &format!("source.{}.directory", name)));
+            srcs.push(try!(SourceId::for_directory(&path)));
+        }
+        if let Some(val) = table.get("git") {
+            let url = try!(url(val, &format!("source.{}.git", name)));
+            let mut references = Vec::new();
+            if let Some(val) = table.get("branch") {
+                let key = format!("source.{}.branch", name);
+                let (s, _) = try!(get(val.string(), &key));
+                references.push(GitReference::Branch(s.to_string()));
+            }
+            if let Some(val) = table.get("tag") {
+                let key = format!("source.{}.tag", name);
+                let (s, _) = try!(get(val.string(), &key));
+                references.push(GitReference::Tag(s.to_string()));
+            }
+            if let Some(val) = table.get("rev") {
+                let key = format!("source.{}.rev", name);
+                let (s, _) = try!(get(val.string(), &key));
+                references.push(GitReference::Rev(s.to_string()));
+            }
+            if references.len() > 1 {
+                bail!("more than one of `branch`, `tag` or `rev` specified \
+                       for `source.{}`", name)
+            }
+            let reference = references.pop().unwrap_or_else(|| {
+                GitReference::Branch("master".to_string())
+            });
+            srcs.push(SourceId::for_git(&url, reference));
+        }
+
+        let mut srcs = srcs.into_iter();
+        let src = match srcs.next() {
+            Some(src) => src,
+            // The crates.io source is always known, so it only needs to be
+            // listed to configure a replacement for it.
+            None if name == "crates-io" => try!(SourceId::for_central(self.config)),
+            None => bail!("no source URL specified for `source.{}`, need \
+                           either `registry`, `local-registry`, `directory` \
+                           or `git` defined", name),
+        };
+        if srcs.next().is_some() {
+            bail!("more than one source URL specified for `source.{}`", name)
+        }
+
+        let mut replace_with = None;
+        if let Some(val) = table.get("replace-with") {
+            let key = format!("source.{}.replace-with", name);
+            let (s, path) = try!(get(val.string(), &key));
+            replace_with = Some((s.to_string(), path.to_path_buf()));
+        }
+
+        self.add(name, SourceConfig {
+            id: src,
+            replace_with: replace_with,
+        });
+
+        return Ok(());
+
+        fn get<T>(val: CargoResult<T>, key: &str) -> CargoResult<T> {
+            val.chain_error(|| {
+                human(format!("invalid configuration for key `{}`", key))
+            })
+        }
+
+        fn url(cfg: &ConfigValue, key: &str) -> CargoResult<Url> {
+            let (url, path) = try!(get(cfg.string(), key));
+            url.to_url().map_err(human).chain_error(|| {
+                human(format!("configuration key `{}` specified an invalid \
+                               URL (in {})", key, path.display()))
+            })
+        }
+
+        // Paths are relative to the directory containing the `.cargo` folder
+        // of the configuration file they were defined in.
+        fn path(cfg: &ConfigValue, key: &str) -> CargoResult<PathBuf> {
+            let (s, path) = try!(get(cfg.string(), key));
+            let mut path = path.to_path_buf();
+            path.pop();
+            path.pop();
+            path.push(s);
+            Ok(path)
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! A `Source` for a directory of unpacked crates.
+//!
+//! A directory source is a flat folder where each entry is the unpacked
+//! contents of one crate, as it would be after being downloaded from a
+//! registry:
+//!
+//! ```notrust
+//! vendor/
+//!     libc-0.2.11/
+//!         .cargo-checksum.json
+//!         Cargo.toml
+//!         src/...
+//!     url-1.1.0/
+//!         .cargo-checksum.json
+//!         Cargo.toml
+//!         src/...
+//! ```
+//!
+//! Each crate carries a `.cargo-checksum.json` file listing the SHA-256 of
+//! every file in the crate (keyed by its path relative to the crate root) as
+//! well as the checksum of the original `.crate` tarball, if known:
+//!
+//! ```notrust
+//! {"files":{"Cargo.toml":"<sha256>","src/lib.rs":"<sha256>"},"package":"<sha256>"}
+//! ```
+//!
+//! These checksums are verified each time a package is handed out of this
+//! source through `download`, so any accidental edits to the contents of the
+//! directory are caught before they're compiled. The checksum of the tarball
+//! is what's recorded in `Cargo.lock`, so it's used to verify that a vendored
+//! crate is the same one that was originally locked.
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json;
+
+use core::{Package, PackageId, Summary, SourceId, Source, Dependency, Registry};
+use sources::PathSource;
+use util::{CargoResult, human, ChainError, Config, Sha256};
+use util::paths;
+
+pub struct DirectorySource<'cfg> {
+    id: SourceId,
+    root: PathBuf,
+    packages: HashMap<PackageId, (Package, Checksum)>,
+    config: &'cfg Config,
+}
+
+#[derive(RustcDecodable, RustcEncodable)]
+pub struct Checksum {
+    /// Checksum of the original `.crate` tarball this crate was unpacked from,
+    /// or the empty string if it is unknown.
+    pub package: String,
+
+    /// Map of the relative path of each file in the crate to its checksum.
+    pub files: HashMap<String, String>,
+}
+
+impl<'cfg> DirectorySource<'cfg> {
+    pub fn new(path: &Path, id: &SourceId, config: &'cfg Config)
+               -> DirectorySource<'cfg> {
+        DirectorySource {
+            id: id.clone(),
+            root: path.to_path_buf(),
+            config: config,
+            packages: HashMap::new(),
+        }
+    }
+
+    /// Verifies that the files of `pkg` on disk match those listed in its
+    /// checksum file.
+    fn verify(&self, pkg: &Package, cksum: &Checksum) -> CargoResult<()> {
+        let mut buf = [0; 16 * 1024];
+        for (file, expected) in cksum.files.iter() {
+            let file = pkg.root().join(file);
+            let mut h = Sha256::new();
+            try!((|| -> CargoResult<()> {
+                let mut f = try!(File::open(&file));
+                loop {
+                    match try!(f.read(&mut buf)) {
+                        0 => return Ok(()),
+                        n => h.update(&buf[..n]),
+                    }
+                }
+            }).chain_error(|| {
+                human(format!("failed to calculate checksum of: {}",
+                              file.display()))
+            }));
+
+            let actual = h.finish().to_hex();
+            if actual != *expected {
+                bail!("the listed checksum of `{}` has changed:\n\
+                       expected: {}\n\
+                       actual:   {}\n\
+                       \n\
+                       directory sources are not intended to be edited, if \
+                       modifications are required then it is recommended \
+                       that [replace] is used with a forked copy of the \
+                       source", file.display(), expected, actual);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'cfg> Debug for DirectorySource<'cfg> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "DirectorySource {{ root: {:?} }}", self.root)
+    }
+}
+
+impl<'cfg> Registry for DirectorySource<'cfg> {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let packages = self.packages.values().map(|p| &p.0);
+        let matches = packages.filter(|pkg| dep.matches(pkg.summary()));
+        let summaries = matches.map(|pkg| pkg.summary().clone());
+        Ok(summaries.collect())
+    }
+}
+
+impl<'cfg> Source for DirectorySource<'cfg> {
+    fn update(&mut self) -> CargoResult<()> {
+        self.packages.clear();
+        let entries = try!(self.root.read_dir().chain_error(|| {
+            human(format!("failed to read root of directory source: {}",
+                          self.root.display()))
+        }));
+
+        for entry in entries {
+            let entry = try!(entry);
+            let path = entry.path();
+
+            // Skip anything that's not an unpacked crate, such as dotfiles
+            // left behind by version control systems.
+            if !path.is_dir() {
+                continue
+            }
+            if let Some(s) = path.file_name().and_then(|s| s.to_str()) {
+                if s.starts_with(".") {
+                    continue
+                }
+            }
+
+            let mut src = PathSource::new(&path, &self.id, self.config);
+            try!(src.update());
+            let mut pkg = try!(src.root_package());
+
+            let cksum_file = path.join(".cargo-checksum.json");
+            let cksum = try!(paths::read(&cksum_file).chain_error(|| {
+                human(format!("failed to load checksum `.cargo-checksum.json` \
+                               of {} v{}",
+                              pkg.package_id().name(),
+                              pkg.package_id().version()))
+            }));
+            let cksum: Checksum = try!(json::decode(&cksum).chain_error(|| {
+                human(format!("failed to decode `.cargo-checksum.json` of \
+                               {} v{}",
+                              pkg.package_id().name(),
+                              pkg.package_id().version()))
+            }));
+
+            // The checksum of the original tarball is what's recorded in lock
+            // files, so make sure it's carried along with the package.
+            if !cksum.package.is_empty() {
+                pkg.manifest_mut().summary_mut()
+                   .set_checksum(cksum.package.clone());
+            }
+            self.packages.insert(pkg.package_id().clone(), (pkg, cksum));
+        }
+
+        Ok(())
+    }
+
+    fn download(&mut self, id: &PackageId) -> CargoResult<Package> {
+        let &(ref pkg, ref cksum) = try!(self.packages.get(id).chain_error(|| {
+            human(format!("failed to find package with id: {}", id))
+        }));
+        try!(self.verify(pkg, cksum));
+        Ok(pkg.clone())
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        Ok(pkg.package_id().version().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs::{self, File};
+    use std::io::prelude::*;
+    use std::path::Path;
+
+    use rustc_serialize::hex::ToHex;
+    use rustc_serialize::json;
+    use tempdir::TempDir;
+
+    use core::{Dependency, Registry, Source, SourceId};
+    use util::{Config, Sha256};
+    use super::{Checksum, DirectorySource};
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        File::create(path).unwrap().write_all(contents.as_bytes()).unwrap();
+    }
+
+    fn cksum(contents: &str) -> String {
+        let mut h = Sha256::new();
+        h.update(contents.as_bytes());
+        h.finish().to_hex()
+    }
+
+    fn vendor(root: &Path, name: &str, version: &str) {
+        let dir = root.join(format!("{}-{}", name, version));
+        let manifest = format!("[package]\nname = \"{}\"\n\
+                                version = \"{}\"\nauthors = []\n",
+                               name, version);
+        let lib = "pub fn f() {}";
+        write(&dir.join("Cargo.toml"), &manifest);
+        write(&dir.join("src/lib.rs"), lib);
+        let mut files = HashMap::new();
+        files.insert("Cargo.toml".to_string(), cksum(&manifest));
+        files.insert("src/lib.rs".to_string(), cksum(lib));
+        let cksum = Checksum { package: String::new(), files: files };
+        let cksum = json::encode(&cksum).unwrap();
+        write(&dir.join(".cargo-checksum.json"), &cksum);
+    }
+
+    #[test]
+    fn loads_and_verifies_packages() {
+        let td = TempDir::new("cargo-directory-source").unwrap();
+        vendor(td.path(), "foo", "0.1.0");
+        vendor(td.path(), "bar", "0.2.0");
+        fs::create_dir(td.path().join(".git")).unwrap();
+
+        let config = Config::default().unwrap();
+        let id = SourceId::for_directory(td.path()).unwrap();
+        let mut src = DirectorySource::new(td.path(), &id, &config);
+        src.update().unwrap();
+
+        let dep = Dependency::parse("foo", Some("0.1"), &id).unwrap();
+        let summaries = src.query(&dep).unwrap();
+        assert_eq!(summaries.len(), 1);
+        let pkg_id = summaries[0].package_id().clone();
+        assert_eq!(pkg_id.source_id(), &id);
+        src.download(&pkg_id).unwrap();
+
+        let dep = Dependency::parse("baz", None, &id).unwrap();
+        assert!(src.query(&dep).unwrap().is_empty());
+
+        // edits to a vendored crate are caught before it's handed out
+        write(&td.path().join("foo-0.1.0/src/lib.rs"), "pub fn g() {}");
+        let err = src.download(&pkg_id).err().unwrap().to_string();
+        assert!(err.contains("the listed checksum of"), "{}", err);
+    }
+
+    #[test]
+    fn missing_checksum_file() {
+        let td = TempDir::new("cargo-directory-source").unwrap();
+        vendor(td.path(), "foo", "0.1.0");
+        fs::remove_file(td.path().join("foo-0.1.0/.cargo-checksum.json"))
+           .unwrap();
+
+        let config = Config::default().unwrap();
+        let id = SourceId::for_directory(td.path()).unwrap();
+        let mut src = DirectorySource::new(td.path(), &id, &config);
+        let err = src.update().err().unwrap().to_string();
+        assert_eq!(err, "failed to load checksum `.cargo-checksum.json` of \
+                         foo v0.1.0");
+    }
+}
@@ -1,4 +1,5 @@
-pub use self::utils::{GitRemote, GitDatabase, GitCheckout, GitRevision, fetch};
-pub use self::source::{GitSource, canonicalize_url};
+pub use self::utils::{GitRemote, GitDatabase, GitCheckout, GitRevision, fetch,
+                      fetch_local};
+pub use self::source::{GitSource, canonicalize_url, ident};
 mod utils;
 mod source;
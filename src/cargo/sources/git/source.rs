@@ -6,7 +6,7 @@ use url::Url;
 use core::source::{Source, SourceId};
 use core::GitReference;
 use core::{Package, PackageId, Summary, Registry, Dependency};
-use util::{CargoResult, Config, FileLock, to_hex};
+use util::{CargoResult, Config, FileLock, last_use, to_hex};
 use sources::PathSource;
 use sources::git::utils::{GitRemote, GitRevision};
 
@@ -58,7 +58,9 @@ impl<'cfg> GitSource<'cfg> {
     }
 }
 
-fn ident(url: &Url) -> String {
+/// Returns the name of the directories holding the database and checkouts of
+/// the git repository at `url`.
+pub fn ident(url: &Url) -> String {
     let mut hasher = SipHasher::new_with_keys(0,0);
 
     let url = canonicalize_url(url);
@@ -157,15 +159,42 @@ impl<'cfg> Source for GitSource<'cfg> {
         let should_update = actual_rev.is_err() ||
                             self.source_id.precise().is_none();
 
+        // When offline we make do with whatever revision is already in the
+        // database, even if it's out of date.
+        let should_update = if self.config.network_allowed() {
+            should_update
+        } else if actual_rev.is_err() {
+            bail!("cannot fetch git repository `{}` because the network is \
+                   disabled (--offline/--frozen), and it has not been \
+                   downloaded before", self.remote.url())
+        } else {
+            false
+        };
+
         let (repo, actual_rev) = if should_update {
             try!(self.config.shell().status("Updating",
                 format!("git repository `{}`", self.remote.url())));
 
             trace!("updating git source `{:?}`", self.remote);
 
-            let repo = try!(self.remote.checkout(&db_path, &self.config));
-            let rev = try!(repo.rev_for(&self.reference));
-            (repo, rev)
+            // Sources pinned to a tag or a revision (including the precise
+            // one from a lock file) only fetch that history at first. If the
+            // revision we're after isn't part of it (say the tag was moved
+            // since it was locked) then everything is fetched instead.
+            let narrow = match self.reference {
+                GitReference::Tag(..) | GitReference::Rev(..) => {
+                    Some(&self.reference)
+                }
+                GitReference::Branch(..) => None,
+            };
+            let mut repo = try!(self.remote.checkout(&db_path, narrow,
+                                                     &self.config));
+            let mut rev = repo.rev_for(&self.reference);
+            if rev.is_err() && narrow.is_some() {
+                repo = try!(self.remote.checkout(&db_path, None, &self.config));
+                rev = repo.rev_for(&self.reference);
+            }
+            (repo, try!(rev))
         } else {
             (try!(self.remote.db_at(&db_path)), actual_rev.unwrap())
         };
@@ -173,7 +202,9 @@ impl<'cfg> Source for GitSource<'cfg> {
         // Copy the database to the checkout location. After this we could drop
         // the lock on the database as we no longer needed it, but we leave it
         // in scope so the destructors here won't tamper with too much.
-        try!(repo.copy_to(actual_rev.clone(), &checkout_path, &self.config));
+        try!(repo.copy_to(actual_rev.clone(), &checkout_path,
+                          self.source_id.submodules(), &self.config));
+        try!(last_use::record(&checkout_path));
 
         let source_id = self.source_id.with_precise(Some(actual_rev.to_string()));
         let path_source = PathSource::new_recursive(&checkout_path,
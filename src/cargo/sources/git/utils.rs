@@ -109,16 +109,25 @@ impl GitRemote {
         db.rev_for(reference)
     }
 
-    pub fn checkout(&self, into: &Path, cargo_config: &Config) -> CargoResult<GitDatabase> {
+    /// Fetches the remote into the database at `into`, cloning it if needed.
+    ///
+    /// If a `reference` is given only what's needed for it is fetched, if
+    /// possible, rather than every branch of the remote. Tags and full commit
+    /// hashes can be fetched on their own, the latter only if the server
+    /// allows it.
+    pub fn checkout(&self, into: &Path, reference: Option<&GitReference>,
+                    cargo_config: &Config) -> CargoResult<GitDatabase> {
         let repo = match git2::Repository::open(into) {
             Ok(repo) => {
-                try!(self.fetch_into(&repo, &cargo_config).chain_error(|| {
+                try!(self.fetch_into(&repo, reference, &cargo_config)
+                         .chain_error(|| {
                     human(format!("failed to fetch into {}", into.display()))
                 }));
                 repo
             }
             Err(..) => {
-                try!(self.clone_into(into, &cargo_config).chain_error(|| {
+                try!(self.clone_into(into, reference, &cargo_config)
+                         .chain_error(|| {
                     human(format!("failed to clone into: {}", into.display()))
                 }))
             }
@@ -140,21 +149,46 @@ impl GitRemote {
         })
     }
 
-    fn fetch_into(&self, dst: &git2::Repository, cargo_config: &Config) -> CargoResult<()> {
+    fn fetch_into(&self, dst: &git2::Repository,
+                  reference: Option<&GitReference>,
+                  cargo_config: &Config) -> CargoResult<()> {
         // Create a local anonymous remote in the repository to fetch the url
         let url = self.url.to_string();
+
+        // A tag or a revision only needs the history leading up to it, which
+        // for large repositories can be far smaller than that of every
+        // branch. Only full commit hashes can be asked for by revision, and
+        // not every server allows that, so fall back to fetching everything.
+        let refspec = match reference {
+            Some(&GitReference::Tag(ref s)) => {
+                Some(format!("+refs/tags/{0}:refs/tags/{0}", s))
+            }
+            Some(&GitReference::Rev(ref s)) if is_commit_hash(s) => {
+                Some(format!("+{0}:refs/commit/{0}", s))
+            }
+            _ => None,
+        };
+        if let Some(refspec) = refspec {
+            match fetch_refspecs(dst, &url, &[&refspec[..]],
+                                 git2::AutotagOption::None, cargo_config) {
+                Ok(()) => return Ok(()),
+                Err(e) => info!("failed to fetch only `{}`, fetching \
+                                 everything instead: {}", refspec, e),
+            }
+        }
+
         let refspec = "refs/heads/*:refs/heads/*";
         fetch(dst, &url, refspec, &cargo_config)
     }
 
-    fn clone_into(&self, dst: &Path, cargo_config: &Config) -> CargoResult<git2::Repository> {
-        let url = self.url.to_string();
+    fn clone_into(&self, dst: &Path, reference: Option<&GitReference>,
+                  cargo_config: &Config) -> CargoResult<git2::Repository> {
         if fs::metadata(&dst).is_ok() {
             try!(fs::remove_dir_all(dst));
         }
         try!(fs::create_dir_all(dst));
         let repo = try!(git2::Repository::init_bare(dst));
-        try!(fetch(&repo, &url, "refs/heads/*:refs/heads/*", &cargo_config));
+        try!(self.fetch_into(&repo, reference, cargo_config));
         Ok(repo)
     }
 }
@@ -164,8 +198,10 @@ impl GitDatabase {
         &self.path
     }
 
-    pub fn copy_to(&self, rev: GitRevision, dest: &Path, cargo_config: &Config)
-                   -> CargoResult<GitCheckout> {
+    /// Checks out `rev` at `dest`, along with all submodules unless
+    /// `submodules` is false.
+    pub fn copy_to(&self, rev: GitRevision, dest: &Path, submodules: bool,
+                   cargo_config: &Config) -> CargoResult<GitCheckout> {
         let checkout = match git2::Repository::open(dest) {
             Ok(repo) => {
                 let checkout = GitCheckout::new(dest, self, rev, repo);
@@ -176,11 +212,14 @@ impl GitDatabase {
                 }
                 checkout
             }
-            Err(..) => try!(GitCheckout::clone_into(dest, self, rev)),
+            Err(..) => try!(GitCheckout::clone_into(dest, self, rev,
+                                                    cargo_config)),
         };
-        try!(checkout.update_submodules(&cargo_config).chain_error(|| {
-            internal("failed to update submodules")
-        }));
+        if submodules {
+            try!(checkout.update_submodules(&cargo_config).chain_error(|| {
+                internal("failed to update submodules")
+            }));
+        }
         Ok(checkout)
     }
 
@@ -235,11 +274,16 @@ impl<'a> GitCheckout<'a> {
     }
 
     fn clone_into(into: &Path, database: &'a GitDatabase,
-                  revision: GitRevision)
+                  revision: GitRevision, cargo_config: &Config)
                   -> CargoResult<GitCheckout<'a>>
     {
         let repo = try!(GitCheckout::clone_repo(database.path(), into));
         let checkout = GitCheckout::new(into, database, revision, repo);
+        // A tag or revision which was fetched on its own isn't reachable from
+        // any branch of the database, so it's not part of the clone.
+        if checkout.repo.find_object(checkout.revision.0, None).is_err() {
+            try!(checkout.fetch(cargo_config));
+        }
         try!(checkout.reset());
         Ok(checkout)
     }
@@ -280,8 +324,9 @@ impl<'a> GitCheckout<'a> {
         info!("fetch {}", self.repo.path().display());
         let url = try!(self.database.path.to_url().map_err(human));
         let url = url.to_string();
-        let refspec = "refs/heads/*:refs/heads/*";
-        try!(fetch(&self.repo, &url, refspec, &cargo_config));
+        let refspecs = ["refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*",
+                        "+refs/commit/*:refs/commit/*"];
+        try!(fetch_local(&self.repo, &url, &refspecs, &cargo_config));
         Ok(())
     }
 
@@ -554,8 +599,36 @@ fn with_authentication<T, F>(url: &str, cfg: &git2::Config, mut f: F)
     })
 }
 
+fn is_commit_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_digit(16))
+}
+
 pub fn fetch(repo: &git2::Repository, url: &str,
              refspec: &str, cargo_config: &Config) -> CargoResult<()> {
+    fetch_refspecs(repo, url, &[refspec], git2::AutotagOption::All,
+                   cargo_config)
+}
+
+fn fetch_refspecs(repo: &git2::Repository, url: &str, refspecs: &[&str],
+                  tags: git2::AutotagOption, cargo_config: &Config)
+                  -> CargoResult<()> {
+    if !cargo_config.network_allowed() {
+        bail!("attempting to update a git repository, but the network is \
+               disabled (--offline/--frozen)")
+    }
+    do_fetch(repo, url, refspecs, tags, cargo_config)
+}
+
+/// Fetches from a git database on the local filesystem, which is allowed even
+/// when the network is disabled.
+pub fn fetch_local(repo: &git2::Repository, url: &str, refspecs: &[&str],
+               cargo_config: &Config) -> CargoResult<()> {
+    do_fetch(repo, url, refspecs, git2::AutotagOption::All, cargo_config)
+}
+
+fn do_fetch(repo: &git2::Repository, url: &str, refspecs: &[&str],
+            tags: git2::AutotagOption, cargo_config: &Config)
+            -> CargoResult<()> {
     // Create a local anonymous remote in the repository to fetch the url
 
     with_authentication(url, &try!(repo.config()), |f| {
@@ -564,10 +637,10 @@ pub fn fetch(repo: &git2::Repository, url: &str,
         let mut remote = try!(repo.remote_anonymous(&url));
         let mut opts = git2::FetchOptions::new();
         opts.remote_callbacks(cb)
-            .download_tags(git2::AutotagOption::All);
+            .download_tags(tags);
 
         try!(network::with_retry(cargo_config, ||{
-            remote.fetch(&[refspec], Some(&mut opts), None)
+            remote.fetch(refspecs, Some(&mut opts), None)
         }));
         Ok(())
     })
@@ -336,6 +336,11 @@ impl<'cfg> Source for PathSource<'cfg> {
     }
 
     fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        self.fingerprint_with_file(pkg).map(|(fingerprint, _)| fingerprint)
+    }
+
+    fn fingerprint_with_file(&self, pkg: &Package)
+                             -> CargoResult<(String, Option<PathBuf>)> {
         if !self.updated {
             return Err(internal_error("BUG: source was not updated", ""));
         }
@@ -358,6 +363,6 @@ impl<'cfg> Source for PathSource<'cfg> {
             }
         }
         trace!("fingerprint {}: {}", self.path.display(), max);
-        Ok(format!("{} ({})", max, max_path.display()))
+        Ok((max.to_string(), Some(max_path)))
     }
 }
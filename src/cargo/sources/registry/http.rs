@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use curl::easy::{Easy, List};
+use rustc_serialize::json;
+
+use core::{Download, PackageId, SourceId};
+use ops;
+use sources::registry::{RegistryData, RegistryConfig, INDEX_LOCK};
+use sources::registry::remote;
+use util::{CargoResult, Config, ChainError, human, internal};
+use util::{paths, Filesystem, FileLock};
+
+/// A registry whose index is served over plain HTTP, one file per crate.
+///
+/// The URL of such a registry is the URL of the root of the index prefixed
+/// with `sparse+`, for example `sparse+https://example.com/index/`. Instead of
+/// cloning the whole index, the files for the crates which are actually
+/// queried are downloaded on demand and stored in the same layout as a git
+/// index. Each file is stored along with the `ETag` and `Last-Modified`
+/// headers it was served with so it can later be revalidated with a
+/// conditional request.
+pub struct HttpRegistry<'cfg> {
+    index_path: Filesystem,
+    cache_path: Filesystem,
+    source_id: SourceId,
+    config: &'cfg Config,
+    handle: Option<Easy>,
+    // Whether `update_index` has been called. Until then the files we've got
+    // locally are trusted, just like the checkout of a git index.
+    updated: bool,
+    // Index files which have already been revalidated since the last update.
+    fresh: HashSet<PathBuf>,
+}
+
+impl<'cfg> HttpRegistry<'cfg> {
+    pub fn new(source_id: &SourceId, config: &'cfg Config, name: &str)
+               -> HttpRegistry<'cfg> {
+        HttpRegistry {
+            index_path: config.registry_index_path().join(name),
+            cache_path: config.registry_cache_path().join(name),
+            source_id: source_id.clone(),
+            config: config,
+            handle: None,
+            updated: false,
+            fresh: HashSet::new(),
+        }
+    }
+
+    /// Returns the URL of the file at `path`, relative to the index root.
+    fn file_url(&self, path: &Path) -> String {
+        let url = self.source_id.url().as_str();
+        let base = url["sparse+".len()..].trim_right_matches('/');
+        let parts = path.iter().map(|p| p.to_string_lossy().into_owned())
+                        .collect::<Vec<_>>();
+        format!("{}/{}", base, parts.join("/"))
+    }
+
+    /// Fetches the file at `path` into the index rooted at `root`, revalidating
+    /// any copy we've already got.
+    ///
+    /// Returns `false` if the server doesn't have the file, in which case any
+    /// local copy is removed as well.
+    fn fetch(&mut self, path: &Path, root: &Path) -> CargoResult<bool> {
+        let url = self.file_url(path);
+        self.fetch_file(&url, &root.join(path)).chain_error(|| {
+            human(format!("failed to fetch `{}`", url))
+        })
+    }
+
+    fn fetch_file(&mut self, url: &str, dst: &Path) -> CargoResult<bool> {
+        let headers_path = cache_headers_path(dst);
+        let mut headers = List::new();
+        if dst.exists() {
+            if let Ok(cached) = paths::read(&headers_path) {
+                for line in cached.lines() {
+                    if line.starts_with("ETag:") {
+                        let value = line["ETag:".len()..].trim();
+                        try!(headers.append(&format!("If-None-Match: {}",
+                                                     value)));
+                    } else if line.starts_with("Last-Modified:") {
+                        let value = line["Last-Modified:".len()..].trim();
+                        try!(headers.append(&format!("If-Modified-Since: {}",
+                                                     value)));
+                    }
+                }
+            }
+        }
+
+        let handle = match self.handle {
+            Some(ref mut handle) => handle,
+            None => {
+                self.handle = Some(try!(ops::http_handle(self.config)));
+                self.handle.as_mut().unwrap()
+            }
+        };
+        try!(handle.get(true));
+        try!(handle.url(url));
+        try!(handle.follow_location(true));
+        try!(handle.http_headers(headers));
+        let mut body = Vec::new();
+        let mut response_headers = Vec::new();
+        let result = {
+            let mut handle = handle.transfer();
+            try!(handle.write_function(|buf| {
+                body.extend_from_slice(buf);
+                Ok(buf.len())
+            }));
+            try!(handle.header_function(|header| {
+                let header = String::from_utf8_lossy(header);
+                let header = header.trim();
+                let lower = header.to_lowercase();
+                if lower.starts_with("etag:") {
+                    let value = header["etag:".len()..].trim();
+                    response_headers.push(format!("ETag: {}", value));
+                } else if lower.starts_with("last-modified:") {
+                    let value = header["last-modified:".len()..].trim();
+                    response_headers.push(format!("Last-Modified: {}", value));
+                }
+                true
+            }));
+            handle.perform()
+        };
+        let code = match result {
+            Ok(()) => try!(handle.response_code()),
+            // `file://` indices report a missing file as an error rather than
+            // with a status code.
+            Err(ref e) if e.is_file_couldnt_read_file() => 404,
+            Err(e) => return Err(e.into()),
+        };
+
+        match code {
+            200 | 0 => {
+                try!(fs::create_dir_all(dst.parent().unwrap()));
+                try!(paths::write(dst, &body));
+                let mut contents = String::new();
+                for header in response_headers {
+                    contents.push_str(&header);
+                    contents.push('\n');
+                }
+                try!(paths::write(&headers_path, contents.as_bytes()));
+                Ok(true)
+            }
+            304 => Ok(true),
+            404 | 410 => {
+                if dst.exists() {
+                    try!(fs::remove_file(dst));
+                }
+                if headers_path.exists() {
+                    try!(fs::remove_file(&headers_path));
+                }
+                Ok(false)
+            }
+            code => {
+                Err(internal(format!("failed to get 200 response from `{}`, \
+                                      got {}", url, code)))
+            }
+        }
+    }
+}
+
+impl<'cfg> RegistryData for HttpRegistry<'cfg> {
+    fn index_path(&self) -> &Filesystem {
+        &self.index_path
+    }
+
+    fn config(&self) -> CargoResult<Option<RegistryConfig>> {
+        let lock = try!(self.index_path.open_ro(Path::new(INDEX_LOCK),
+                                                self.config,
+                                                "the registry index"));
+        let path = lock.path().parent().unwrap();
+        let contents = try!(paths::read(&path.join("config.json")));
+        let config = try!(json::decode(&contents));
+        Ok(Some(config))
+    }
+
+    fn update_index(&mut self) -> CargoResult<()> {
+        if self.updated {
+            return Ok(())
+        }
+
+        // When offline the files we've already got will have to do, as long
+        // as the index has been fetched at least once.
+        if !self.config.network_allowed() {
+            let path = self.index_path.clone().into_path_unlocked();
+            if !path.join("config.json").exists() {
+                bail!("the index of registry `{}` has not been downloaded yet \
+                       and the network is disabled (--offline/--frozen)",
+                      self.source_id.url())
+            }
+            return Ok(())
+        }
+
+        try!(self.index_path.create_dir());
+        let lock = try!(self.index_path.open_rw(Path::new(INDEX_LOCK),
+                                                self.config,
+                                                "the registry index"));
+        let path = lock.path().parent().unwrap();
+
+        try!(self.config.shell().status("Updating",
+             format!("registry `{}`", self.source_id.url())));
+
+        // Only the configuration is fetched eagerly, the files for each crate
+        // are revalidated as they're read.
+        if !try!(self.fetch(Path::new("config.json"), path)) {
+            bail!("the registry `{}` does not have a `config.json`",
+                  self.source_id.url())
+        }
+        self.updated = true;
+        self.fresh.clear();
+        Ok(())
+    }
+
+    fn load_index_file(&mut self, path: &Path) -> CargoResult<()> {
+        if !self.config.network_allowed() || self.fresh.contains(path) {
+            return Ok(())
+        }
+        if !self.updated {
+            let root = self.index_path.clone().into_path_unlocked();
+            if root.join(path).exists() {
+                return Ok(())
+            }
+            // We've never seen this crate before, so this is as good a time
+            // as any to make sure the rest of the index is up to date.
+            try!(self.update_index());
+        }
+
+        try!(self.index_path.create_dir());
+        let lock = try!(self.index_path.open_rw(Path::new(INDEX_LOCK),
+                                                self.config,
+                                                "the registry index"));
+        try!(self.fetch(path, lock.parent()));
+        self.fresh.insert(path.to_path_buf());
+        Ok(())
+    }
+
+    fn download(&mut self, pkg: &PackageId, checksum: &str)
+                -> CargoResult<(FileLock, bool)> {
+        let registry = try!(self.config()).unwrap();
+        remote::download(&self.cache_path, self.config, &registry, pkg,
+                         checksum)
+    }
+
+    fn prepare_download(&mut self, pkg: &PackageId, checksum: &str)
+                        -> CargoResult<Option<Download>> {
+        let registry = try!(self.config()).unwrap();
+        remote::prepare_download(&self.cache_path, self.config, &registry, pkg,
+                                 checksum)
+    }
+
+    fn is_crate_downloaded(&self, pkg: &PackageId) -> bool {
+        let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
+        let path = self.cache_path.join(&filename).into_path_unlocked();
+        path.metadata().map(|m| m.len() > 0).unwrap_or(false)
+    }
+}
+
+/// Returns the path of the file holding the caching headers for the index
+/// file `path`.
+fn cache_headers_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap().to_string_lossy();
+    path.with_file_name(format!("{}.http", name))
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use core::PackageId;
+use sources::registry::{RegistryData, RegistryConfig};
+use util::{CargoResult, Config};
+use util::{Filesystem, FileLock};
+
+/// A registry which lives entirely in a directory on the local filesystem.
+///
+/// The directory contains an `index` folder laid out like the index of a
+/// remote registry, and all `.crate` tarballs are placed next to it.
+pub struct LocalRegistry<'cfg> {
+    index_path: Filesystem,
+    root: Filesystem,
+    src_path: Filesystem,
+    config: &'cfg Config,
+}
+
+impl<'cfg> LocalRegistry<'cfg> {
+    pub fn new(root: &Path, config: &'cfg Config, name: &str)
+               -> LocalRegistry<'cfg> {
+        LocalRegistry {
+            index_path: Filesystem::new(root.join("index")),
+            root: Filesystem::new(root.to_path_buf()),
+            src_path: config.registry_source_path().join(name),
+            config: config,
+        }
+    }
+}
+
+impl<'cfg> RegistryData for LocalRegistry<'cfg> {
+    fn index_path(&self) -> &Filesystem {
+        &self.index_path
+    }
+
+    fn config(&self) -> CargoResult<Option<RegistryConfig>> {
+        // Local registries don't have configuration for remote APIs or
+        // downloads, so there's nothing to load.
+        Ok(None)
+    }
+
+    fn update_index(&mut self) -> CargoResult<()> {
+        // Nothing to update, we just use what's on disk. Verify it actually
+        // exists though.
+        let root = self.root.clone().into_path_unlocked();
+        let index = self.index_path.clone().into_path_unlocked();
+        if !root.is_dir() {
+            bail!("local registry path is not a directory: {}",
+                  root.display())
+        }
+        if !index.is_dir() {
+            bail!("local registry index path is not a directory: {}",
+                  index.display())
+        }
+        Ok(())
+    }
+
+    fn download(&mut self, pkg: &PackageId, _checksum: &str)
+                -> CargoResult<(FileLock, bool)> {
+        let crate_file = format!("{}-{}.crate", pkg.name(), pkg.version());
+        let crate_file = try!(self.root.open_ro(&crate_file,
+                                                self.config,
+                                                "crate file"));
+
+        // We don't actually need to download anything per-se, the checksum of
+        // the .crate file itself is verified if it has to be unpacked.
+        let dst = format!("{}-{}", pkg.name(), pkg.version());
+        if !self.src_path.join(dst).into_path_unlocked().exists() {
+            try!(self.config.shell().status("Unpacking", pkg));
+        }
+
+        Ok((crate_file, false))
+    }
+}
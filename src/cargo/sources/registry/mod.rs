@@ -0,0 +1,1106 @@
+//! A `Source` for registry-based packages.
+//!
+//! # What's a Registry?
+//!
+//! Registries are central locations where packages can be uploaded to,
+//! discovered, and searched for. The purpose of a registry is to have a
+//! location that serves as permanent storage for versions of a crate over time.
+//!
+//! Compared to git sources, a registry provides many packages as well as many
+//! versions simultaneously. Git sources can also have commits deleted through
+//! rebasings where registries cannot have their versions deleted.
+//!
+//! # The Index of a Registry
+//!
+//! One of the major difficulties with a registry is that hosting so many
+//! packages may quickly run into performance problems when dealing with
+//! dependency graphs. It's infeasible for cargo to download the entire contents
+//! of the registry just to resolve one package's dependencies, for example. As
+//! a result, cargo needs some efficient method of querying what packages are
+//! available on a registry, what versions are available, and what the
+//! dependencies for each version is.
+//!
+//! One method of doing so would be having the registry expose an HTTP endpoint
+//! which can be queried with a list of packages and a response of their
+//! dependencies and versions is returned. This is somewhat inefficient however
+//! as we may have to hit the endpoint many times and we may have already
+//! queried for much of the data locally already (for other packages, for
+//! example). This also involves inventing a transport format between the
+//! registry and Cargo itself, so this route was not taken.
+//!
+//! Instead, Cargo communicates with registries through a git repository
+//! referred to as the Index. The Index of a registry is essentially an easily
+//! query-able version of the registry's database for a list of versions of a
+//! package as well as a list of dependencies for each version.
+//!
+//! Using git to host this index provides a number of benefits:
+//!
+//! * The entire index can be stored efficiently locally on disk. This means
+//!   that all queries of a registry can happen locally and don't need to touch
+//!   the network.
+//!
+//! * Updates of the index are quite efficient. Using git buys incremental
+//!   updates, compressed transmission, etc for free. The index must be updated
+//!   each time we need fresh information from a registry, but this is one
+//!   update of a git repository that probably hasn't changed a whole lot so
+//!   it shouldn't be too expensive.
+//!
+//!   Additionally, each modification to the index is just appending a line at
+//!   the end of a file (the exact format is described later). This means that
+//!   the commits for an index are quite small and easily applied/compressable.
+//!
+//! ## The format of the Index
+//!
+//! The index is a store for the list of versions for all packages known, so its
+//! format on disk is optimized slightly to ensure that `ls registry` doesn't
+//! produce a list of all packages ever known. The index also wants to ensure
+//! that there's not a million files which may actually end up hitting
+//! filesystem limits at some point. To this end, a few decisions were made
+//! about the format of the registry:
+//!
+//! 1. Each crate will have one file corresponding to it. Each version for a
+//!    crate will just be a line in this file.
+//! 2. There will be two tiers of directories for crate names, under which
+//!    crates corresponding to those tiers will be located.
+//!
+//! As an example, this is an example hierarchy of an index:
+//!
+//! ```notrust
+//! .
+//! ├── 3
+//! │   └── u
+//! │       └── url
+//! ├── bz
+//! │   └── ip
+//! │       └── bzip2
+//! ├── config.json
+//! ├── en
+//! │   └── co
+//! │       └── encoding
+//! └── li
+//!     ├── bg
+//!     │   └── libgit2
+//!     └── nk
+//!         └── link-config
+//! ```
+//!
+//! The root of the index contains a `config.json` file with a few entries
+//! corresponding to the registry (see `RegistryConfig` below).
+//!
+//! Otherwise, there are three numbered directories (1, 2, 3) for crates with
+//! names 1, 2, and 3 characters in length. The 1/2 directories simply have the
+//! crate files underneath them, while the 3 directory is sharded by the first
+//! letter of the crate name.
+//!
+//! Otherwise the top-level directory contains many two-letter directory names,
+//! each of which has many sub-folders with two letters. At the end of all these
+//! are the actual crate files themselves.
+//!
+//! The purpose of this layout is to hopefully cut down on `ls` sizes as well as
+//! efficient lookup based on the crate name itself.
+//!
+//! ## Crate files
+//!
+//! Each file in the index is the history of one crate over time. Each line in
+//! the file corresponds to one version of a crate, stored in JSON format (see
+//! the `RegistryPackage` structure below).
+//!
+//! As new versions are published, new lines are appended to this file. The only
+//! modifications to this file that should happen over time are yanks of a
+//! particular version.
+//!
+//! ## The index cache
+//!
+//! Decoding every line of an index file is expensive for crates with a long
+//! history, and resolution reads the files of every crate in the graph. The
+//! first time an index file is read after the index changes, its lines are
+//! therefore decoded once and written to a cache file at the same relative path
+//! under `.cache` in the index's directory, with every field separated by NUL:
+//!
+//! ```notrust
+//! <cache version byte> <index version> <entry count> <entry>*
+//!
+//! entry:      <name> <version> <cksum> <0 or 1 for yanked>
+//!             <dependency count> <dependency>* <feature count> <feature>*
+//! dependency: <name> <req> <kind> <target> <registry> <0 or 1 for optional>
+//!             <0 or 1 for default features> <feature count> <feature name>*
+//! feature:    <name> <count> <enabled feature or dependency>*
+//! ```
+//!
+//! An empty kind, target or registry stands for no value. The index version is
+//! the commit the index is checked out at, so updating the index invalidates
+//! every cache file. Reading a cached index file involves no JSON at all, and
+//! only the versions a query could match are turned into a `Summary`, once per
+//! process. Indices without a notion of version, such as those of local and
+//! sparse registries, aren't cached.
+//!
+//! # Downloading Packages
+//!
+//! The purpose of the Index was to provide an efficient method to resolve the
+//! dependency graph for a package. So far we only required one network
+//! interaction to update the registry's repository (yay!). After resolution has
+//! been performed, however we need to download the contents of packages so we
+//! can read the full manifest and build the source code.
+//!
+//! To accomplish this, this source's `download` method will make an HTTP
+//! request per-package requested to download tarballs into a local cache. These
+//! tarballs will then be unpacked into a destination folder.
+//!
+//! Note that because versions uploaded to the registry are frozen forever that
+//! the HTTP download and unpacking can all be skipped if the version has
+//! already been downloaded and unpacked. This caching allows us to only
+//! download a package when absolutely necessary.
+//!
+//! # Filesystem Hierarchy
+//!
+//! Overall, the `$HOME/.cargo` looks like this when talking about the registry:
+//!
+//! ```notrust
+//! # A folder under which all registry metadata is hosted (similar to
+//! # $HOME/.cargo/git)
+//! $HOME/.cargo/registry/
+//!
+//!     # For each registry that cargo knows about (keyed by hostname + hash)
+//!     # there is a folder which is the checked out version of the index for
+//!     # the registry in this location. Note that this is done so cargo can
+//!     # support multiple registries simultaneously
+//!     index/
+//!         registry1-<hash>/
+//!         registry2-<hash>/
+//!         ...
+//!
+//!     # This folder is a cache for all downloaded tarballs from a registry.
+//!     # Once downloaded and verified, a tarball never changes.
+//!     cache/
+//!         registry1-<hash>/<pkg>-<version>.crate
+//!         ...
+//!
+//!     # Location in which all tarballs are unpacked. Each tarball is known to
+//!     # be frozen after downloading, so transitively this folder is also
+//!     # frozen once its unpacked (it's never unpacked again)
+//!     src/
+//!         registry1-<hash>/<pkg>-<version>/...
+//!         ...
+//! ```
+//!
+//! # Local Registries
+//!
+//! The index and tarballs of a registry don't have to live on a remote server.
+//! A *local registry* is a directory on the filesystem (a network share or a
+//! USB drive, for example) laid out like so:
+//!
+//! ```notrust
+//! local-registry/
+//!     index/
+//!         3/u/url
+//!         li/bg/libgit2
+//!         ...
+//!     libgit2-0.4.3.crate
+//!     url-1.1.0.crate
+//!     ...
+//! ```
+//!
+//! The `index` folder has exactly the same format as the git index described
+//! above, although it need not be a git repository. The `.crate` tarballs
+//! listed in the index are read directly out of the directory instead of being
+//! downloaded, but they're otherwise verified and unpacked just like those of a
+//! remote registry.
+//!
+//! # Sparse Registries
+//!
+//! A registry whose URL is prefixed with `sparse+` serves the files of its
+//! index over plain HTTP rather than as a git repository. Cloning the index of
+//! a large registry is a sizable download for a project which only depends on
+//! a handful of crates, so the files of a sparse index are instead fetched one
+//! at a time as they're queried. They're stored locally in the same layout as
+//! a git index and revalidated with conditional requests.
+//!
+//! The differences between these flavors are captured by the `RegistryData`
+//! trait, with `RemoteRegistry`, `HttpRegistry` and `LocalRegistry` being the
+//! implementations.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::prelude::*;
+use std::io::SeekFrom;
+use std::path::{PathBuf, Path};
+use std::str::Split;
+
+use flate2::read::GzDecoder;
+use rustc_serialize::hex::ToHex;
+use rustc_serialize::json;
+use semver::Version;
+use tar::Archive;
+use url::Url;
+
+use core::{Source, SourceId, PackageId, Package, Summary, Registry};
+use core::Download;
+use core::dependency::{Dependency, DependencyInner, Kind};
+use sources::PathSource;
+use util::{CargoResult, Config, internal, ChainError, ToUrl, human};
+use util::{hex, last_use, paths, Filesystem, FileLock, Sha256};
+use ops;
+
+pub use self::http::HttpRegistry;
+pub use self::local::LocalRegistry;
+pub use self::remote::RemoteRegistry;
+
+mod http;
+mod local;
+mod remote;
+
+const DEFAULT: &'static str = "https://github.com/rust-lang/crates.io-index";
+pub const INDEX_LOCK: &'static str = ".cargo-index-lock";
+
+/// Lock file in the directory of unpacked sources of a registry.
+///
+/// Builds hold a shared lock on it for as long as they may read the unpacked
+/// sources, and `cargo cache` takes an exclusive lock before removing any.
+pub const SRC_LOCK: &'static str = ".cargo-src-lock";
+
+/// File in the directory of an index recording when it was last fetched, in
+/// seconds since the Unix epoch, see `registry.index-ttl`.
+const LAST_UPDATE_FILE: &'static str = ".cargo-last-update";
+
+/// Version of the format of the files in the index cache, see the module
+/// documentation. Bumped whenever the format changes.
+const CACHE_VERSION: u8 = 2;
+
+pub struct RegistrySource<'cfg> {
+    source_id: SourceId,
+    src_path: Filesystem,
+    src_lock: Option<FileLock>,
+    config: &'cfg Config,
+    ops: Box<RegistryData + 'cfg>,
+    // Whether the index is managed by Cargo and must be locked while read,
+    // false for indices which are read-only from Cargo's point of view.
+    index_locked: bool,
+    cache: HashMap<String, Vec<IndexEntry>>,
+    updated: bool,
+    // Whether an update was skipped because the index was fetched recently,
+    // in which case it's forced if a query comes up empty.
+    update_skipped: bool,
+}
+
+/// The operations which differ between the various flavors of registries.
+///
+/// A `RegistrySource` is responsible for parsing the index and unpacking
+/// tarballs, while implementors of this trait are responsible for making the
+/// index and tarballs available locally.
+pub trait RegistryData {
+    /// Returns the root of the index on the local filesystem.
+    fn index_path(&self) -> &Filesystem;
+
+    /// Decodes the `config.json` of this registry's index, if it has one.
+    fn config(&self) -> CargoResult<Option<RegistryConfig>>;
+
+    /// Brings the local copy of the index up to date.
+    fn update_index(&mut self) -> CargoResult<()>;
+
+    /// Returns an identifier for the current contents of the index, such as
+    /// the commit it's checked out at, used to key the index cache.
+    ///
+    /// This is called with the index locked. Indices returning `None` are
+    /// never cached.
+    fn current_version(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Makes sure the index file at `path`, relative to the index root, is
+    /// available locally before it's read.
+    ///
+    /// Indices which are downloaded all at once have nothing to do here.
+    fn load_index_file(&mut self, _path: &Path) -> CargoResult<()> {
+        Ok(())
+    }
+
+    /// Returns a locked handle to the `.crate` tarball for `pkg`, positioned
+    /// at the start of the file, along with whether its contents were verified
+    /// against `checksum`, the SHA-256 listed for `pkg` in the index, as they
+    /// were fetched.
+    ///
+    /// Tarballs which were already available locally are only verified by the
+    /// caller when they have to be unpacked.
+    fn download(&mut self, pkg: &PackageId, checksum: &str)
+                -> CargoResult<(FileLock, bool)>;
+
+    /// Prepares the transfer `download` would otherwise perform for `pkg`,
+    /// see `Source::prepare_download`.
+    ///
+    /// Registries whose tarballs are already on the local filesystem have
+    /// nothing to do here.
+    fn prepare_download(&mut self, _pkg: &PackageId, _checksum: &str)
+                        -> CargoResult<Option<Download>> {
+        Ok(None)
+    }
+
+    /// Returns whether the tarball for `pkg` is available without touching
+    /// the network.
+    fn is_crate_downloaded(&self, _pkg: &PackageId) -> bool {
+        true
+    }
+}
+
+#[derive(RustcDecodable)]
+pub struct RegistryConfig {
+    /// Download endpoint for all crates. This will be appended with
+    /// `/<crate>/<version>/download` and then will be hit with an HTTP GET
+    /// request to download the tarball for a crate.
+    pub dl: String,
+
+    /// API endpoint for the registry. This is what's actually hit to perform
+    /// operations like yanks, owner modifications, publish new crates, etc.
+    pub api: String,
+}
+
+#[derive(RustcDecodable)]
+struct RegistryPackage {
+    name: String,
+    vers: String,
+    deps: Vec<RegistryDependency>,
+    features: HashMap<String, Vec<String>>,
+    cksum: String,
+    yanked: Option<bool>,
+}
+
+/// A version of a crate listed in the index, which is only turned into a
+/// `Summary` once a query asks for it.
+struct IndexEntry {
+    name: String,
+    vers: Version,
+    cksum: String,
+    yanked: bool,
+    deps: Vec<RegistryDependency>,
+    features: HashMap<String, Vec<String>>,
+    summary: Option<Summary>,
+}
+
+#[derive(RustcDecodable, Clone)]
+struct RegistryDependency {
+    name: String,
+    req: String,
+    features: Vec<String>,
+    optional: bool,
+    default_features: bool,
+    target: Option<String>,
+    kind: Option<String>,
+    registry: Option<String>,
+}
+
+impl<'cfg> RegistrySource<'cfg> {
+    /// Creates a source for a registry whose index is a remote git
+    /// repository.
+    pub fn remote(source_id: &SourceId,
+                  config: &'cfg Config) -> RegistrySource<'cfg> {
+        let name = short_name(source_id);
+        let ops: Box<RegistryData + 'cfg> = if is_sparse(source_id) {
+            Box::new(HttpRegistry::new(source_id, config, &name))
+        } else {
+            Box::new(RemoteRegistry::new(source_id, config, &name))
+        };
+        RegistrySource::new(source_id, config, &name, ops, true)
+    }
+
+    /// Creates a source for a registry which lives in the directory `path` on
+    /// the local filesystem.
+    pub fn local(source_id: &SourceId,
+                 path: &Path,
+                 config: &'cfg Config) -> RegistrySource<'cfg> {
+        let name = short_name(source_id);
+        let ops = LocalRegistry::new(path, config, &name);
+        RegistrySource::new(source_id, config, &name, Box::new(ops), false)
+    }
+
+    fn new(source_id: &SourceId,
+           config: &'cfg Config,
+           name: &str,
+           ops: Box<RegistryData + 'cfg>,
+           index_locked: bool) -> RegistrySource<'cfg> {
+        RegistrySource {
+            src_path: config.registry_source_path().join(name),
+            src_lock: None,
+            config: config,
+            source_id: source_id.clone(),
+            ops: ops,
+            index_locked: index_locked,
+            cache: HashMap::new(),
+            updated: false,
+            update_skipped: false,
+        }
+    }
+
+    /// Get the configured default registry URL.
+    ///
+    /// This is the main cargo registry by default, but it can be overridden in
+    /// a .cargo/config
+    pub fn url(config: &Config) -> CargoResult<Url> {
+        let config = try!(ops::registry_configuration(config));
+        let url = config.index.unwrap_or(DEFAULT.to_string());
+        url.to_url().map_err(human)
+    }
+
+    /// Get the index URL of the registry named `name` in the `[registries]`
+    /// table of `.cargo/config`.
+    pub fn alt_url(config: &Config, name: &str) -> CargoResult<Url> {
+        let config = try!(ops::alt_registry_configuration(config, name));
+        let url = try!(config.index.chain_error(|| {
+            human(format!("no index found for registry: `{}`", name))
+        }));
+        url.to_url().map_err(human)
+    }
+
+    /// Get the default url for the registry
+    pub fn default_url() -> String {
+        DEFAULT.to_string()
+    }
+
+    /// Decode the configuration stored within the registry.
+    ///
+    /// This requires that the index has been at least checked out.
+    pub fn config(&self) -> CargoResult<RegistryConfig> {
+        let config = try!(self.ops.config());
+        config.chain_error(|| {
+            human(format!("the registry `{}` does not have a `config.json`",
+                          self.source_id.url()))
+        })
+    }
+
+    /// Return the hash listed for a specified PackageId.
+    fn hash(&mut self, pkg: &PackageId) -> CargoResult<String> {
+        let entries = try!(self.entries(pkg.name()));
+        entries.iter().find(|e| e.vers == *pkg.version()).map(|e| {
+            e.cksum.clone()
+        }).chain_error(|| {
+            internal(format!("no hash listed for {}", pkg))
+        })
+    }
+
+    /// Acquires a shared lock on the unpacked sources of this registry, held
+    /// until this source is dropped, so they're not removed from under us.
+    fn lock_sources(&mut self) -> CargoResult<()> {
+        if self.src_lock.is_some() {
+            return Ok(())
+        }
+        // `open_ro` requires the lock file to exist, so make sure it does
+        // without locking it exclusively in the meantime.
+        try!(self.src_path.create_dir());
+        let path = self.src_path.join(SRC_LOCK).into_path_unlocked();
+        let mut opts = OpenOptions::new();
+        try!(opts.write(true).create(true).open(&path).chain_error(|| {
+            human(format!("failed to create: {}", path.display()))
+        }));
+        let lock = try!(self.src_path.open_ro(Path::new(SRC_LOCK),
+                                              self.config,
+                                              "the registry sources"));
+        self.src_lock = Some(lock);
+        Ok(())
+    }
+
+    /// Unpacks a downloaded package into a location where it's ready to be
+    /// compiled.
+    ///
+    /// No action is taken if the source looks like it's already unpacked.
+    fn unpack_package(&self,
+                      pkg: &PackageId,
+                      tarball: &FileLock,
+                      checksum: &str)
+                      -> CargoResult<PathBuf> {
+        let dst = self.src_path.join(&format!("{}-{}", pkg.name(),
+                                              pkg.version()));
+        try!(dst.create_dir());
+        // Note that we've already got the `tarball` locked above, and that
+        // implies a lock on the unpacked destination as well, so this access
+        // via `into_path_unlocked` should be ok.
+        let dst = dst.into_path_unlocked();
+        //
+        // The `.cargo-ok` file records the checksum of the tarball the sources
+        // were unpacked from, so if that's not the tarball we've got now they
+        // are unpacked again from scratch.
+        let ok = dst.join(".cargo-ok");
+        match paths::read(&ok) {
+            Ok(ref unpacked) if unpacked == checksum => return Ok(dst),
+            Ok(..) => {
+                try!(fs::remove_dir_all(&dst));
+                try!(fs::create_dir_all(&dst));
+            }
+            Err(..) => {}
+        }
+
+        let gz = try!(GzDecoder::new(tarball.file()));
+        let mut tar = Archive::new(gz);
+        try!(tar.unpack(dst.parent().unwrap()));
+        try!(paths::write(&ok, checksum.as_bytes()));
+        Ok(dst)
+    }
+
+    /// Returns whether the sources of `pkg` are already unpacked from the
+    /// tarball with the SHA-256 `checksum`, see `unpack_package`.
+    fn is_unpacked(&self, pkg: &PackageId, checksum: &str) -> bool {
+        let dst = self.src_path.join(&format!("{}-{}", pkg.name(),
+                                              pkg.version()));
+        let ok = dst.into_path_unlocked().join(".cargo-ok");
+        paths::read(&ok).map(|s| s == checksum).unwrap_or(false)
+    }
+
+    /// Returns the summaries of the versions in the index which may match
+    /// `dep`, along with whether each has been yanked.
+    fn summaries(&mut self, dep: &Dependency)
+                 -> CargoResult<Vec<(Summary, bool)>> {
+        try!(self.entries(dep.name()));
+        let source_id = &self.source_id;
+        let entries = self.cache.get_mut(dep.name()).unwrap();
+        let mut ret = Vec::new();
+        for entry in entries.iter_mut() {
+            let id = try!(PackageId::new(&entry.name, entry.vers.clone(),
+                                         source_id));
+            if !dep.matches_id(&id) {
+                continue
+            }
+            if entry.summary.is_none() {
+                let summary = try!(entry_summary(entry, id).chain_error(|| {
+                    internal(format!("failed to parse registry's information \
+                                      for: {}", dep.name()))
+                }));
+                entry.summary = Some(summary);
+            }
+            ret.push((entry.summary.clone().unwrap(), entry.yanked));
+        }
+        Ok(ret)
+    }
+
+    /// Loads the versions listed in the index for the crate `name`, from the
+    /// index cache if it's up to date.
+    fn entries(&mut self, name: &str) -> CargoResult<&Vec<IndexEntry>> {
+        if self.cache.contains_key(name) {
+            return Ok(self.cache.get(name).unwrap());
+        }
+        let rel = index_file_path(name);
+        try!(self.ops.load_index_file(&rel));
+        // Indices managed by Cargo are locked while they're read so a
+        // concurrent update doesn't pull the rug out from under us.
+        let lock = if self.index_locked {
+            Some(self.ops.index_path().open_ro(Path::new(INDEX_LOCK),
+                                               self.config,
+                                               "the registry index"))
+        } else {
+            None
+        };
+        let root = match lock {
+            Some(Err(..)) => None,
+            Some(Ok(ref lock)) => Some(lock.parent().to_path_buf()),
+            None => Some(self.ops.index_path().clone().into_path_unlocked()),
+        };
+        let version = if self.index_locked {
+            self.ops.current_version()
+        } else {
+            None
+        };
+
+        let mut entries = Vec::new();
+        if let Some(root) = root {
+            let cache_path = root.join(".cache").join(&rel);
+            let cached = version.as_ref().and_then(|v| {
+                read_cache(&cache_path, v)
+            });
+            if let Some(cached) = cached {
+                entries = cached;
+            } else if let Ok(contents) = paths::read(&root.join(&rel)) {
+                entries = try!(parse_index_file(&contents).chain_error(|| {
+                    internal(format!("failed to parse registry's information \
+                                      for: {}", name))
+                }));
+                // The cache is only an optimization, so failing to write it
+                // isn't fatal.
+                if let Some(ref v) = version {
+                    let _ = write_cache(&cache_path, v, &entries);
+                }
+            }
+        }
+        entries.retain(|e| e.name == name);
+        self.cache.insert(name.to_string(), entries);
+        Ok(self.cache.get(name).unwrap())
+    }
+
+    /// Actually perform network operations to update the registry
+    fn do_update(&mut self) -> CargoResult<()> {
+        if self.updated {
+            return Ok(())
+        }
+        try!(self.ops.update_index());
+        if self.index_locked && self.config.network_allowed() {
+            let index = self.ops.index_path();
+            let lock = try!(index.open_rw(Path::new(INDEX_LOCK), self.config,
+                                          "the registry index"));
+            let now = last_use::now().to_string();
+            try!(paths::write(&lock.parent().join(LAST_UPDATE_FILE),
+                              now.as_bytes()));
+        }
+        self.updated = true;
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Returns whether the index was fetched recently enough, according to
+    /// `registry.index-ttl`, for an update to be skipped.
+    fn index_is_fresh(&self) -> CargoResult<bool> {
+        let ttl = try!(self.config.registry_index_ttl());
+        if ttl == 0 || !self.index_locked {
+            return Ok(false)
+        }
+        let path = self.ops.index_path().clone().into_path_unlocked()
+                       .join(LAST_UPDATE_FILE);
+        let last = paths::read(&path).ok().and_then(|s| {
+            s.trim().parse::<u64>().ok()
+        });
+        let now = last_use::now();
+        Ok(match last {
+            Some(last) => last <= now && now - last < ttl,
+            None => false,
+        })
+    }
+
+    /// Queries the index for `dep`, see `Registry::query`.
+    fn query_index(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        // If this is a precise dependency, then it came from a lockfile and in
+        // theory the registry is known to contain this version. If, however, we
+        // come back with no summaries, then our registry may need to be
+        // updated, so we fall back to performing a lazy update.
+        if dep.source_id().precise().is_some() {
+            let mut summaries = try!(self.summaries(dep)).into_iter().map(|s| {
+                s.0
+            }).collect::<Vec<_>>();
+            if try!(summaries.query(dep)).is_empty() {
+                try!(self.do_update());
+            }
+        }
+
+        let mut summaries = try!(self.summaries(dep)).into_iter().filter(|s| {
+            dep.source_id().precise().is_some() || !s.1
+        }).map(|s| s.0).collect::<Vec<_>>();
+
+        // Handle `cargo update --precise` here. If specified, our own source
+        // will have a precise version listed of the form `<pkg>=<req>` where
+        // `<pkg>` is the name of a crate on this source and `<req>` is the
+        // version requested (agument to `--precise`).
+        summaries.retain(|s| {
+            match self.source_id.precise() {
+                Some(p) if p.starts_with(dep.name()) &&
+                           p[dep.name().len()..].starts_with("=") => {
+                    let vers = &p[dep.name().len() + 1..];
+                    s.version().to_string() == vers
+                }
+                _ => true,
+            }
+        });
+
+        // When the network is disabled prefer versions which have already
+        // been downloaded, so resolution doesn't pick something that can't be
+        // fetched later on.
+        if !self.config.network_allowed() {
+            let mut cached = summaries.iter().filter(|s| {
+                self.ops.is_crate_downloaded(s.package_id())
+            }).cloned().collect::<Vec<_>>();
+            let cached = try!(cached.query(dep));
+            if !cached.is_empty() {
+                return Ok(cached)
+            }
+        }
+        summaries.query(dep)
+    }
+}
+
+/// Returns whether `source_id` names a registry whose index is served file by
+/// file over HTTP rather than as a git repository.
+fn is_sparse(source_id: &SourceId) -> bool {
+    source_id.url().scheme().starts_with("sparse+")
+}
+
+/// Returns the directory name used for `source_id` in the various caches under
+/// `$CARGO_HOME/registry`.
+pub fn short_name(source_id: &SourceId) -> String {
+    let hash = hex::short_hash(source_id);
+    let ident = source_id.url().host_str().unwrap_or("").to_string();
+    format!("{}-{}", ident, hash)
+}
+
+/// Returns the path of the index file for the crate `name`, relative to the
+/// root of the index.
+pub fn index_file_path(name: &str) -> PathBuf {
+    let fs_name = name.chars().flat_map(|c| {
+        c.to_lowercase()
+    }).collect::<String>();
+
+    // see module comment for why this is structured the way it is
+    match fs_name.len() {
+        1 => Path::new("1").join(&fs_name),
+        2 => Path::new("2").join(&fs_name),
+        3 => Path::new("3").join(&fs_name[..1]).join(&fs_name),
+        _ => Path::new(&fs_name[0..2])
+                 .join(&fs_name[2..4])
+                 .join(&fs_name),
+    }
+}
+
+/// Decodes each line of the contents of an index file into an entry.
+fn parse_index_file(contents: &str) -> CargoResult<Vec<IndexEntry>> {
+    contents.lines().filter(|l| l.trim().len() > 0).map(|line| {
+        let RegistryPackage {
+            name, vers, deps, features, cksum, yanked
+        } = try!(json::decode::<RegistryPackage>(line));
+        let vers = try!(Version::parse(&vers).map_err(|_| {
+            human(format!("cannot parse '{}' as a semver", vers))
+        }));
+        Ok(IndexEntry {
+            name: name,
+            vers: vers,
+            cksum: cksum,
+            yanked: yanked.unwrap_or(false),
+            deps: deps,
+            features: features,
+            summary: None,
+        })
+    }).collect()
+}
+
+/// Reads the entries of the index cache file at `path`, returning `None` if
+/// it's missing, malformed or was written for another `version` of the
+/// index.
+fn read_cache(path: &Path, version: &str) -> Option<Vec<IndexEntry>> {
+    let mut contents = Vec::new();
+    match File::open(path) {
+        Ok(mut f) => {
+            if f.read_to_end(&mut contents).is_err() {
+                return None
+            }
+        }
+        Err(..) => return None,
+    }
+    if contents.first() != Some(&CACHE_VERSION) {
+        return None
+    }
+    let contents = match String::from_utf8(contents[1..].to_vec()) {
+        Ok(contents) => contents,
+        Err(..) => return None,
+    };
+    let mut fields = contents.split('\0');
+    if fields.next() != Some(version) {
+        return None
+    }
+    let count = match read_count(&mut fields) {
+        Some(count) => count,
+        None => return None,
+    };
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        match read_cache_entry(&mut fields) {
+            Some(entry) => entries.push(entry),
+            None => return None,
+        }
+    }
+    // A partially written file is caught by its fields running out early.
+    match (fields.next(), fields.next()) {
+        (Some(""), None) => Some(entries),
+        _ => None,
+    }
+}
+
+/// Reads a count of the items which follow in the index cache.
+fn read_count(fields: &mut Split<char>) -> Option<usize> {
+    fields.next().and_then(|s| s.parse().ok())
+}
+
+/// Reads a flag written by `write_cache`.
+fn read_flag(fields: &mut Split<char>) -> Option<bool> {
+    match fields.next() {
+        Some("0") => Some(false),
+        Some("1") => Some(true),
+        _ => None,
+    }
+}
+
+/// Reads a field which is empty when it has no value.
+fn read_optional(fields: &mut Split<char>) -> Option<Option<String>> {
+    fields.next().map(|s| {
+        if s.is_empty() {None} else {Some(s.to_string())}
+    })
+}
+
+/// Reads a count followed by that many fields.
+fn read_list(fields: &mut Split<char>) -> Option<Vec<String>> {
+    let count = match read_count(fields) {
+        Some(count) => count,
+        None => return None,
+    };
+    let list = fields.by_ref().take(count).map(|s| s.to_string())
+                     .collect::<Vec<_>>();
+    if list.len() == count {Some(list)} else {None}
+}
+
+/// Reads one entry of the index cache, see the module documentation.
+fn read_cache_entry(fields: &mut Split<char>) -> Option<IndexEntry> {
+    let (name, vers, cksum) = match (fields.next(), fields.next(),
+                                     fields.next()) {
+        (Some(a), Some(b), Some(c)) => (a.to_string(), b, c.to_string()),
+        _ => return None,
+    };
+    let vers = match Version::parse(vers) {
+        Ok(vers) => vers,
+        Err(..) => return None,
+    };
+    let yanked = match read_flag(fields) {
+        Some(yanked) => yanked,
+        None => return None,
+    };
+
+    let count = match read_count(fields) {
+        Some(count) => count,
+        None => return None,
+    };
+    let mut deps = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (name, req) = match (fields.next(), fields.next()) {
+            (Some(a), Some(b)) => (a.to_string(), b.to_string()),
+            _ => return None,
+        };
+        let dep = match (read_optional(fields), read_optional(fields),
+                         read_optional(fields), read_flag(fields),
+                         read_flag(fields), read_list(fields)) {
+            (Some(kind), Some(target), Some(registry), Some(optional),
+             Some(default_features), Some(features)) => RegistryDependency {
+                name: name,
+                req: req,
+                features: features,
+                optional: optional,
+                default_features: default_features,
+                target: target,
+                kind: kind,
+                registry: registry,
+            },
+            _ => return None,
+        };
+        deps.push(dep);
+    }
+
+    let count = match read_count(fields) {
+        Some(count) => count,
+        None => return None,
+    };
+    let mut features = HashMap::with_capacity(count);
+    for _ in 0..count {
+        match (fields.next(), read_list(fields)) {
+            (Some(name), Some(list)) => {
+                features.insert(name.to_string(), list);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(IndexEntry {
+        name: name,
+        vers: vers,
+        cksum: cksum,
+        yanked: yanked,
+        deps: deps,
+        features: features,
+        summary: None,
+    })
+}
+
+/// Writes `entries` to the index cache file at `path` for `version` of the
+/// index.
+fn write_cache(path: &Path, version: &str, entries: &[IndexEntry])
+               -> CargoResult<()> {
+    let mut contents = vec![CACHE_VERSION];
+    {
+        let mut field = |s: &str| {
+            contents.extend_from_slice(s.as_bytes());
+            contents.push(0);
+        };
+        let flag = |b: bool| if b {"1"} else {"0"};
+        field(version);
+        field(&entries.len().to_string());
+        for entry in entries {
+            field(&entry.name);
+            field(&entry.vers.to_string());
+            field(&entry.cksum);
+            field(flag(entry.yanked));
+            field(&entry.deps.len().to_string());
+            for dep in entry.deps.iter() {
+                field(&dep.name);
+                field(&dep.req);
+                field(dep.kind.as_ref().map(|s| &s[..]).unwrap_or(""));
+                field(dep.target.as_ref().map(|s| &s[..]).unwrap_or(""));
+                field(dep.registry.as_ref().map(|s| &s[..]).unwrap_or(""));
+                field(flag(dep.optional));
+                field(flag(dep.default_features));
+                field(&dep.features.len().to_string());
+                for feature in dep.features.iter() {
+                    field(feature);
+                }
+            }
+            field(&entry.features.len().to_string());
+            for (name, list) in entry.features.iter() {
+                field(name);
+                field(&list.len().to_string());
+                for item in list {
+                    field(item);
+                }
+            }
+        }
+    }
+
+    // Other processes may be reading the cache at the same time, so never
+    // leave a partially written file in its place.
+    try!(fs::create_dir_all(path.parent().unwrap()));
+    let tmp = path.with_extension("cargo-tmp");
+    {
+        let mut f = try!(OpenOptions::new().write(true).create(true)
+                                           .truncate(true).open(&tmp));
+        try!(f.write_all(&contents));
+    }
+    try!(fs::rename(&tmp, path));
+    Ok(())
+}
+
+/// Builds the summary of the package with the id `pkgid` from its already
+/// decoded entry in the index.
+fn entry_summary(entry: &IndexEntry, pkgid: PackageId)
+                 -> CargoResult<Summary> {
+    let source_id = pkgid.source_id().clone();
+    let deps: CargoResult<Vec<Dependency>> = entry.deps.iter().map(|dep| {
+        parse_registry_dependency(dep.clone(), &source_id)
+    }).collect();
+    let deps = try!(deps);
+    let mut summary = try!(Summary::new(pkgid, deps, entry.features.clone()));
+    summary.set_checksum(entry.cksum.clone());
+    Ok(summary)
+}
+
+/// Converts an encoded dependency in the registry to a cargo dependency
+fn parse_registry_dependency(dep: RegistryDependency, source_id: &SourceId)
+                             -> CargoResult<Dependency> {
+    let RegistryDependency {
+        name, req, features, optional, default_features, target, kind,
+        registry
+    } = dep;
+
+    // Dependencies on packages in other registries list the index URL of
+    // that registry, otherwise they're in this same registry.
+    let id = match registry {
+        Some(url) => SourceId::for_registry(&try!(url.to_url().map_err(human))),
+        None => source_id.clone(),
+    };
+    let dep = try!(DependencyInner::parse(&name, Some(&req), &id));
+    let kind = match kind.as_ref().map(|s| &s[..]).unwrap_or("") {
+        "dev" => Kind::Development,
+        "build" => Kind::Build,
+        _ => Kind::Normal,
+    };
+
+    let platform = match target {
+        Some(target) => Some(try!(target.parse())),
+        None => None,
+    };
+
+    // Unfortunately older versions of cargo and/or the registry ended up
+    // publishing lots of entries where the features array contained the
+    // empty feature, "", inside. This confuses the resolution process much
+    // later on and these features aren't actually valid, so filter them all
+    // out here.
+    let features = features.into_iter().filter(|s| !s.is_empty()).collect();
+
+    Ok(dep.set_optional(optional)
+          .set_default_features(default_features)
+          .set_features(features)
+          .set_platform(platform)
+          .set_kind(kind)
+          .into_dependency())
+}
+
+impl<'cfg> Registry for RegistrySource<'cfg> {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let ret = try!(self.query_index(dep));
+        // If the index was trusted without an update because it was fetched
+        // recently, what's being asked for may have been published since, so
+        // make sure before giving up.
+        if ret.is_empty() && self.update_skipped && !self.updated {
+            try!(self.do_update());
+            return self.query_index(dep)
+        }
+        Ok(ret)
+    }
+}
+
+impl<'cfg> Source for RegistrySource<'cfg> {
+    fn update(&mut self) -> CargoResult<()> {
+        // If we have an imprecise version then we don't know what we're going
+        // to look for, so we always attempt to perform an update here.
+        //
+        // If we have a precise version, then we'll update lazily during the
+        // querying phase. Note that precise in this case is only
+        // `Some("locked")` as other `Some` values indicate a `cargo update
+        // --precise` request
+        //
+        // Either way, an index fetched within `registry.index-ttl` is trusted
+        // to be up to date.
+        if self.source_id.precise() != Some("locked") {
+            if try!(self.index_is_fresh()) {
+                self.update_skipped = true;
+            } else {
+                try!(self.do_update());
+            }
+        }
+        Ok(())
+    }
+
+    fn download(&mut self, package: &PackageId) -> CargoResult<Package> {
+        let hash = try!(self.hash(package));
+        let (mut krate, verified) = try!(self.ops.download(package, &hash));
+        try!(self.lock_sources());
+        // Once its sources are unpacked the `.cargo-ok` file vouches for a
+        // tarball, so one which was already cached is only hashed again if it
+        // has to be unpacked.
+        if !verified && !self.is_unpacked(package, &hash) &&
+           try!(tarball_checksum(&mut krate)) != hash {
+            bail!("failed to verify the checksum of `{}`, its tarball at `{}` \
+                   is corrupt", package, krate.path().display())
+        }
+        let unpacked = self.unpack_package(package, &krate, &hash);
+        let path = try!(unpacked.chain_error(|| {
+            internal(format!("failed to unpack package `{}`", package))
+        }));
+        try!(last_use::record(&path));
+
+        let mut src = PathSource::new(&path, &self.source_id, self.config);
+        try!(src.update());
+        src.download(package)
+    }
+
+    fn prepare_download(&mut self, package: &PackageId)
+                        -> CargoResult<Option<Download>> {
+        let hash = try!(self.hash(package));
+        self.ops.prepare_download(package, &hash)
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        Ok(pkg.package_id().version().to_string())
+    }
+}
+
+/// Calculates the SHA-256 of the `.crate` tarball `file`, leaving it positioned
+/// at the start again afterwards.
+fn tarball_checksum(file: &mut FileLock) -> CargoResult<String> {
+    let mut state = Sha256::new();
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let n = try!(file.read(&mut buf).chain_error(|| {
+            human(format!("failed to read `{}`", file.path().display()))
+        }));
+        if n == 0 {
+            break
+        }
+        state.update(&buf[..n]);
+    }
+    try!(file.seek(SeekFrom::Start(0)));
+    Ok(state.finish().to_hex())
+}
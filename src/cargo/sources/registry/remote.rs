@@ -0,0 +1,218 @@
+use std::path::Path;
+
+use git2;
+use rustc_serialize::json;
+use url::Url;
+
+use core::{Download, PackageId, SourceId};
+use core::download;
+use ops;
+use sources::git;
+use sources::registry::{RegistryData, RegistryConfig, INDEX_LOCK};
+use util::{CargoResult, CargoError, Config, internal, ChainError, ToUrl};
+use util::{paths, Filesystem, FileLock};
+
+/// A registry whose index is a git repository and whose tarballs are
+/// downloaded over HTTP.
+pub struct RemoteRegistry<'cfg> {
+    index_path: Filesystem,
+    cache_path: Filesystem,
+    source_id: SourceId,
+    config: &'cfg Config,
+    // The commit the index is checked out at, once it's been looked up.
+    head: Option<String>,
+}
+
+impl<'cfg> RemoteRegistry<'cfg> {
+    pub fn new(source_id: &SourceId, config: &'cfg Config, name: &str)
+               -> RemoteRegistry<'cfg> {
+        RemoteRegistry {
+            index_path: config.registry_index_path().join(name),
+            cache_path: config.registry_cache_path().join(name),
+            source_id: source_id.clone(),
+            config: config,
+            head: None,
+        }
+    }
+}
+
+impl<'cfg> RegistryData for RemoteRegistry<'cfg> {
+    fn index_path(&self) -> &Filesystem {
+        &self.index_path
+    }
+
+    fn config(&self) -> CargoResult<Option<RegistryConfig>> {
+        let lock = try!(self.index_path.open_ro(Path::new(INDEX_LOCK),
+                                                self.config,
+                                                "the registry index"));
+        let path = lock.path().parent().unwrap();
+        let contents = try!(paths::read(&path.join("config.json")));
+        let config = try!(json::decode(&contents));
+        Ok(Some(config))
+    }
+
+    fn update_index(&mut self) -> CargoResult<()> {
+        // When offline the index we've already got will have to do, as long
+        // as it's been fetched at least once.
+        if !self.config.network_allowed() {
+            let path = self.index_path.clone().into_path_unlocked();
+            if !path.join("config.json").exists() {
+                bail!("the index of registry `{}` has not been downloaded yet \
+                       and the network is disabled (--offline/--frozen)",
+                      self.source_id.url())
+            }
+            return Ok(())
+        }
+
+        self.head = None;
+        try!(self.index_path.create_dir());
+        let lock = try!(self.index_path.open_rw(Path::new(INDEX_LOCK),
+                                                self.config,
+                                                "the registry index"));
+        let path = lock.path().parent().unwrap();
+
+        try!(self.config.shell().status("Updating",
+             format!("registry `{}`", self.source_id.url())));
+        let repo = try!(git2::Repository::open(path).or_else(|_| {
+            let _ = lock.remove_siblings();
+            git2::Repository::init(path)
+        }));
+
+        // git fetch origin, falling back to each of the mirrors in turn if
+        // the network gets in the way
+        let mirrors = try!(ops::registry_mirrors(self.config, &self.source_id));
+        let mut urls = vec![self.source_id.url().to_string()];
+        urls.extend(mirrors.index);
+        let refspec = "refs/heads/*:refs/remotes/origin/*";
+
+        for (i, url) in urls.iter().enumerate() {
+            let res = git::fetch(&repo, url, refspec, &self.config);
+            let res = res.chain_error(|| {
+                internal(format!("failed to fetch `{}`", url))
+            });
+            match res {
+                Ok(()) => break,
+                Err(ref e) if e.is_spurious() && i + 1 < urls.len() => {
+                    try!(self.config.shell().warn(format!(
+                        "failed to fetch `{}`, trying mirror `{}`",
+                        url, urls[i + 1])));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // git reset --hard origin/master
+        let reference = "refs/remotes/origin/master";
+        let oid = try!(repo.refname_to_id(reference));
+        trace!("[{}] updating to rev {}", self.source_id, oid);
+        let object = try!(repo.find_object(oid, None));
+        try!(repo.reset(&object, git2::ResetType::Hard, None));
+        Ok(())
+    }
+
+    fn current_version(&mut self) -> Option<String> {
+        if let Some(ref head) = self.head {
+            return Some(head.clone())
+        }
+        // Our caller holds the index lock.
+        let path = self.index_path.clone().into_path_unlocked();
+        let repo = match git2::Repository::open(&path) {
+            Ok(repo) => repo,
+            Err(..) => return None,
+        };
+        let head = repo.head().ok().and_then(|head| head.target());
+        self.head = head.map(|oid| oid.to_string());
+        self.head.clone()
+    }
+
+    fn download(&mut self, pkg: &PackageId, checksum: &str)
+                -> CargoResult<(FileLock, bool)> {
+        let registry = try!(self.config()).unwrap();
+        download(&self.cache_path, self.config, &registry, pkg, checksum)
+    }
+
+    fn prepare_download(&mut self, pkg: &PackageId, checksum: &str)
+                        -> CargoResult<Option<Download>> {
+        let registry = try!(self.config()).unwrap();
+        prepare_download(&self.cache_path, self.config, &registry, pkg,
+                         checksum)
+    }
+
+    fn is_crate_downloaded(&self, pkg: &PackageId) -> bool {
+        let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
+        let path = self.cache_path.join(&filename).into_path_unlocked();
+        path.metadata().map(|m| m.len() > 0).unwrap_or(false)
+    }
+}
+
+/// Prepares the download of the tarball for `pkg` from the `dl` endpoint of
+/// `registry` into `cache_path`, shared by all registries which fetch
+/// tarballs over HTTP.
+///
+/// Returns `None` if the tarball is already downloaded, in which case it's
+/// left to be verified when it's unpacked.
+pub fn prepare_download(cache_path: &Filesystem,
+                        config: &Config,
+                        registry: &RegistryConfig,
+                        pkg: &PackageId,
+                        checksum: &str) -> CargoResult<Option<Download>> {
+    let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
+    let dst = try!(cache_path.open_rw(Path::new(&filename), config,
+                                      &filename));
+    let meta = try!(dst.file().metadata());
+    if meta.len() > 0 {
+        return Ok(None)
+    }
+    if !config.network_allowed() {
+        bail!("cannot download `{}` because the network is disabled \
+               (--offline/--frozen), and it is not in the local cache",
+              pkg)
+    }
+
+    let url = try!(dl_url(&registry.dl, pkg));
+    let dl_mirrors = try!(ops::registry_mirrors(config, pkg.source_id())).dl;
+    let mirrors = try!(dl_mirrors.iter().map(|dl| {
+        dl_url(dl, pkg)
+    }).collect::<CargoResult<Vec<_>>>());
+    Ok(Some(Download {
+        id: pkg.clone(),
+        url: url,
+        mirrors: mirrors,
+        checksum: checksum.to_string(),
+        dst: dst,
+    }))
+}
+
+/// Returns the URL of the tarball for `pkg` at the download endpoint `dl`.
+fn dl_url(dl: &str, pkg: &PackageId) -> CargoResult<Url> {
+    let mut url = try!(dl.to_url().map_err(internal));
+    url.path_segments_mut().unwrap()
+        .push(pkg.name())
+        .push(&pkg.version().to_string())
+        .push("download");
+    Ok(url)
+}
+
+/// Downloads the tarball for `pkg` on its own, see `prepare_download` and
+/// `RegistryData::download`.
+///
+/// No action is taken if the package is already downloaded.
+pub fn download(cache_path: &Filesystem,
+                config: &Config,
+                registry: &RegistryConfig,
+                pkg: &PackageId,
+                checksum: &str) -> CargoResult<(FileLock, bool)> {
+    match try!(prepare_download(cache_path, config, registry, pkg, checksum)) {
+        Some(download) => {
+            let mut files = try!(download::download_all(config,
+                                                        vec![download]));
+            Ok((files.pop().unwrap(), true))
+        }
+        None => {
+            let filename = format!("{}-{}.crate", pkg.name(), pkg.version());
+            let file = try!(cache_path.open_rw(Path::new(&filename), config,
+                                               &filename));
+            Ok((file, false))
+        }
+    }
+}
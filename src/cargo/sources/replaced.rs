@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use core::{Source, Registry, PackageId, Package, Dependency, Summary, SourceId};
+use core::Download;
+use util::{CargoResult, ChainError, human};
+
+/// A source which loads all of its packages from another source while
+/// pretending that they came from the original `SourceId`.
+///
+/// All queries and downloads are translated to `replace_with` before being
+/// forwarded to `inner`, and all results are translated back to `to_replace`,
+/// so the rest of Cargo (notably the lock file) never sees the replacement.
+pub struct ReplacedSource<'cfg> {
+    to_replace: SourceId,
+    replace_with: SourceId,
+    inner: Box<Source + 'cfg>,
+}
+
+impl<'cfg> ReplacedSource<'cfg> {
+    pub fn new(to_replace: &SourceId,
+               replace_with: &SourceId,
+               src: Box<Source + 'cfg>) -> ReplacedSource<'cfg> {
+        ReplacedSource {
+            to_replace: to_replace.clone(),
+            replace_with: replace_with.clone(),
+            inner: src,
+        }
+    }
+}
+
+impl<'cfg> Registry for ReplacedSource<'cfg> {
+    fn query(&mut self, dep: &Dependency) -> CargoResult<Vec<Summary>> {
+        let dep = dep.clone().map_source(&self.to_replace, &self.replace_with);
+        let ret = try!(self.inner.query(&dep).chain_error(|| {
+            human(format!("failed to query replaced source `{}`",
+                          self.to_replace))
+        }));
+        Ok(ret.into_iter().map(|summary| {
+            summary.map_source(&self.replace_with, &self.to_replace)
+        }).collect())
+    }
+}
+
+impl<'cfg> Source for ReplacedSource<'cfg> {
+    fn update(&mut self) -> CargoResult<()> {
+        self.inner.update().chain_error(|| {
+            human(format!("failed to update replaced source `{}`",
+                          self.to_replace))
+        })
+    }
+
+    fn download(&mut self, id: &PackageId) -> CargoResult<Package> {
+        let id = id.with_source_id(&self.replace_with);
+        let pkg = try!(self.inner.download(&id).chain_error(|| {
+            human(format!("failed to download replaced source `{}`",
+                          self.to_replace))
+        }));
+        Ok(pkg.map_source(&self.replace_with, &self.to_replace))
+    }
+
+    fn prepare_download(&mut self, id: &PackageId)
+                        -> CargoResult<Option<Download>> {
+        let id = id.with_source_id(&self.replace_with);
+        self.inner.prepare_download(&id).chain_error(|| {
+            human(format!("failed to download replaced source `{}`",
+                          self.to_replace))
+        })
+    }
+
+    fn fingerprint(&self, pkg: &Package) -> CargoResult<String> {
+        self.inner.fingerprint(pkg)
+    }
+
+    fn fingerprint_with_file(&self, pkg: &Package)
+                             -> CargoResult<(String, Option<PathBuf>)> {
+        self.inner.fingerprint_with_file(pkg)
+    }
+}
@@ -31,6 +31,9 @@ pub struct Config {
     rustc: PathBuf,
     rustdoc: PathBuf,
     target_dir: RefCell<Option<Filesystem>>,
+    frozen: Cell<bool>,
+    locked: Cell<bool>,
+    offline: Cell<bool>,
 }
 
 impl Config {
@@ -47,6 +50,9 @@ impl Config {
             rustc: PathBuf::from("rustc"),
             rustdoc: PathBuf::from("rustdoc"),
             target_dir: RefCell::new(None),
+            frozen: Cell::new(false),
+            locked: Cell::new(false),
+            offline: Cell::new(false),
         };
 
         try!(cfg.scrape_tool_config());
@@ -280,16 +286,51 @@ impl Config {
         }
     }
 
+    /// Returns the maximum number of crates to download at once, from
+    /// `net.download-jobs`.
+    pub fn net_download_jobs(&self) -> CargoResult<u32> {
+        match try!(self.get_i64("net.download-jobs")) {
+            Some(v) => {
+                if v.val <= 0 {
+                    bail!("net.download-jobs must be positive, but found {} \
+                           in {}", v.val, v.definition)
+                }
+                Ok(v.val as u32)
+            }
+            None => Ok(8),
+        }
+    }
+
+    /// Returns for how many seconds after a registry index was fetched it's
+    /// considered up to date, from `registry.index-ttl`.
+    ///
+    /// Zero, the default, means the index is fetched every time it's needed.
+    pub fn registry_index_ttl(&self) -> CargoResult<u64> {
+        match try!(self.get_i64("registry.index-ttl")) {
+            Some(v) => {
+                if v.val < 0 {
+                    bail!("registry.index-ttl must not be negative, but found \
+                           {} in {}", v.val, v.definition)
+                }
+                Ok(v.val as u64)
+            }
+            None => Ok(0),
+        }
+    }
+
     pub fn expected<T>(&self, ty: &str, key: &str, val: CV) -> CargoResult<T> {
         val.expected(ty).map_err(|e| {
             human(format!("invalid configuration for key `{}`\n{}", key, e))
         })
     }
 
-    pub fn configure_shell(&self,
-                           verbose: Option<bool>,
-                           quiet: Option<bool>,
-                           color: &Option<String>) -> CargoResult<()> {
+    pub fn configure(&self,
+                     verbose: Option<bool>,
+                     quiet: Option<bool>,
+                     color: &Option<String>,
+                     frozen: bool,
+                     locked: bool,
+                     offline: bool) -> CargoResult<()> {
         let cfg_verbose = try!(self.get_bool("term.verbose")).map(|v| v.val);
         let cfg_color = try!(self.get_string("term.color")).map(|v| v.val);
         let color = color.as_ref().or(cfg_color.as_ref());
@@ -321,9 +362,34 @@ impl Config {
         self.shell().set_verbosity(verbosity);
         try!(self.shell().set_color_config(color.map(|s| &s[..])));
 
+        let cfg_offline = try!(self.get_bool("net.offline")).map(|v| v.val);
+        self.frozen.set(frozen);
+        self.locked.set(locked);
+        self.offline.set(offline || cfg_offline == Some(true));
+
         Ok(())
     }
 
+    /// Returns whether Cargo may touch the network, which is disallowed by
+    /// both `--offline` and `--frozen`.
+    ///
+    /// When this is false sources must make do with whatever has already been
+    /// downloaded into `$CARGO_HOME`.
+    pub fn network_allowed(&self) -> bool {
+        !self.frozen.get() && !self.offline.get()
+    }
+
+    /// Returns whether `Cargo.lock` may be created or modified, which is
+    /// disallowed by both `--locked` and `--frozen`.
+    pub fn lock_update_allowed(&self) -> bool {
+        !self.frozen.get() && !self.locked.get()
+    }
+
+    /// Returns whether `--frozen` was passed.
+    pub fn frozen(&self) -> bool {
+        self.frozen.get()
+    }
+
     fn load_values(&self) -> CargoResult<()> {
         let mut cfg = CV::Table(HashMap::new(), PathBuf::from("."));
 
@@ -23,6 +23,10 @@ pub type CargoResult<T> = Result<T, Box<CargoError>>;
 pub trait CargoError: Error + Send + 'static {
     fn is_human(&self) -> bool { false }
     fn cargo_cause(&self) -> Option<&CargoError>{ None }
+
+    /// Whether this error, or the error it was caused by, is a network error
+    /// which may not happen again, see `NetworkError::maybe_spurious`.
+    fn is_spurious(&self) -> bool { false }
 }
 
 impl Error for Box<CargoError> {
@@ -33,6 +37,7 @@ impl Error for Box<CargoError> {
 impl CargoError for Box<CargoError> {
     fn is_human(&self) -> bool { (**self).is_human() }
     fn cargo_cause(&self) -> Option<&CargoError> { (**self).cargo_cause() }
+    fn is_spurious(&self) -> bool { (**self).is_spurious() }
 }
 
 // =============================================================================
@@ -101,6 +106,9 @@ impl<E: fmt::Display> fmt::Display for ChainedError<E> {
 impl<E: CargoError> CargoError for ChainedError<E> {
     fn is_human(&self) -> bool { self.error.is_human() }
     fn cargo_cause(&self) -> Option<&CargoError> { Some(&*self.cause) }
+    fn is_spurious(&self) -> bool {
+        self.error.is_spurious() || self.cause.is_spurious()
+    }
 }
 
 // =============================================================================
@@ -237,6 +245,7 @@ impl<E: fmt::Display> fmt::Display for Human<E> {
 impl<E: CargoError> CargoError for Human<E> {
     fn is_human(&self) -> bool { true }
     fn cargo_cause(&self) -> Option<&CargoError> { self.0.cargo_cause() }
+    fn is_spurious(&self) -> bool { self.0.is_spurious() }
 }
 
 // =============================================================================
@@ -356,10 +365,14 @@ impl<E: CargoError> From<Human<E>> for Box<CargoError> {
 
 impl CargoError for semver::ReqParseError {}
 impl CargoError for io::Error {}
-impl CargoError for git2::Error {}
+impl CargoError for git2::Error {
+    fn is_spurious(&self) -> bool { self.maybe_spurious() }
+}
 impl CargoError for json::DecoderError {}
 impl CargoError for json::EncoderError {}
-impl CargoError for curl::Error {}
+impl CargoError for curl::Error {
+    fn is_spurious(&self) -> bool { self.maybe_spurious() }
+}
 impl CargoError for ProcessError {}
 impl CargoError for CargoTestError {}
 impl CargoError for CliError {}
@@ -0,0 +1,50 @@
+//! Tracking of when the sources cached in `$CARGO_HOME` were last used.
+//!
+//! Each unpacked registry crate and git checkout has a `.cargo-last-use` file
+//! in its root holding the time (in seconds since the Unix epoch) at which a
+//! build last opened it. `cargo cache` uses this to find sources which no
+//! project needs any more.
+
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use util::{CargoResult, paths};
+
+pub const LAST_USE_FILE: &'static str = ".cargo-last-use";
+
+/// The recorded time is only rewritten once it's this many seconds out of
+/// date, so builds aren't constantly writing to every source they use.
+const GRANULARITY: u64 = 60 * 60;
+
+/// Returns the current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs())
+                     .unwrap_or(0)
+}
+
+/// Records that the source unpacked at `dir` has just been used.
+pub fn record(dir: &Path) -> CargoResult<()> {
+    let now = now();
+    if let Some(prev) = get(dir) {
+        if prev <= now && now - prev < GRANULARITY {
+            return Ok(())
+        }
+    }
+    paths::write(&dir.join(LAST_USE_FILE), now.to_string().as_bytes())
+}
+
+/// Returns when the source unpacked at `dir` was last used.
+///
+/// Sources unpacked before this was tracked fall back to the time at which
+/// the directory itself was last modified.
+pub fn get(dir: &Path) -> Option<u64> {
+    if let Ok(s) = paths::read(&dir.join(LAST_USE_FILE)) {
+        if let Ok(time) = s.trim().parse() {
+            return Some(time)
+        }
+    }
+    fs::metadata(dir).and_then(|m| m.modified()).ok().and_then(|t| {
+        t.duration_since(UNIX_EPOCH).ok()
+    }).map(|d| d.as_secs())
+}
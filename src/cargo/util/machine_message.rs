@@ -0,0 +1,93 @@
+//! Messages printed on stdout with `--message-format=json`, one JSON object
+//! per line. Every object has a `reason` key naming which kind of message it
+//! is, so that tools reading them can skip over the ones they don't know.
+
+use std::path::PathBuf;
+
+use rustc_serialize::Encodable;
+use rustc_serialize::json::{self, Json};
+
+use core::{PackageId, Target, Profile};
+
+pub trait Message: Encodable {
+    fn reason(&self) -> &str;
+}
+
+pub fn emit<T: Message>(t: T) {
+    let json = json::encode(&t).unwrap();
+    let mut json = Json::from_str(&json).unwrap();
+    if let Json::Object(ref mut map) = json {
+        map.insert("reason".to_string(), Json::String(t.reason().to_string()));
+    }
+    println!("{}", json);
+}
+
+/// A diagnostic emitted by the compiler, passed through as it was emitted.
+#[derive(RustcEncodable)]
+pub struct FromCompiler<'a> {
+    pub package_id: &'a PackageId,
+    pub target: &'a Target,
+    pub message: Json,
+}
+
+impl<'a> Message for FromCompiler<'a> {
+    fn reason(&self) -> &str {
+        "compiler-message"
+    }
+}
+
+/// The files produced for a target, and whether they were up to date.
+#[derive(RustcEncodable)]
+pub struct Artifact<'a> {
+    pub package_id: &'a PackageId,
+    pub target: &'a Target,
+    pub profile: ArtifactProfile,
+    pub features: Vec<String>,
+    pub filenames: Vec<String>,
+    pub fresh: bool,
+}
+
+impl<'a> Message for Artifact<'a> {
+    fn reason(&self) -> &str {
+        "compiler-artifact"
+    }
+}
+
+/// The settings an artifact was compiled with. This is kept apart from
+/// `Profile` so that the message stays the same as profiles grow new fields.
+#[derive(RustcEncodable)]
+pub struct ArtifactProfile {
+    pub opt_level: String,
+    pub debuginfo: Option<u32>,
+    pub debug_assertions: bool,
+    pub overflow_checks: bool,
+    pub test: bool,
+}
+
+impl<'a> From<&'a Profile> for ArtifactProfile {
+    fn from(profile: &'a Profile) -> ArtifactProfile {
+        ArtifactProfile {
+            opt_level: profile.opt_level.clone(),
+            debuginfo: profile.debuginfo,
+            debug_assertions: profile.debug_assertions,
+            overflow_checks: profile.overflow_checks,
+            test: profile.test,
+        }
+    }
+}
+
+/// What a build script told Cargo to do.
+#[derive(RustcEncodable)]
+pub struct BuildScript<'a> {
+    pub package_id: &'a PackageId,
+    pub linked_libs: &'a [String],
+    pub linked_paths: &'a [PathBuf],
+    pub cfgs: &'a [String],
+    pub fresh: bool,
+}
+
+impl<'a> Message for BuildScript<'a> {
+    fn reason(&self) -> &str {
+        "build-script-executed"
+    }
+}
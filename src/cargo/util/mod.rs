@@ -32,6 +32,8 @@ pub mod to_url;
 pub mod toml;
 pub mod lev_distance;
 pub mod job;
+pub mod last_use;
+pub mod machine_message;
 pub mod network;
 mod cfg;
 mod dependency_queue;
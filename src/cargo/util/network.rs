@@ -14,20 +14,51 @@ pub fn with_retry<T, E, F>(config: &Config, mut callback: F) -> CargoResult<T>
     where F: FnMut() -> Result<T, E>,
           E: errors::NetworkError
 {
-    let mut remaining = try!(config.net_retry());
+    let mut retry = try!(Retry::new(config));
     loop {
-        match callback() {
-            Ok(ret) => return Ok(ret),
-            Err(ref e) if e.maybe_spurious() && remaining > 0 => {
+        if let Some(ret) = try!(retry.check(callback())) {
+            return Ok(ret)
+        }
+    }
+}
+
+/// The retry state of a single network operation.
+///
+/// This is what `with_retry` is built on, for operations which can't simply
+/// be called in a loop, such as transfers performed concurrently on other
+/// threads.
+pub struct Retry<'a> {
+    config: &'a Config,
+    remaining: i64,
+}
+
+impl<'a> Retry<'a> {
+    pub fn new(config: &'a Config) -> CargoResult<Retry<'a>> {
+        Ok(Retry {
+            config: config,
+            remaining: try!(config.net_retry()),
+        })
+    }
+
+    /// Returns the value of a successful attempt, or `None` if the attempt
+    /// failed with a spurious error and should be made again.
+    pub fn check<T, E>(&mut self, result: Result<T, E>) -> CargoResult<Option<T>>
+        where E: errors::NetworkError
+    {
+        match result {
+            Ok(ret) => Ok(Some(ret)),
+            Err(ref e) if e.maybe_spurious() && self.remaining > 0 => {
                 let msg = format!("spurious network error ({} tries \
-                          remaining): {}", remaining, e);
-                try!(config.shell().warn(msg));
-                remaining -= 1;
+                          remaining): {}", self.remaining, e);
+                try!(self.config.shell().warn(msg));
+                self.remaining -= 1;
+                Ok(None)
             }
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => Err(Box::new(e)),
         }
     }
 }
+
 #[test]
 fn with_retry_repeats_the_call_then_works() {
 
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::default::Default;
 use std::fmt;
 use std::fs;
@@ -13,7 +13,7 @@ use core::{SourceId, Profiles, PackageIdSpec};
 use core::{Summary, Manifest, Target, Dependency, DependencyInner, PackageId,
            GitReference};
 use core::dependency::{Kind, Platform};
-use core::manifest::{LibKind, Profile, ManifestMetadata};
+use core::manifest::{LibKind, Profile, CustomProfile, ManifestMetadata};
 use core::package_id::Metadata;
 use util::{self, CargoResult, human, ToUrl, ToSemver, ChainError, Config};
 
@@ -198,6 +198,8 @@ pub struct DetailedTomlDependency {
     branch: Option<String>,
     tag: Option<String>,
     rev: Option<String>,
+    registry: Option<String>,
+    submodules: Option<bool>,
     features: Option<Vec<String>>,
     optional: Option<bool>,
     default_features: Option<bool>,
@@ -221,24 +223,106 @@ pub struct TomlManifest {
     replace: Option<HashMap<String, TomlDependency>>,
 }
 
-#[derive(RustcDecodable, Clone, Default)]
-pub struct TomlProfiles {
-    test: Option<TomlProfile>,
-    doc: Option<TomlProfile>,
-    bench: Option<TomlProfile>,
-    dev: Option<TomlProfile>,
-    release: Option<TomlProfile>,
+/// The `[profile.*]` tables: the built-in `dev`, `release`, `test`, `bench`
+/// and `doc` profiles, and any custom ones.
+#[derive(Clone, Default)]
+pub struct TomlProfiles(HashMap<String, TomlProfile>);
+
+impl TomlProfiles {
+    fn get(&self, name: &str) -> Option<&TomlProfile> {
+        self.0.get(name)
+    }
+}
+
+impl Decodable for TomlProfiles {
+    fn decode<D: Decoder>(d: &mut D) -> Result<TomlProfiles, D::Error> {
+        HashMap::decode(d).map(TomlProfiles)
+    }
 }
 
 #[derive(RustcDecodable, Clone, Default)]
 pub struct TomlProfile {
-    opt_level: Option<u32>,
+    inherits: Option<String>,
+    opt_level: Option<TomlOptLevel>,
     lto: Option<bool>,
     codegen_units: Option<u32>,
-    debug: Option<bool>,
+    debug: Option<TomlDebugLevel>,
     debug_assertions: Option<bool>,
+    overflow_checks: Option<bool>,
     rpath: Option<bool>,
+    incremental: Option<bool>,
+    strip: Option<TomlStrip>,
     panic: Option<String>,
+    package: Option<BTreeMap<String, TomlProfile>>,
+    build_override: Option<Box<TomlProfile>>,
+}
+
+/// The `opt-level` key of a profile, either a number or `"s"` or `"z"` to
+/// optimize for size.
+#[derive(Clone)]
+pub struct TomlOptLevel(String);
+
+impl Decodable for TomlOptLevel {
+    fn decode<D: Decoder>(d: &mut D) -> Result<TomlOptLevel, D::Error> {
+        match d.read_u32() {
+            Ok(i) => Ok(TomlOptLevel(i.to_string())),
+            Err(_) => {
+                match d.read_str() {
+                    Ok(ref s) if s == "s" || s == "z" => {
+                        Ok(TomlOptLevel(s.to_string()))
+                    }
+                    Ok(_) | Err(_) => {
+                        Err(d.error("expected an integer, a string \"s\" or \
+                                     a string \"z\""))
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The `debug` key of a profile, either a boolean or a debuginfo level of 0,
+/// 1 (line tables only) or 2 (full debuginfo).
+#[derive(Clone)]
+pub struct TomlDebugLevel(Option<u32>);
+
+impl Decodable for TomlDebugLevel {
+    fn decode<D: Decoder>(d: &mut D) -> Result<TomlDebugLevel, D::Error> {
+        let level = match d.read_u32() {
+            Ok(0) => None,
+            Ok(i) if i <= 2 => Some(i),
+            Ok(_) => return Err(d.error("expected a boolean or a debuginfo \
+                                         level of 0, 1 or 2")),
+            Err(_) => {
+                match d.read_bool() {
+                    Ok(true) => Some(2),
+                    Ok(false) => None,
+                    Err(_) => return Err(d.error("expected a boolean or a \
+                                                  debuginfo level of 0, 1 \
+                                                  or 2")),
+                }
+            }
+        };
+        Ok(TomlDebugLevel(level))
+    }
+}
+
+/// The `strip` key of a profile, which is what to strip from the binaries.
+#[derive(Clone)]
+pub struct TomlStrip(String);
+
+impl Decodable for TomlStrip {
+    fn decode<D: Decoder>(d: &mut D) -> Result<TomlStrip, D::Error> {
+        match d.read_str() {
+            Ok(ref s) if s == "symbols" || s == "debuginfo" => {
+                Ok(TomlStrip(s.to_string()))
+            }
+            Ok(_) | Err(_) => {
+                Err(d.error("expected a string \"symbols\" or a string \
+                             \"debuginfo\""))
+            }
+        }
+    }
 }
 
 #[derive(RustcDecodable)]
@@ -600,7 +684,12 @@ impl TomlManifest {
             repository: project.repository.clone(),
             keywords: project.keywords.clone().unwrap_or(Vec::new()),
         };
-        let profiles = build_profiles(&self.profile);
+        // Only the profiles of the root package are used, so invalid ones are
+        // only an error when it comes to that, and a warning otherwise.
+        let (profiles, profile_error) = match build_profiles(&self.profile) {
+            Ok(profiles) => (profiles, None),
+            Err(e) => (try!(build_profiles(&None)), Some(e)),
+        };
         let publish = project.publish.unwrap_or(true);
         let mut manifest = Manifest::new(summary,
                                          targets,
@@ -611,6 +700,9 @@ impl TomlManifest {
                                          profiles,
                                          publish,
                                          replace);
+        if let Some(error) = profile_error {
+            manifest.set_profile_error(layout.root.join("Cargo.toml"), &*error);
+        }
         if project.license_file.is_some() && project.license.is_some() {
             manifest.add_warning(format!("only one of `license` or \
                                           `license-file` is necessary"));
@@ -659,6 +751,17 @@ impl TomlDependency {
             cx.warnings.push(msg);
         }
 
+        if details.registry.is_some() &&
+           (details.git.is_some() || details.path.is_some()) {
+            bail!("dependency ({}) specification is ambiguous. Only one of \
+                   `git`, `path` or `registry` is allowed", name)
+        }
+
+        if details.submodules.is_some() && details.git.is_none() {
+            bail!("dependency ({}) specifies `submodules`, which is only \
+                   allowed for git dependencies", name)
+        }
+
         let reference = details.branch.clone().map(GitReference::Branch)
             .or_else(|| details.tag.clone().map(GitReference::Tag))
             .or_else(|| details.rev.clone().map(GitReference::Rev))
@@ -669,7 +772,9 @@ impl TomlDependency {
                 let loc = try!(git.to_url().map_err(|e| {
                     human(e)
                 }));
-                Some(SourceId::for_git(&loc, reference))
+                let submodules = details.submodules.unwrap_or(true);
+                Some(SourceId::for_git(&loc, reference)
+                              .with_submodules(submodules))
             }
             None => {
                 match details.path.as_ref() {
@@ -694,7 +799,14 @@ impl TomlDependency {
                     None => None,
                 }
             }
-        }.unwrap_or(try!(SourceId::for_central(cx.config)));
+        };
+        let new_source_id = match (new_source_id, details.registry.as_ref()) {
+            (Some(id), _) => id,
+            (None, Some(registry)) => {
+                try!(SourceId::alt_registry(cx.config, registry))
+            }
+            (None, None) => try!(SourceId::for_central(cx.config)),
+        };
 
         let version = details.version.as_ref().map(|v| &v[..]);
         let mut dep = try!(DependencyInner::parse(name, version, &new_source_id));
@@ -1029,49 +1141,307 @@ fn normalize(lib: &Option<TomlLibTarget>,
     ret
 }
 
-fn build_profiles(profiles: &Option<TomlProfiles>) -> Profiles {
-    let profiles = profiles.as_ref();
+const BUILTIN_PROFILES: &'static [&'static str] = &[
+    "dev", "release", "test", "bench", "doc",
+];
+
+// Names which would be mistaken for the directories of the `dev` profile and
+// of `cargo package`
+const RESERVED_PROFILES: &'static [&'static str] = &["debug", "package"];
+
+fn build_profiles(toml: &Option<TomlProfiles>) -> CargoResult<Profiles> {
+    let toml = toml.as_ref();
+    let get = |name| toml.and_then(|p| p.get(name));
     let mut profiles = Profiles {
-        release: merge(Profile::default_release(),
-                       profiles.and_then(|p| p.release.as_ref())),
-        dev: merge(Profile::default_dev(),
-                   profiles.and_then(|p| p.dev.as_ref())),
-        test: merge(Profile::default_test(),
-                    profiles.and_then(|p| p.test.as_ref())),
-        test_deps: merge(Profile::default_dev(),
-                         profiles.and_then(|p| p.dev.as_ref())),
-        bench: merge(Profile::default_bench(),
-                     profiles.and_then(|p| p.bench.as_ref())),
-        bench_deps: merge(Profile::default_release(),
-                          profiles.and_then(|p| p.release.as_ref())),
-        doc: merge(Profile::default_doc(),
-                   profiles.and_then(|p| p.doc.as_ref())),
+        release: merge(Profile::default_release(), get("release")),
+        dev: merge(Profile::default_dev(), get("dev")),
+        test: merge(Profile::default_test(), get("test")),
+        test_deps: merge(Profile::default_dev(), get("dev")),
+        bench: merge(Profile::default_bench(), get("bench")),
+        bench_deps: merge(Profile::default_release(), get("release")),
+        doc: merge(Profile::default_doc(), get("doc")),
         custom_build: Profile::default_custom_build(),
+        check: merge(Profile::default_check(), get("dev")),
+        custom: HashMap::new(),
+        package: Vec::new(),
+        build_override: None,
     };
     profiles.test_deps.panic = None;
     profiles.bench_deps.panic = None;
-    return profiles;
+
+    // Build scripts, plugins and their dependencies get a set of profiles of
+    // their own if `dev` or `release` has a `build-override`.
+    let host = |name| get(name).and_then(|p| p.build_override.as_ref());
+    for &name in BUILTIN_PROFILES.iter() {
+        if let Some(o) = host(name) {
+            if name != "dev" && name != "release" {
+                bail!("`build-override` can only be set in the `dev`, \
+                       `release` and custom profiles, not in `{}`", name)
+            }
+            try!(check_override(o, &format!("`[profile.{}.build-override]`",
+                                            name)));
+        }
+    }
+    if host("dev").is_some() || host("release").is_some() {
+        let for_host = |profile: &Profile, o: Option<&Box<TomlProfile>>| {
+            Profile {
+                build_override: true,
+                ..merge(profile.clone(), o.map(|o| &**o))
+            }
+        };
+        profiles.build_override = Some(Box::new(Profiles {
+            release: for_host(&profiles.release, host("release")),
+            dev: for_host(&profiles.dev, host("dev")),
+            test_deps: for_host(&profiles.test_deps, host("dev")),
+            bench_deps: for_host(&profiles.bench_deps, host("release")),
+            ..profiles.clone()
+        }));
+    }
+
+    // Every package with an override in one of the built-in profiles gets a
+    // set of profiles of its own, with the overrides applied on top.
+    let mut specs = BTreeSet::new();
+    for &name in BUILTIN_PROFILES.iter() {
+        for (spec, o) in get(name).and_then(|p| p.package.as_ref()).iter()
+                                  .flat_map(|p| p.iter()) {
+            try!(check_override(o, &format!("the package override \
+                                             `[profile.{}.package.\"{}\"]`",
+                                            name, spec)));
+            specs.insert(spec);
+        }
+    }
+    for spec in specs {
+        let o = |name| {
+            get(name).and_then(|p| p.package.as_ref()).and_then(|p| p.get(spec))
+        };
+        let package = Profiles {
+            release: merge(profiles.release.clone(), o("release")),
+            dev: merge(profiles.dev.clone(), o("dev")),
+            test: merge(profiles.test.clone(), o("test")),
+            test_deps: merge(profiles.test_deps.clone(), o("dev")),
+            bench: merge(profiles.bench.clone(), o("bench")),
+            bench_deps: merge(profiles.bench_deps.clone(), o("release")),
+            doc: merge(profiles.doc.clone(), o("doc")),
+            custom_build: profiles.custom_build.clone(),
+            check: merge(profiles.check.clone(), o("dev")),
+            custom: HashMap::new(),
+            package: Vec::new(),
+            build_override: profiles.build_override.as_ref().map(|host| {
+                Box::new(Profiles {
+                    release: merge(host.release.clone(), o("release")),
+                    dev: merge(host.dev.clone(), o("dev")),
+                    test_deps: merge(host.test_deps.clone(), o("dev")),
+                    bench_deps: merge(host.bench_deps.clone(), o("release")),
+                    ..(**host).clone()
+                })
+            }),
+        };
+        let spec = try!(parse_override_spec(spec));
+        profiles.package.push((spec, package));
+    }
+    by_specificity(&mut profiles.package);
+
+    if let Some(toml) = toml {
+        for (name, profile) in toml.0.iter() {
+            if BUILTIN_PROFILES.contains(&&name[..]) {
+                if profile.inherits.is_some() {
+                    bail!("`inherits` can't be used in the built-in profile \
+                           `{}`", name)
+                }
+                continue
+            }
+            if RESERVED_PROFILES.contains(&&name[..]) {
+                bail!("profile name `{}` is reserved", name)
+            }
+            if !name.chars().all(|c| c.is_alphanumeric() || c == '-' ||
+                                     c == '_') {
+                bail!("invalid character in profile name `{}`, only \
+                       letters, numbers, `-` and `_` are allowed", name)
+            }
+            let (profile, release, overrides, host) =
+                try!(custom_profile(&profiles, toml, name, &mut Vec::new()));
+            let build_override = if host.is_empty() {
+                None
+            } else {
+                let base = Profile { build_override: true, ..profile.clone() };
+                Some(host.iter().fold(base, |p, o| merge(p, Some(o))))
+            };
+            let mut package = Vec::new();
+            for (spec, o) in overrides {
+                package.push((try!(parse_override_spec(&spec)), CustomProfile {
+                    profile: merge(profile.clone(), Some(&o)),
+                    release: release,
+                    build_override: build_override.as_ref().map(|p| {
+                        merge(p.clone(), Some(&o))
+                    }),
+                    package: Vec::new(),
+                }));
+            }
+            by_specificity(&mut package);
+            profiles.custom.insert(name.clone(), CustomProfile {
+                profile: profile,
+                release: release,
+                build_override: build_override,
+                package: package,
+            });
+        }
+    }
+    return Ok(profiles);
+
+    // Resolves the chain of `inherits` keys of the custom profile `name`,
+    // down to `dev` or `release`. Returns the profile, whether it's based on
+    // `release`, its package overrides including the inherited ones, and the
+    // `build-override` tables of the chain, to be applied in order.
+    fn custom_profile(profiles: &Profiles,
+                      toml: &TomlProfiles,
+                      name: &str,
+                      stack: &mut Vec<String>)
+                      -> CargoResult<(Profile, bool,
+                                      BTreeMap<String, TomlProfile>,
+                                      Vec<TomlProfile>)> {
+        let overrides = |name: &str| {
+            toml.get(name).and_then(|p| p.package.clone())
+                .unwrap_or(BTreeMap::new())
+        };
+        let host = |name: &str| {
+            toml.get(name).and_then(|p| p.build_override.clone())
+                .map(|o| *o).into_iter().collect::<Vec<_>>()
+        };
+        match name {
+            "dev" => {
+                return Ok((profiles.dev.clone(), false, overrides("dev"),
+                           host("dev")))
+            }
+            "release" => {
+                return Ok((profiles.release.clone(), true,
+                           overrides("release"), host("release")))
+            }
+            _ => {}
+        }
+        if BUILTIN_PROFILES.contains(&name) {
+            bail!("profile `{}` can't inherit from `{}`, only from `dev`, \
+                   `release` or a custom profile", stack.last().unwrap(), name)
+        }
+        if stack.iter().any(|n| n == name) {
+            bail!("profile `{}` inherits from itself: {} -> {}",
+                  name, stack.join(" -> "), name)
+        }
+        let toml_profile = match toml.get(name) {
+            Some(profile) => profile,
+            None => bail!("profile `{}` inherits from `{}`, which isn't \
+                           defined", stack.last().unwrap(), name),
+        };
+        let parent = match toml_profile.inherits {
+            Some(ref parent) => parent,
+            None => bail!("profile `{}` must specify which profile it \
+                           `inherits` from", name),
+        };
+        stack.push(name.to_string());
+        let (profile, release, mut package, mut build_override) =
+            try!(custom_profile(profiles, toml, parent, stack));
+        for (spec, o) in overrides(name) {
+            try!(check_override(&o, &format!("the package override \
+                                              `[profile.{}.package.\"{}\"]`",
+                                             name, spec)));
+            let o = match package.remove(&spec) {
+                Some(inherited) => overlay(inherited, o),
+                None => o,
+            };
+            package.insert(spec, o);
+        }
+        for o in host(name) {
+            try!(check_override(&o, &format!("`[profile.{}.build-override]`",
+                                             name)));
+            build_override.push(o);
+        }
+        Ok((merge(profile, Some(toml_profile)), release, package,
+            build_override))
+    }
+
+    // Package overrides and build overrides only change how code is
+    // generated, and can't change anything which has to be the same across
+    // the crates being linked.
+    fn check_override(o: &TomlProfile, table: &str) -> CargoResult<()> {
+        let keys = [("inherits", o.inherits.is_some()),
+                    ("lto", o.lto.is_some()),
+                    ("rpath", o.rpath.is_some()),
+                    ("panic", o.panic.is_some()),
+                    ("package", o.package.is_some()),
+                    ("build-override", o.build_override.is_some())];
+        for &(key, set) in keys.iter() {
+            if set {
+                bail!("`{}` can't be set in {}", key, table)
+            }
+        }
+        Ok(())
+    }
+
+    // Sets the keys of `o` on top of those of `base`.
+    fn overlay(base: TomlProfile, o: TomlProfile) -> TomlProfile {
+        TomlProfile {
+            inherits: o.inherits.or(base.inherits),
+            opt_level: o.opt_level.or(base.opt_level),
+            lto: o.lto.or(base.lto),
+            codegen_units: o.codegen_units.or(base.codegen_units),
+            debug: o.debug.or(base.debug),
+            debug_assertions: o.debug_assertions.or(base.debug_assertions),
+            overflow_checks: o.overflow_checks.or(base.overflow_checks),
+            rpath: o.rpath.or(base.rpath),
+            incremental: o.incremental.or(base.incremental),
+            strip: o.strip.or(base.strip),
+            panic: o.panic.or(base.panic),
+            package: o.package.or(base.package),
+            build_override: o.build_override.or(base.build_override),
+        }
+    }
+
+    // Package overrides are checked in order, so they're sorted to have the
+    // most specific spec matching a package win: one with a version over one
+    // without, then one with a URL over one without.
+    fn by_specificity<T>(overrides: &mut Vec<(PackageIdSpec, T)>) {
+        overrides.sort_by(|a, b| specificity(&b.0).cmp(&specificity(&a.0)));
+
+        fn specificity(spec: &PackageIdSpec) -> (bool, bool) {
+            (spec.version().is_some(), spec.url().is_some())
+        }
+    }
+
+    fn parse_override_spec(spec: &str) -> CargoResult<PackageIdSpec> {
+        PackageIdSpec::parse(spec).chain_error(|| {
+            human(format!("invalid package id specification `{}` in a \
+                           profile override", spec))
+        })
+    }
 
     fn merge(profile: Profile, toml: Option<&TomlProfile>) -> Profile {
         let &TomlProfile {
-            opt_level, lto, codegen_units, debug, debug_assertions, rpath,
-            ref panic
+            ref opt_level, lto, codegen_units, ref debug, debug_assertions,
+            overflow_checks, rpath, incremental, ref strip, ref panic,
+            inherits: _, package: _, build_override: _,
         } = match toml {
             Some(toml) => toml,
             None => return profile,
         };
         Profile {
-            opt_level: opt_level.unwrap_or(profile.opt_level),
+            opt_level: opt_level.as_ref().map(|o| o.0.clone())
+                                .unwrap_or(profile.opt_level),
             lto: lto.unwrap_or(profile.lto),
-            codegen_units: codegen_units,
+            codegen_units: codegen_units.or(profile.codegen_units),
             rustc_args: None,
             rustdoc_args: None,
-            debuginfo: debug.unwrap_or(profile.debuginfo),
+            debuginfo: debug.as_ref().map(|d| d.0).unwrap_or(profile.debuginfo),
             debug_assertions: debug_assertions.unwrap_or(profile.debug_assertions),
+            // Overflow checks follow debug assertions unless set on their own
+            overflow_checks: overflow_checks.or(debug_assertions)
+                                            .unwrap_or(profile.overflow_checks),
             rpath: rpath.unwrap_or(profile.rpath),
+            incremental: incremental.unwrap_or(profile.incremental),
+            strip: strip.as_ref().map(|s| s.0.clone()).or(profile.strip),
             test: profile.test,
             doc: profile.doc,
             run_custom_build: profile.run_custom_build,
+            check: profile.check,
+            build_override: profile.build_override,
             panic: panic.clone().or(profile.panic),
         }
     }
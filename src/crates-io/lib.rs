@@ -10,7 +10,7 @@ use std::io::{self, Cursor};
 use std::result;
 
 use curl::easy::{Easy, List};
-use rustc_serialize::json;
+use rustc_serialize::{json, Encodable, Encoder};
 
 use url::percent_encoding::{percent_encode, QUERY_ENCODE_SET};
 
@@ -83,7 +83,6 @@ pub struct NewCrate {
     pub repository: Option<String>,
 }
 
-#[derive(RustcEncodable)]
 pub struct NewCrateDependency {
     pub optional: bool,
     pub default_features: bool,
@@ -92,6 +91,34 @@ pub struct NewCrateDependency {
     pub version_req: String,
     pub target: Option<String>,
     pub kind: String,
+    /// Index URL of the registry the dependency comes from, if it's not the
+    /// one the crate is published to.
+    pub registry: Option<String>,
+}
+
+// Written out by hand so `registry` is left out entirely for dependencies in
+// the same registry, rather than being sent as `null`.
+impl Encodable for NewCrateDependency {
+    fn encode<S: Encoder>(&self, s: &mut S) -> result::Result<(), S::Error> {
+        let len = if self.registry.is_some() {8} else {7};
+        s.emit_struct("NewCrateDependency", len, |s| {
+            try!(s.emit_struct_field("optional", 0, |s| self.optional.encode(s)));
+            try!(s.emit_struct_field("default_features", 1, |s| {
+                self.default_features.encode(s)
+            }));
+            try!(s.emit_struct_field("name", 2, |s| self.name.encode(s)));
+            try!(s.emit_struct_field("features", 3, |s| self.features.encode(s)));
+            try!(s.emit_struct_field("version_req", 4, |s| {
+                self.version_req.encode(s)
+            }));
+            try!(s.emit_struct_field("target", 5, |s| self.target.encode(s)));
+            try!(s.emit_struct_field("kind", 6, |s| self.kind.encode(s)));
+            if let Some(ref registry) = self.registry {
+                try!(s.emit_struct_field("registry", 7, |s| registry.encode(s)));
+            }
+            Ok(())
+        })
+    }
 }
 
 #[derive(RustcDecodable)]
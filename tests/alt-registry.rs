@@ -0,0 +1,123 @@
+#[macro_use]
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::File;
+use std::io::prelude::*;
+
+use cargotest::support::registry::{self, Package};
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+#[test]
+fn depend_on_alt_registry() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry = "alternative"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    Package::new("bar", "0.0.1").alternative(true).publish();
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[UPDATING] registry `{reg}`
+[DOWNLOADING] bar v0.0.1 (registry file://[..])
+[COMPILING] bar v0.0.1 (registry file://[..])
+[COMPILING] foo v0.0.1 ({dir})
+",
+        dir = p.url(),
+        reg = registry::alt_registry())));
+
+    // The lock file records the alternate registry by URL.
+    assert_that(p.cargo("build"), execs().with_status(0));
+    let mut lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock"))).read_to_string(&mut lock));
+    assert!(lock.contains(&format!("registry+{}", registry::alt_registry())),
+            "bad lockfile:\n{}", lock);
+}
+
+#[test]
+fn alt_registry_depends_on_crates_io() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry = "alternative"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    Package::new("baz", "0.0.1").publish();
+    Package::new("bar", "0.0.1")
+        .registry_dep("baz", "0.0.1", "crates-io")
+        .alternative(true)
+        .publish();
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0)
+                       .with_stderr_contains(&format!("\
+[UPDATING] registry `{}`", registry::alt_registry()))
+                       .with_stderr_contains(&format!("\
+[UPDATING] registry `{}`", registry::registry()))
+                       .with_stderr_contains("\
+[COMPILING] baz v0.0.1 (registry file://[..])")
+                       .with_stderr_contains("\
+[COMPILING] bar v0.0.1 (registry file://[..])"));
+}
+
+#[test]
+fn registry_and_path_dep_fails() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "bar"
+            registry = "alternative"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]dependency (bar) specification is ambiguous. Only one of `git`, `path` or \
+`registry` is allowed
+"));
+}
+
+#[test]
+fn unknown_registry() {
+    Package::new("bar", "0.0.1").alternative(true).publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            version = "0.0.1"
+            registry = "nope"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]no index found for registry: `nope`
+"));
+}
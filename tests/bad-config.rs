@@ -143,6 +143,27 @@ fn bad_cargo_config_jobs() {
 "));
 }
 
+#[test]
+fn bad_cargo_config_freshness() {
+    let foo = project("foo")
+    .file("Cargo.toml", r#"
+        [package]
+        name = "foo"
+        version = "0.0.0"
+        authors = []
+    "#)
+    .file("src/lib.rs", "")
+    .file(".cargo/config", r#"
+        [build]
+        freshness = "ctime"
+    "#);
+    assert_that(foo.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] build.freshness must be `mtime` or `content-hash`, but found `ctime` \
+in [..]
+"));
+}
+
 #[test]
 fn default_cargo_config_jobs() {
     let foo = project("foo")
@@ -399,6 +399,8 @@ fn only_rerun_build_script() {
     assert_that(p.cargo("build").arg("-v"),
                 execs().with_status(0)
                        .with_stderr("\
+[DIRTY] foo v0.5.0 ([..]) (build script execution): \
+    the file `[..]` has changed
 [COMPILING] foo v0.5.0 (file://[..])
 [RUNNING] `[..]build-script-build[..]`
 [RUNNING] `rustc [..] --crate-name foo [..]`
@@ -486,6 +488,8 @@ fn testing_and_such() {
     assert_that(p.cargo("test").arg("-vj1"),
                 execs().with_status(0)
                        .with_stderr("\
+[DIRTY] foo v0.5.0 ([..]) (build script execution): \
+    the file `[..]` has changed
 [COMPILING] foo v0.5.0 (file://[..])
 [RUNNING] `[..]build-script-build[..]`
 [RUNNING] `rustc [..] --crate-name foo [..]`
@@ -1713,6 +1717,8 @@ fn adding_an_override_invalidates() {
 
     assert_that(p.cargo("build").arg("-v"),
                 execs().with_status(0).with_stderr("\
+[DIRTY] foo v0.5.0 ([..]) (build script execution): \
+    the overridden output of the build script has changed
 [COMPILING] foo v0.5.0 ([..]
 [RUNNING] `rustc [..] -L native=bar`
 "));
@@ -1750,6 +1756,8 @@ fn changing_an_override_invalidates() {
 
     assert_that(p.cargo("build").arg("-v"),
                 execs().with_status(0).with_stderr("\
+[DIRTY] foo v0.5.0 ([..]) (build script execution): \
+    the overridden output of the build script has changed
 [COMPILING] foo v0.5.0 ([..]
 [RUNNING] `rustc [..] -L native=bar`
 "));
@@ -1781,6 +1789,8 @@ fn rebuild_only_on_explicit_paths() {
     println!("run without");
     assert_that(p.cargo("build").arg("-v"),
                 execs().with_status(0).with_stderr("\
+[DIRTY] a v0.5.0 ([..]) (build script execution): \
+    the file `[..]foo` is missing
 [COMPILING] a v0.5.0 ([..])
 [RUNNING] `[..]build-script-build[..]`
 [RUNNING] `rustc src[..]lib.rs [..]`
@@ -1794,6 +1804,8 @@ fn rebuild_only_on_explicit_paths() {
     println!("run with");
     assert_that(p.cargo("build").arg("-v"),
                 execs().with_status(0).with_stderr("\
+[DIRTY] a v0.5.0 ([..]) (build script execution): \
+    the file `[..]foo` has changed
 [COMPILING] a v0.5.0 ([..])
 [RUNNING] `[..]build-script-build[..]`
 [RUNNING] `rustc src[..]lib.rs [..]`
@@ -1820,6 +1832,8 @@ fn rebuild_only_on_explicit_paths() {
     File::create(p.root().join("foo")).unwrap();
     assert_that(p.cargo("build").arg("-v"),
                 execs().with_status(0).with_stderr("\
+[DIRTY] a v0.5.0 ([..]) (build script execution): \
+    the file `[..]foo` has changed
 [COMPILING] a v0.5.0 ([..])
 [RUNNING] `[..]build-script-build[..]`
 [RUNNING] `rustc src[..]lib.rs [..]`
@@ -1830,6 +1844,8 @@ fn rebuild_only_on_explicit_paths() {
     fs::remove_file(p.root().join("bar")).unwrap();
     assert_that(p.cargo("build").arg("-v"),
                 execs().with_status(0).with_stderr("\
+[DIRTY] a v0.5.0 ([..]) (build script execution): \
+    the file `[..]bar` is missing
 [COMPILING] a v0.5.0 ([..])
 [RUNNING] `[..]build-script-build[..]`
 [RUNNING] `rustc src[..]lib.rs [..]`
@@ -2212,3 +2212,277 @@ fn panic_abort_compiles_with_panic_abort() {
                 execs().with_status(0)
                        .with_stderr_contains("[..] -C panic=abort [..]"));
 }
+
+#[test]
+fn compiler_json_error_format() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+
+            name = "foo"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "fn main() { let unused = 92; }")
+        .file("bar/Cargo.toml", r#"
+            [project]
+
+            name = "bar"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+        "#)
+        .file("bar/src/lib.rs", "fn dead() {}");
+
+    assert_that(p.cargo_process("build").arg("-v")
+                    .arg("--message-format").arg("json"),
+                execs().with_status(0).with_json(r#"
+    {
+        "reason":"compiler-message",
+        "package_id":"bar 0.5.0 ([..])",
+        "target":{"kind":["lib"],"name":"bar","src_path":"[..]lib.rs"},
+        "message":"{...}"
+    }
+
+    {
+        "reason":"compiler-artifact",
+        "package_id":"bar 0.5.0 ([..])",
+        "target":{"kind":["lib"],"name":"bar","src_path":"[..]lib.rs"},
+        "profile":{
+            "opt_level":"0",
+            "debuginfo":2,
+            "debug_assertions":true,
+            "overflow_checks":true,
+            "test":false
+        },
+        "features":[],
+        "filenames":["[..]libbar-[..].rlib"],
+        "fresh":false
+    }
+
+    {
+        "reason":"compiler-message",
+        "package_id":"foo 0.5.0 ([..])",
+        "target":{"kind":["bin"],"name":"foo","src_path":"[..]main.rs"},
+        "message":"{...}"
+    }
+
+    {
+        "reason":"compiler-artifact",
+        "package_id":"foo 0.5.0 ([..])",
+        "target":{"kind":["bin"],"name":"foo","src_path":"[..]main.rs"},
+        "profile":{
+            "opt_level":"0",
+            "debuginfo":2,
+            "debug_assertions":true,
+            "overflow_checks":true,
+            "test":false
+        },
+        "features":[],
+        "filenames":["[..]foo[..]"],
+        "fresh":false
+    }
+"#));
+
+    // Nothing is recompiled, but the artifacts are still reported
+    assert_that(p.cargo("build").arg("--message-format").arg("json"),
+                execs().with_status(0).with_json(r#"
+    {
+        "reason":"compiler-artifact",
+        "package_id":"bar 0.5.0 ([..])",
+        "target":{"kind":["lib"],"name":"bar","src_path":"[..]lib.rs"},
+        "profile":"{...}",
+        "features":[],
+        "filenames":["[..]libbar-[..].rlib"],
+        "fresh":true
+    }
+
+    {
+        "reason":"compiler-artifact",
+        "package_id":"foo 0.5.0 ([..])",
+        "target":{"kind":["bin"],"name":"foo","src_path":"[..]main.rs"},
+        "profile":"{...}",
+        "features":[],
+        "filenames":["[..]foo[..]"],
+        "fresh":true
+    }
+"#));
+}
+
+#[test]
+fn build_script_json_messages() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rustc-cfg=xyz");
+                println!("cargo:rustc-link-search=native=/path/to/native");
+                println!("cargo:rustc-link-lib=foo");
+            }
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--message-format").arg("json"),
+                execs().with_status(0).with_json(r#"
+    {
+        "reason":"compiler-artifact",
+        "package_id":"foo 0.5.0 ([..])",
+        "target":{"kind":["custom-build"],"name":"build-script-build",
+                  "src_path":"[..]build.rs"},
+        "profile":"{...}",
+        "features":[],
+        "filenames":["[..]build-script-build[..]"],
+        "fresh":false
+    }
+
+    {
+        "reason":"build-script-executed",
+        "package_id":"foo 0.5.0 ([..])",
+        "linked_libs":["foo"],
+        "linked_paths":["native=/path/to/native"],
+        "cfgs":["xyz"],
+        "fresh":false
+    }
+
+    {
+        "reason":"compiler-artifact",
+        "package_id":"foo 0.5.0 ([..])",
+        "target":{"kind":["lib"],"name":"foo","src_path":"[..]lib.rs"},
+        "profile":"{...}",
+        "features":[],
+        "filenames":["[..]libfoo.rlib"],
+        "fresh":false
+    }
+"#));
+}
+
+#[test]
+fn build_plan() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+
+            name = "foo"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [project]
+
+            name = "bar"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--build-plan"),
+                execs().with_status(0).with_json(r#"
+    {
+        "invocations":[
+            {
+                "package_name":"bar",
+                "package_version":"0.5.0",
+                "target_kind":["lib"],
+                "kind":"host",
+                "deps":[],
+                "outputs":["[..]deps[..]libbar-[..].rlib"],
+                "links":{},
+                "program":"[..]rustc[..]",
+                "args":"{...}",
+                "env":"{...}",
+                "cwd":"[..]"
+            },
+            {
+                "package_name":"foo",
+                "package_version":"0.5.0",
+                "target_kind":["bin"],
+                "kind":"host",
+                "deps":[0],
+                "outputs":["[..]debug[..]foo[..]"],
+                "links":{},
+                "program":"[..]rustc[..]",
+                "args":"{...}",
+                "env":"{...}",
+                "cwd":"[..]"
+            }
+        ],
+        "inputs":["[..]foo[..]Cargo.toml", "[..]bar[..]Cargo.toml"]
+    }
+"#));
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+}
+
+#[test]
+fn build_plan_with_build_script() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+
+            name = "foo"
+            version = "0.5.0"
+            authors = ["wycats@example.com"]
+            build = "build.rs"
+        "#)
+        .file("build.rs", "fn main() {}")
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--build-plan"),
+                execs().with_status(0).with_json(r#"
+    {
+        "invocations":[
+            {
+                "package_name":"foo",
+                "package_version":"0.5.0",
+                "target_kind":["custom-build"],
+                "kind":"host",
+                "deps":[],
+                "outputs":["[..]build_script_build[..]"],
+                "links":"{...}",
+                "program":"[..]rustc[..]",
+                "args":"{...}",
+                "env":"{...}",
+                "cwd":"[..]"
+            },
+            {
+                "package_name":"foo",
+                "package_version":"0.5.0",
+                "target_kind":["custom-build"],
+                "kind":"host",
+                "deps":[0],
+                "outputs":[],
+                "links":{},
+                "program":"[..]build-script-build",
+                "args":[],
+                "env":"{...}",
+                "cwd":"[..]"
+            },
+            {
+                "package_name":"foo",
+                "package_version":"0.5.0",
+                "target_kind":["lib"],
+                "kind":"host",
+                "deps":[1],
+                "outputs":["[..]libfoo.rlib"],
+                "links":{},
+                "program":"[..]rustc[..]",
+                "args":"{...}",
+                "env":"{...}",
+                "cwd":"[..]"
+            }
+        ],
+        "inputs":["[..]Cargo.toml"]
+    }
+"#));
+}
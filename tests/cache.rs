@@ -0,0 +1,120 @@
+#[macro_use]
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+use cargotest::support::paths;
+use cargotest::support::git;
+use cargotest::support::registry::Package;
+use cargotest::support::{project, execs, ProjectBuilder};
+use hamcrest::{assert_that, existing_dir, is_not};
+
+fn project_with_deps() -> ProjectBuilder {
+    Package::new("bar", "0.1.0").publish();
+    let baz = git::new("baz", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "baz"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+    }).unwrap();
+
+    project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+            baz = {{ git = '{}' }}
+        "#, baz.url()))
+        .file("src/lib.rs", "")
+}
+
+/// Returns the unpacked sources of `bar` and the checkout of `baz`.
+fn cached_sources() -> (PathBuf, PathBuf) {
+    let src = paths::home().join(".cargo/registry/src");
+    let src = t!(t!(fs::read_dir(&src)).next().unwrap()).path();
+    let checkouts = paths::home().join(".cargo/git/checkouts");
+    let checkouts = t!(t!(fs::read_dir(&checkouts)).next().unwrap()).path();
+    (src.join("bar-0.1.0"), checkouts.join("master"))
+}
+
+#[test]
+fn reports_usage() {
+    let p = project_with_deps();
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    assert_that(p.cargo("cache"),
+                execs().with_status(0)
+                       .with_stdout_contains("[..] git/baz-[..]")
+                       .with_stdout_contains("[..] registry/[..]")
+                       .with_stdout_contains("[..] total"));
+}
+
+#[test]
+fn empty_cache() {
+    let p = project("foo");
+    assert_that(p.cargo_process("cache"),
+                execs().with_status(0).with_stdout("\
+       0 B total
+"));
+}
+
+#[test]
+fn remove_unused_sources() {
+    let p = project_with_deps();
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let (bar, baz) = cached_sources();
+    assert_that(&bar, existing_dir());
+    assert_that(&baz, existing_dir());
+
+    // Everything was used within the last day
+    assert_that(p.cargo("cache").arg("--remove-unused").arg("1"),
+                execs().with_status(0).with_stderr(""));
+    assert_that(&bar, existing_dir());
+    assert_that(&baz, existing_dir());
+
+    // Pretend nothing has been used for a couple of days
+    for dir in &[&bar, &baz] {
+        t!(t!(File::create(dir.join(".cargo-last-use"))).write_all(b"0"));
+    }
+    assert_that(p.cargo("cache").arg("--remove-unused").arg("1"),
+                execs().with_status(0).with_stderr_contains("\
+[REMOVING] [..]bar-0.1.0
+").with_stderr_contains("\
+[REMOVING] [..]master
+"));
+    assert_that(&bar, is_not(existing_dir()));
+    assert_that(&baz, is_not(existing_dir()));
+
+    // And they're brought back the next time they're needed
+    assert_that(p.cargo("build"), execs().with_status(0));
+    assert_that(&bar, existing_dir());
+    assert_that(&baz, existing_dir());
+}
+
+#[test]
+fn builds_record_last_use() {
+    let p = project_with_deps();
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let (bar, baz) = cached_sources();
+    for dir in &[&bar, &baz] {
+        t!(t!(File::create(dir.join(".cargo-last-use"))).write_all(b"0"));
+    }
+    assert_that(p.cargo("build"), execs().with_status(0));
+
+    assert_that(p.cargo("cache").arg("--remove-unused").arg("1"),
+                execs().with_status(0).with_stderr(""));
+    assert_that(&bar, existing_dir());
+    assert_that(&baz, existing_dir());
+}
@@ -247,7 +247,7 @@ pub struct Execs {
     expect_exit_code: Option<i32>,
     expect_stdout_contains: Vec<String>,
     expect_stderr_contains: Vec<String>,
-    expect_json: Option<Json>,
+    expect_json: Option<Vec<Json>>,
 }
 
 impl Execs {
@@ -276,8 +276,13 @@ impl Execs {
         self
     }
 
+    /// Expects stdout to be one JSON object per line, matching the objects
+    /// in `expected` which are separated by blank lines. A string `"{...}"`
+    /// in the expected objects matches any value.
     pub fn with_json(mut self, expected: &str) -> Execs {
-        self.expect_json = Some(Json::from_str(expected).unwrap());
+        self.expect_json = Some(expected.split("\n\n").map(|obj| {
+            Json::from_str(obj).unwrap()
+        }).collect());
         self
     }
 
@@ -314,7 +319,18 @@ impl Execs {
         }
 
         if let Some(ref expect_json) = self.expect_json {
-            try!(self.match_json(expect_json, &actual.stdout));
+            let stdout = match str::from_utf8(&actual.stdout) {
+                Err(..) => return Err("stdout was not utf8 encoded".to_owned()),
+                Ok(stdout) => stdout,
+            };
+            let lines = stdout.lines().collect::<Vec<_>>();
+            if lines.len() != expect_json.len() {
+                return Err(format!("expected {} json lines, got {}, stdout:\n{}",
+                                   expect_json.len(), lines.len(), stdout))
+            }
+            for (expected, line) in expect_json.iter().zip(lines) {
+                try!(self.match_json(expected, line));
+            }
         }
         Ok(())
     }
@@ -364,12 +380,7 @@ impl Execs {
 
     }
 
-    fn match_json(&self, expected: &Json, stdout: &[u8]) -> ham::MatchResult {
-        let stdout = match str::from_utf8(stdout) {
-            Err(..) => return Err("stdout was not utf8 encoded".to_owned()),
-            Ok(stdout) => stdout,
-        };
-
+    fn match_json(&self, expected: &Json, stdout: &str) -> ham::MatchResult {
         let actual = match Json::from_str(stdout) {
              Err(..) => return Err(format!("Invalid json {}", stdout)),
              Ok(actual) => actual,
@@ -454,6 +465,7 @@ fn find_mismatch<'a>(expected: &'a Json, actual: &'a Json) -> Option<(&'a Json,
         (&U64(l), &U64(r)) if l == r => None,
         (&Boolean(l), &Boolean(r)) if l == r => None,
         (&String(ref l), &String(ref r)) if lines_match(l, r) => None,
+        (&String(ref l), _) if l == "{...}" => None,
         (&Array(ref l), &Array(ref r)) => {
             if l.len() != r.len() {
                 return Some((expected, actual));
@@ -635,10 +647,12 @@ fn substitute_macros(input: &str) -> String {
     let macros = [
         ("[RUNNING]",     "     Running"),
         ("[COMPILING]",   "   Compiling"),
+        ("[CHECKING]",    "    Checking"),
         ("[ERROR]",       "error:"),
         ("[WARNING]",     "warning:"),
         ("[DOCUMENTING]", " Documenting"),
         ("[FRESH]",       "       Fresh"),
+        ("[DIRTY]",       "       Dirty"),
         ("[UPDATING]",    "    Updating"),
         ("[ADDING]",      "      Adding"),
         ("[REMOVING]",    "    Removing"),
@@ -649,7 +663,8 @@ fn substitute_macros(input: &str) -> String {
         ("[VERIFYING]",   "   Verifying"),
         ("[ARCHIVING]",   "   Archiving"),
         ("[INSTALLING]",  "  Installing"),
-        ("[REPLACING]",   "   Replacing")
+        ("[REPLACING]",   "   Replacing"),
+        ("[UNPACKING]",   "   Unpacking")
     ];
     let mut result = input.to_owned();
     for &(pat, subst) in macros.iter() {
@@ -19,6 +19,10 @@ pub fn registry_path() -> PathBuf { paths::root().join("registry") }
 pub fn registry() -> Url { Url::from_file_path(&*registry_path()).ok().unwrap() }
 pub fn dl_path() -> PathBuf { paths::root().join("dl") }
 pub fn dl_url() -> Url { Url::from_file_path(&*dl_path()).ok().unwrap() }
+pub fn alt_registry_path() -> PathBuf { paths::root().join("alternative-registry") }
+pub fn alt_registry() -> Url { Url::from_file_path(&*alt_registry_path()).ok().unwrap() }
+pub fn alt_dl_path() -> PathBuf { paths::root().join("alt_dl") }
+pub fn alt_dl_url() -> Url { Url::from_file_path(&*alt_dl_path()).ok().unwrap() }
 
 pub struct Package {
     name: String,
@@ -27,6 +31,8 @@ pub struct Package {
     files: Vec<(String, String)>,
     yanked: bool,
     features: HashMap<String, Vec<String>>,
+    local: bool,
+    alternative: bool,
 }
 
 struct Dependency {
@@ -35,6 +41,7 @@ struct Dependency {
     kind: String,
     target: Option<String>,
     features: Vec<String>,
+    registry: Option<String>,
 }
 
 fn init() {
@@ -47,7 +54,10 @@ fn init() {
         [registry]
             index = "{reg}"
             token = "api-token"
-    "#, reg = registry()).as_bytes()));
+
+        [registries.alternative]
+            index = "{alt}"
+    "#, reg = registry(), alt = alt_registry()).as_bytes()));
 
     // Init a new registry
     repo(&registry_path())
@@ -55,6 +65,13 @@ fn init() {
             {{"dl":"{}","api":""}}
         "#, dl_url()))
         .build();
+
+    // Init an alternative registry
+    repo(&alt_registry_path())
+        .file("config.json", &format!(r#"
+            {{"dl":"{}","api":""}}
+        "#, alt_dl_url()))
+        .build();
 }
 
 impl Package {
@@ -67,9 +84,25 @@ impl Package {
             files: Vec::new(),
             yanked: false,
             features: HashMap::new(),
+            local: false,
+            alternative: false,
         }
     }
 
+    /// Publishes this package into the registry named `alternative` instead
+    /// of the default registry.
+    pub fn alternative(&mut self, alternative: bool) -> &mut Package {
+        self.alternative = alternative;
+        self
+    }
+
+    /// Publishes this package into a local registry at `registry_path()`
+    /// instead of into the git index.
+    pub fn local(&mut self, local: bool) -> &mut Package {
+        self.local = local;
+        self
+    }
+
     pub fn file(&mut self, name: &str, contents: &str) -> &mut Package {
         self.files.push((name.to_string(), contents.to_string()));
         self
@@ -109,10 +142,20 @@ impl Package {
             kind: kind.to_string(),
             target: target.map(|s| s.to_string()),
             features: features.iter().map(|s| s.to_string()).collect(),
+            registry: None,
         });
         self
     }
 
+    /// Adds a dependency on a package in the named registry `registry`
+    /// (either `alternative` or the default, `crates-io`).
+    pub fn registry_dep(&mut self, name: &str, vers: &str,
+                        registry: &str) -> &mut Package {
+        self.full_dep(name, vers, None, "normal", &[]);
+        self.deps.last_mut().unwrap().registry = Some(registry.to_string());
+        self
+    }
+
     pub fn yanked(&mut self, yanked: bool) -> &mut Package {
         self.yanked = yanked;
         self
@@ -131,6 +174,17 @@ impl Package {
             map.insert("target".to_string(), dep.target.to_json());
             map.insert("optional".to_string(), false.to_json());
             map.insert("kind".to_string(), dep.kind.to_json());
+            // Dependencies on other registries are listed by index URL
+            let registry = match dep.registry.as_ref().map(|s| &s[..]) {
+                Some("alternative") if !self.alternative => {
+                    Some(alt_registry().to_string())
+                }
+                Some("crates-io") if self.alternative => {
+                    Some(registry().to_string())
+                }
+                _ => None,
+            };
+            map.insert("registry".to_string(), registry.to_json());
             map
         }).collect::<Vec<_>>();
         let cksum = {
@@ -155,15 +209,29 @@ impl Package {
         };
 
         // Write file/line in the index
-        let dst = registry_path().join(&file);
+        let registry_path = if self.alternative {
+            alt_registry_path()
+        } else {
+            registry_path()
+        };
+        let dst = if self.local {
+            registry_path.join("index").join(&file)
+        } else {
+            registry_path.join(&file)
+        };
         let mut prev = String::new();
         let _ = File::open(&dst).and_then(|mut f| f.read_to_string(&mut prev));
         t!(fs::create_dir_all(dst.parent().unwrap()));
         t!(t!(File::create(&dst))
                   .write_all((prev + &line[..] + "\n").as_bytes()));
 
+        // Local registries are just read off the filesystem
+        if self.local {
+            return
+        }
+
         // Add the new file to the index
-        let repo = t!(git2::Repository::open(&registry_path()));
+        let repo = t!(git2::Repository::open(&registry_path));
         let mut index = t!(repo.index());
         t!(index.add_path(Path::new(&file)));
         t!(index.write());
@@ -200,6 +268,9 @@ impl Package {
                 [{}{}dependencies.{}]
                 version = "{}"
             "#, target, kind, dep.name, dep.vers));
+            if let Some(ref registry) = dep.registry {
+                manifest.push_str(&format!("registry = \"{}\"\n", registry));
+            }
         }
 
         let dst = self.archive_dst();
@@ -226,11 +297,16 @@ impl Package {
     }
 
     pub fn archive_dst(&self) -> PathBuf {
-        dl_path().join(&self.name).join(&self.vers).join("download")
+        if self.local {
+            return registry_path().join(format!("{}-{}.crate", self.name,
+                                                self.vers))
+        }
+        let dl_path = if self.alternative {alt_dl_path()} else {dl_path()};
+        dl_path.join(&self.name).join(&self.vers).join("download")
     }
 }
 
-fn cksum(s: &[u8]) -> String {
+pub fn cksum(s: &[u8]) -> String {
     let mut sha = Sha256::new();
     sha.update(s);
     sha.finish().to_hex()
@@ -0,0 +1,180 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use cargotest::support::{project, execs, path2url};
+use hamcrest::{assert_that, existing_file, is_not};
+
+#[test]
+fn check_success() {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "../bar"
+        "#)
+        .file("src/main.rs", r#"
+            extern crate bar;
+            fn main() {
+                ::bar::baz();
+            }
+        "#);
+    let bar = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", r#"
+            pub fn baz() {}
+        "#);
+    bar.build();
+
+    assert_that(foo.cargo_process("check"),
+                execs().with_status(0).with_stderr(&format!("\
+[CHECKING] bar v0.1.0 ({bar})
+[CHECKING] foo v0.0.1 ({foo})
+", bar = path2url(bar.root()), foo = path2url(foo.root()))));
+    assert_that(&foo.bin("foo"), is_not(existing_file()));
+}
+
+#[test]
+fn check_fail() {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies.bar]
+            path = "../bar"
+        "#)
+        .file("src/main.rs", r#"
+            extern crate bar;
+            fn main() {
+                ::bar::baz(42);
+            }
+        "#);
+    let bar = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", r#"
+            pub fn baz() {}
+        "#);
+    bar.build();
+
+    assert_that(foo.cargo_process("check"),
+                execs().with_status(101));
+}
+
+#[test]
+fn check_uses_metadata() {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(foo.cargo_process("check").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc src[..]lib.rs --crate-name foo --crate-type lib -g \
+        -C metadata=[..] -C extra-filename=[..] \
+        --out-dir [..]target[..]debug[..].check \
+        --emit=dep-info,metadata [..]`"));
+    assert_that(&foo.root().join("target/debug/libfoo.rlib"),
+                is_not(existing_file()));
+}
+
+#[test]
+fn check_and_build_are_kept_apart() {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(foo.cargo_process("check"),
+                execs().with_status(0).with_stderr(&format!("\
+[CHECKING] foo v0.0.1 ({dir})
+", dir = path2url(foo.root()))));
+
+    // A check doesn't leave anything behind that a build could reuse
+    assert_that(foo.cargo("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] foo v0.0.1 ({dir})
+", dir = path2url(foo.root()))));
+    assert_that(&foo.bin("foo"), existing_file());
+
+    // ... and neither one makes the other dirty
+    assert_that(foo.cargo("check"),
+                execs().with_status(0).with_stderr(""));
+    assert_that(foo.cargo("build"),
+                execs().with_status(0).with_stderr(""));
+}
+
+#[test]
+fn check_runs_build_scripts() {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            build = "build.rs"
+        "#)
+        .file("build.rs", r#"
+            fn main() {
+                println!("cargo:rustc-cfg=from_build_script");
+            }
+        "#)
+        .file("src/lib.rs", r#"
+            #[cfg(from_build_script)]
+            pub fn foo() {}
+
+            pub fn bar() { foo() }
+        "#);
+
+    assert_that(foo.cargo_process("check"),
+                execs().with_status(0));
+}
+
+#[test]
+fn check_message_format_json() {
+    let foo = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(foo.cargo_process("check").arg("--message-format").arg("json"),
+                execs().with_status(0).with_json(r#"
+    {
+        "reason":"compiler-artifact",
+        "package_id":"foo 0.0.1 ([..])",
+        "target":{"kind":["lib"],"name":"foo","src_path":"[..]lib.rs"},
+        "profile":"{...}",
+        "features":[],
+        "filenames":"{...}",
+        "fresh":false
+    }
+"#));
+}
@@ -136,6 +136,43 @@ fn clean_release() {
 "));
 }
 
+#[test]
+fn clean_custom_profile() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            a = { path = "a" }
+
+            [profile.ci]
+            inherits = "release"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("a/Cargo.toml", r#"
+            [package]
+            name = "a"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("a/src/lib.rs", "");
+    p.build();
+
+    assert_that(p.cargo_process("build").arg("--profile").arg("ci"),
+                execs().with_status(0));
+    assert!(p.root().join("target/profiles/ci").is_dir());
+
+    assert_that(p.cargo("clean").arg("-p").arg("foo"),
+                execs().with_status(0));
+    assert_that(p.cargo("build").arg("--profile").arg("ci"),
+                execs().with_status(0).with_stderr("\
+[COMPILING] foo v0.0.1 ([..])
+"));
+}
+
 #[test]
 fn build_script() {
     let p = project("foo")
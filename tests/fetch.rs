@@ -1,8 +1,16 @@
+#[macro_use]
 extern crate cargotest;
+extern crate git2;
 extern crate hamcrest;
 
-use cargotest::support::{project, execs};
-use hamcrest::assert_that;
+use std::fs::File;
+use std::io::prelude::*;
+
+use cargotest::support::git;
+use cargotest::support::paths::{self, CargoPathExt};
+use cargotest::support::registry::Package;
+use cargotest::support::{project, execs, ProjectBuilder};
+use hamcrest::{assert_that, existing_file};
 
 #[test]
 fn no_deps() {
@@ -21,3 +29,167 @@ fn no_deps() {
     assert_that(p.cargo_process("fetch"),
                 execs().with_status(0).with_stdout(""));
 }
+
+fn project_with_deps() -> ProjectBuilder {
+    Package::new("baz", "0.1.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "0.1").publish();
+    let git = git::new("git-dep", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "git-dep"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+    }).unwrap();
+
+    project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+
+            [dependencies]
+            bar = "0.1"
+            git-dep = {{ git = '{}' }}
+        "#, git.url()))
+        .file("src/lib.rs", "")
+}
+
+#[test]
+fn bundle_and_import() {
+    let p = project_with_deps();
+    assert_that(p.cargo_process("fetch").arg("--bundle").arg("deps.tar"),
+                execs().with_status(0));
+    assert_that(&p.root().join("deps.tar"), existing_file());
+
+    // Start over with an empty cache, as if on another machine
+    paths::home().join(".cargo/registry").rm_rf();
+    paths::home().join(".cargo/git").rm_rf();
+    assert_that(p.cargo("build").arg("--frozen"),
+                execs().with_status(101));
+
+    assert_that(p.cargo("fetch").arg("--import").arg("deps.tar"),
+                execs().with_status(0).with_stdout(""));
+    assert_that(p.cargo("build").arg("--frozen"),
+                execs().with_status(0).with_stderr_contains("\
+[COMPILING] baz v0.1.0 ([..])
+").with_stderr_contains("\
+[COMPILING] bar v0.1.0 ([..])
+").with_stderr_contains("\
+[COMPILING] git-dep v0.1.0 ([..])
+"));
+}
+
+#[test]
+fn import_keeps_existing_index_entries() {
+    let p = project_with_deps();
+    assert_that(p.cargo_process("fetch").arg("--bundle").arg("deps.tar"),
+                execs().with_status(0));
+
+    // A newer version shows up in the local cache after the bundle was made
+    Package::new("bar", "0.1.1").dep("baz", "0.1").publish();
+    assert_that(p.cargo("update"), execs().with_status(0));
+    assert_that(p.cargo("fetch"), execs().with_status(0));
+
+    assert_that(p.cargo("fetch").arg("--import").arg("deps.tar"),
+                execs().with_status(0));
+    assert_that(p.cargo("build").arg("--frozen"),
+                execs().with_status(0).with_stderr_contains("\
+[COMPILING] bar v0.1.1 ([..])
+"));
+}
+
+#[test]
+fn import_fetches_into_existing_git_databases() {
+    let git_project = git::new("git-dep", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "git-dep"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+    }).unwrap();
+    let repo = t!(git2::Repository::open(&git_project.root()));
+    let old_rev = t!(repo.revparse_single("HEAD")).id();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+
+            [dependencies]
+            git-dep = {{ git = '{}' }}
+        "#, git_project.url()))
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("fetch"), execs().with_status(0));
+    let mut old_lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock")))
+        .read_to_string(&mut old_lock));
+
+    // The bundle is made once the dependency has moved on
+    t!(t!(File::create(git_project.root().join("src/lib.rs")))
+        .write_all(b"pub fn new() {}"));
+    git::add(&repo);
+    git::commit(&repo);
+    assert_that(p.cargo("update"), execs().with_status(0));
+    assert_that(p.cargo("fetch").arg("--bundle").arg("deps.tar"),
+                execs().with_status(0));
+    let mut new_lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock")))
+        .read_to_string(&mut new_lock));
+
+    // Elsewhere the database only knows of the old revision
+    paths::home().join(".cargo/git").rm_rf();
+    let old = t!(repo.find_object(old_rev, None));
+    t!(repo.reset(&old, git2::ResetType::Hard, None));
+    t!(t!(File::create(p.root().join("Cargo.lock")))
+        .write_all(old_lock.as_bytes()));
+    assert_that(p.cargo("fetch"), execs().with_status(0));
+
+    t!(t!(File::create(p.root().join("Cargo.lock")))
+        .write_all(new_lock.as_bytes()));
+    assert_that(p.cargo("fetch").arg("--import").arg("deps.tar"),
+                execs().with_status(0));
+    assert_that(p.cargo("build").arg("--frozen"),
+                execs().with_status(0).with_stderr_contains("\
+[COMPILING] git-dep v0.1.0 ([..])
+"));
+}
+
+#[test]
+fn import_rejects_corrupt_crates() {
+    let p = project_with_deps();
+    assert_that(p.cargo_process("fetch").arg("--bundle").arg("deps.tar"),
+                execs().with_status(0));
+
+    // Damage the tarball of `bar` within the bundle
+    let mut contents = Vec::new();
+    t!(t!(File::open(p.root().join("deps.tar"))).read_to_end(&mut contents));
+    let name = b"bar-0.1.0.crate";
+    let pos = contents.windows(name.len()).position(|w| w == name).unwrap();
+    // The tarball's data starts at the next 512-byte block after its header
+    let data = (pos / 512 + 1) * 512;
+    contents[data + 100] ^= 0xff;
+    t!(t!(File::create(p.root().join("deps.tar"))).write_all(&contents));
+
+    paths::home().join(".cargo/registry").rm_rf();
+    assert_that(p.cargo("fetch").arg("--import").arg("deps.tar"),
+                execs().with_status(101).with_stderr_contains("\
+[..]failed to verify the checksum of `bar-0.1.0.crate`
+"));
+}
+
+#[test]
+fn bundle_and_import_are_exclusive() {
+    let p = project_with_deps();
+    assert_that(p.cargo_process("fetch").arg("--bundle").arg("a.tar")
+                 .arg("--import").arg("b.tar"),
+                execs().with_status(1).with_stderr("\
+[ERROR] cannot specify both --bundle and --import
+"));
+}
@@ -7,7 +7,7 @@ use std::io::prelude::*;
 use cargotest::sleep_ms;
 use cargotest::support::{project, execs, path2url};
 use cargotest::support::paths::CargoPathExt;
-use hamcrest::{assert_that, existing_file};
+use hamcrest::{assert_that, existing_file, is_not};
 
 #[test]
 fn modifying_and_moving() {
@@ -360,3 +360,235 @@ fn same_build_dir_cached_packages() {
 [COMPILING] a2 v0.0.1 ({dir}/a2)
 ", dir = p.url())));
 }
+
+#[test]
+fn explain_rebuilds() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--explain-rebuilds"),
+                execs().with_status(0).with_stderr(&format!("\
+[DIRTY] bar v0.0.1 ({dir}/bar) (lib `bar`): it hasn't been built before
+[COMPILING] bar v0.0.1 ({dir}/bar)
+[DIRTY] foo v0.0.1 ({dir}) (bin `foo`): it hasn't been built before
+[COMPILING] foo v0.0.1 ({dir})
+", dir = p.url())));
+
+    p.root().move_into_the_past();
+    p.root().join("target").move_into_the_past();
+    File::create(&p.root().join("bar/src/lib.rs")).unwrap()
+         .write_all(b"pub fn bar() {}").unwrap();
+
+    assert_that(p.cargo("build").arg("--explain-rebuilds"),
+                execs().with_status(0).with_stderr(&format!("\
+[DIRTY] bar v0.0.1 ({dir}/bar) (lib `bar`): \
+    the file `[..]lib.rs` has changed
+[COMPILING] bar v0.0.1 ({dir}/bar)
+[DIRTY] foo v0.0.1 ({dir}) (bin `foo`): \
+    the dependency `bar v0.0.1 ({dir}/bar)` has changed
+[COMPILING] foo v0.0.1 ({dir})
+", dir = p.url())));
+
+    assert_that(p.cargo("build").arg("--explain-rebuilds"),
+                execs().with_status(0).with_stderr(""));
+
+    File::create(&p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [package]
+        name = "foo"
+        authors = []
+        version = "0.0.1"
+
+        [dependencies.bar]
+        path = "bar"
+
+        [profile.dev]
+        opt-level = 1
+    "#).unwrap();
+
+    assert_that(p.cargo("build").arg("--explain-rebuilds"),
+                execs().with_status(0).with_stderr(&format!("\
+[DIRTY] bar v0.0.1 ({dir}/bar) (lib `bar`): the profile has changed
+[COMPILING] bar v0.0.1 ({dir}/bar)
+[DIRTY] foo v0.0.1 ({dir}) (bin `foo`): the profile has changed
+[COMPILING] foo v0.0.1 ({dir})
+", dir = p.url())));
+}
+
+#[test]
+fn dry_run() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build").arg("--dry-run"),
+                execs().with_status(0).with_stderr(&format!("\
+[DIRTY] bar v0.0.1 ({dir}/bar) (lib `bar`): it hasn't been built before
+[DIRTY] foo v0.0.1 ({dir}) (bin `foo`): it hasn't been built before
+", dir = p.url())));
+    assert_that(&p.bin("foo"), is_not(existing_file()));
+    assert!(!p.root().join("target/debug/deps").exists());
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0));
+    assert_that(&p.bin("foo"), existing_file());
+
+    p.root().move_into_the_past();
+    p.root().join("target").move_into_the_past();
+    File::create(&p.root().join("src/main.rs")).unwrap()
+         .write_all(b"extern crate bar; fn main() { }").unwrap();
+
+    assert_that(p.cargo("build").arg("--dry-run"),
+                execs().with_status(0).with_stderr(&format!("\
+[DIRTY] foo v0.0.1 ({dir}) (bin `foo`): the file `[..]main.rs` has changed
+", dir = p.url())));
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] foo v0.0.1 ({dir})
+", dir = p.url())));
+}
+
+#[test]
+fn content_hash_freshness() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+
+            [dependencies.bar]
+            path = "bar"
+        "#)
+        .file("src/main.rs", "extern crate bar; fn main() {}")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+        "#)
+        .file("bar/src/lib.rs", "")
+        .file(".cargo/config", r#"
+            [build]
+            freshness = "content-hash"
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] bar v0.0.1 ({dir}/bar)
+[COMPILING] foo v0.0.1 ({dir})
+", dir = p.url())));
+
+    // touching files without changing them doesn't rebuild anything
+    sleep_ms(1000);
+    File::create(&p.root().join("bar/src/lib.rs")).unwrap();
+    File::create(&p.root().join("src/main.rs")).unwrap()
+         .write_all(b"extern crate bar; fn main() {}").unwrap();
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr(""));
+
+    File::create(&p.root().join("src/main.rs")).unwrap()
+         .write_all(b"extern crate bar; fn main() { bar::bar() }").unwrap();
+    File::create(&p.root().join("bar/src/lib.rs")).unwrap()
+         .write_all(b"pub fn bar() {}").unwrap();
+    assert_that(p.cargo("build").arg("--explain-rebuilds"),
+                execs().with_status(0).with_stderr(&format!("\
+[DIRTY] bar v0.0.1 ({dir}/bar) (lib `bar`): \
+    the file `[..]lib.rs` has changed
+[COMPILING] bar v0.0.1 ({dir}/bar)
+[DIRTY] foo v0.0.1 ({dir}) (bin `foo`): the file `[..]main.rs` has changed
+[COMPILING] foo v0.0.1 ({dir})
+", dir = p.url())));
+}
+
+#[test]
+fn content_hash_freshness_of_build_scripts() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            authors = []
+            version = "0.0.1"
+            build = "build.rs"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("build.rs", r#"
+            fn main() { println!("cargo:rerun-if-changed=data.txt"); }
+        "#)
+        .file("data.txt", "a")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            authors = []
+            version = "0.0.1"
+            build = "build.rs"
+        "#)
+        .file("bar/src/main.rs", "fn main() {}")
+        .file("bar/build.rs", "fn main() {}")
+        .file("bar/data.txt", "a")
+        .file(".cargo/config", r#"
+            [build]
+            freshness = "content-hash"
+        "#);
+
+    // `rerun-if-changed` files are compared by their contents
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    sleep_ms(1000);
+    File::create(&p.root().join("data.txt")).unwrap()
+         .write_all(b"a").unwrap();
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr(""));
+    File::create(&p.root().join("data.txt")).unwrap()
+         .write_all(b"b").unwrap();
+    assert_that(p.cargo("build").arg("--explain-rebuilds"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[..]the file `[..]data.txt` has changed")
+                       .with_stderr_contains(&format!("\
+[COMPILING] foo v0.0.1 ({dir})", dir = p.url())));
+
+    // as are all the files of a package whose build script doesn't list any
+    let bar = p.root().join("bar");
+    assert_that(p.cargo("build").cwd(&bar), execs().with_status(0));
+    sleep_ms(1000);
+    File::create(&bar.join("data.txt")).unwrap().write_all(b"a").unwrap();
+    assert_that(p.cargo("build").cwd(&bar),
+                execs().with_status(0).with_stderr(""));
+    File::create(&bar.join("data.txt")).unwrap().write_all(b"b").unwrap();
+    assert_that(p.cargo("build").cwd(&bar).arg("--explain-rebuilds"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[..]the file `[..]data.txt` has changed")
+                       .with_stderr_contains("\
+[COMPILING] bar v0.0.1 ([..])"));
+}
@@ -1794,3 +1794,182 @@ fn add_a_git_dep() {
 
     assert_that(p.cargo("build"), execs().with_status(0));
 }
+
+// Opens the only git database next to its lock file
+fn git_db() -> git2::Repository {
+    let db = paths::home().join(".cargo/git/db");
+    let db = fs::read_dir(&db).unwrap().map(|e| e.unwrap().path())
+                              .find(|p| p.is_dir()).unwrap();
+    git2::Repository::open(&db).unwrap()
+}
+
+#[test]
+fn tag_dep_only_fetches_the_tag() {
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", r#"
+                [package]
+                name = "dep1"
+                version = "0.5.0"
+                authors = []
+            "#)
+            .file("src/lib.rs", "pub fn dep() {}")
+    }).unwrap();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    git::tag(&repo, "v0.1.0");
+
+    // A commit on master which isn't part of the tag's history
+    File::create(&git_project.root().join("README")).unwrap();
+    git::add(&repo);
+    let unrelated = git::commit(&repo);
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            dep1 = {{ git = '{}', tag = 'v0.1.0' }}
+        "#, git_project.url()))
+        .file("src/lib.rs", "
+            extern crate dep1;
+            pub fn foo() { dep1::dep() }
+        ");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let db = git_db();
+    assert!(db.find_object(unrelated, None).is_err());
+
+    // Building again from the lock file, and from scratch, works as well
+    assert_that(p.cargo("build"), execs().with_status(0).with_stdout(""));
+    fs::remove_dir_all(paths::home().join(".cargo/git")).unwrap();
+    assert_that(p.cargo("build"), execs().with_status(0));
+}
+
+#[test]
+fn rev_dep_only_fetches_the_rev() {
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", r#"
+                [package]
+                name = "dep1"
+                version = "0.5.0"
+                authors = []
+            "#)
+            .file("src/lib.rs", "pub fn dep() {}")
+    }).unwrap();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    let rev = repo.revparse_single("HEAD").unwrap().id();
+
+    // A commit on master which isn't part of the revision's history
+    File::create(&git_project.root().join("README")).unwrap();
+    git::add(&repo);
+    let unrelated = git::commit(&repo);
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            dep1 = {{ git = '{}', rev = '{}' }}
+        "#, git_project.url(), rev))
+        .file("src/lib.rs", "
+            extern crate dep1;
+            pub fn foo() { dep1::dep() }
+        ");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let db = git_db();
+    assert!(db.find_object(unrelated, None).is_err());
+    assert!(db.find_branch("master", git2::BranchType::Local).is_err());
+
+    // The precise revision in the lock file is fetched on its own as well
+    fs::remove_dir_all(paths::home().join(".cargo/git")).unwrap();
+    assert_that(p.cargo("build"), execs().with_status(0));
+    let db = git_db();
+    assert!(db.find_object(unrelated, None).is_err());
+}
+
+#[test]
+fn submodules_can_be_skipped() {
+    let git_project = git::new("dep1", |project| {
+        project
+            .file("Cargo.toml", r#"
+                [package]
+                name = "dep1"
+                version = "0.5.0"
+                authors = []
+            "#)
+            .file("src/lib.rs", "pub fn dep() {}")
+    }).unwrap();
+    let git_project2 = git::new("dep2", |project| {
+        project.file("lib.rs", "pub fn dep() {}")
+    }).unwrap();
+
+    let repo = git2::Repository::open(&git_project.root()).unwrap();
+    let url = path2url(git_project2.root()).to_string();
+    git::add_submodule(&repo, &url, Path::new("sub"));
+    git::commit(&repo);
+
+    // Checking out the submodule would fail now
+    fs::remove_dir_all(git_project2.root()).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            dep1 = {{ git = '{}', submodules = false }}
+        "#, git_project.url()))
+        .file("src/lib.rs", "
+            extern crate dep1;
+            pub fn foo() { dep1::dep() }
+        ");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[UPDATING] git repository `{}`
+[COMPILING] dep1 v0.5.0 ({}#[..])
+[COMPILING] foo v0.0.1 ({})
+", git_project.url(), git_project.url(), p.url())));
+}
+
+#[test]
+fn submodules_key_requires_git() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = { path = 'bar', submodules = false }
+        "#)
+        .file("src/lib.rs", "")
+        .file("bar/Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+  dependency (bar) specifies `submodules`, which is only allowed for git \
+dependencies
+"));
+}
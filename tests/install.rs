@@ -9,7 +9,7 @@ use cargo::util::ProcessBuilder;
 use cargotest::install::{cargo_home, has_installed_exe};
 use cargotest::support::git;
 use cargotest::support::paths;
-use cargotest::support::registry::Package;
+use cargotest::support::registry::{self, Package};
 use cargotest::support::{project, execs};
 use hamcrest::{assert_that, is_not};
 
@@ -786,3 +786,46 @@ fn readonly_dir() {
                 execs().with_status(0));
     assert_that(cargo_home(), has_installed_exe("foo"));
 }
+
+#[test]
+fn locked_honors_shipped_lockfile() {
+    pkg("bar", "0.1.0");
+    pkg("bar", "0.1.1");
+
+    let p = git::repo(&paths::root().join("foo"))
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file("Cargo.lock", &format!(r#"
+[root]
+name = "foo"
+version = "0.1.0"
+dependencies = [
+ "bar 0.1.0 (registry+{reg})",
+]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+{reg}"
+"#, reg = registry::registry()));
+    p.build();
+
+    assert_that(cargo_process("install").arg("--locked")
+                                        .arg("--git").arg(p.url().to_string()),
+                execs().with_status(101).with_stderr_contains("\
+[..]the lock file of `foo v0.1.0 ([..])` needs to be updated but --locked was \
+passed to prevent this
+
+changes to the lock file:
+  changed bar v0.1.0 -> v0.1.1
+"));
+    assert_that(cargo_home(), is_not(has_installed_exe("foo")));
+}
@@ -0,0 +1,264 @@
+#[macro_use]
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+
+use cargotest::support::paths::{self, CargoPathExt};
+use cargotest::support::registry::Package;
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+fn setup() {
+    let root = paths::root();
+    t!(fs::create_dir(&root.join(".cargo")));
+    t!(t!(File::create(root.join(".cargo/config"))).write_all(br#"
+        [source.crates-io]
+        replace-with = 'my-awesome-local-registry'
+
+        [source.my-awesome-local-registry]
+        local-registry = 'registry'
+    "#));
+}
+
+#[test]
+fn simple() {
+    setup();
+    Package::new("foo", "0.0.1")
+            .local(true)
+            .file("src/lib.rs", "pub fn foo() {}")
+            .publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.0.1"
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate foo;
+            pub fn bar() {
+                foo::foo();
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[UNPACKING] foo v0.0.1 ([..])
+[COMPILING] foo v0.0.1 ([..])
+[COMPILING] bar v0.0.1 ({dir})
+",
+        dir = p.url())));
+    assert_that(p.cargo("build"), execs().with_status(0).with_stderr(""));
+    assert_that(p.cargo("test"), execs().with_status(0));
+}
+
+#[test]
+fn multiple_versions() {
+    setup();
+    Package::new("foo", "0.0.1").local(true).publish();
+    Package::new("foo", "0.1.0")
+            .local(true)
+            .file("src/lib.rs", "pub fn foo() {}")
+            .publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "*"
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate foo;
+            pub fn bar() {
+                foo::foo();
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[UNPACKING] foo v0.1.0 ([..])
+[COMPILING] foo v0.1.0 ([..])
+[COMPILING] bar v0.0.1 ({dir})
+",
+        dir = p.url())));
+
+    Package::new("foo", "0.2.0")
+            .local(true)
+            .file("src/lib.rs", "pub fn foo() {}")
+            .publish();
+
+    assert_that(p.cargo("update").arg("-v"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] foo v0.1.0 -> v0.2.0
+"));
+}
+
+#[test]
+fn interdependent() {
+    setup();
+    Package::new("foo", "0.0.1")
+            .local(true)
+            .file("src/lib.rs", "pub fn foo() {}")
+            .publish();
+    Package::new("bar", "0.1.0")
+            .local(true)
+            .dep("foo", "*")
+            .file("src/lib.rs", "extern crate foo; pub fn bar() {}")
+            .publish();
+
+    let p = project("baz")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "baz"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate bar;
+            pub fn baz() {
+                bar::bar();
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[UNPACKING] [..]
+[UNPACKING] [..]
+[COMPILING] foo v0.0.1 ([..])
+[COMPILING] bar v0.1.0 ([..])
+[COMPILING] baz v0.0.1 ({dir})
+",
+        dir = p.url())));
+}
+
+#[test]
+fn bad_checksum() {
+    setup();
+    Package::new("foo", "0.0.1")
+            .local(true)
+            .file("src/lib.rs", "pub fn foo() {}")
+            .publish();
+
+    // Corrupt the tarball after it's been listed in the index.
+    let krate = paths::root().join("registry/foo-0.0.1.crate");
+    t!(t!(File::create(&krate)).write_all(b"not a tarball"));
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.0.1"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]failed to verify the checksum of `foo v0.0.1[..]`, its tarball at \
+`[..]foo-0.0.1.crate` is corrupt
+"));
+}
+
+#[test]
+fn not_found() {
+    setup();
+    Package::new("foo", "0.0.1")
+            .local(true)
+            .publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            baz = "0.1.0"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] no matching package named `baz` found (required by `bar`)
+location searched: registry file://[..]
+version required: ^0.1.0
+"));
+}
+
+#[test]
+fn missing_index() {
+    setup();
+    paths::root().join("registry").rm_rf();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.0.1"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]local registry path is not a directory: [..]registry
+"));
+}
+
+#[test]
+fn tarball_replaced_after_unpacking() {
+    setup();
+    Package::new("foo", "0.0.1")
+            .local(true)
+            .file("src/lib.rs", "pub fn foo() {}")
+            .publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.0.1"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    // The sources which were unpacked from the verified tarball are still used
+    let krate = paths::root().join("registry/foo-0.0.1.crate");
+    t!(t!(File::create(&krate)).write_all(b"not a tarball"));
+    assert_that(p.cargo("build"), execs().with_status(0).with_stderr(""));
+
+    // but the tarball is verified before it's unpacked again
+    let src = paths::home().join(".cargo/registry/src");
+    let src = t!(t!(fs::read_dir(&src)).next().unwrap()).path();
+    t!(fs::remove_dir_all(src.join("foo-0.0.1")));
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]failed to verify the checksum of `foo v0.0.1[..]`, its tarball at \
+`[..]foo-0.0.1.crate` is corrupt
+"));
+}
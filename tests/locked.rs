@@ -0,0 +1,97 @@
+#[macro_use]
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::File;
+use std::io::prelude::*;
+
+use cargotest::support::registry::Package;
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+#[test]
+fn locked_without_lockfile() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--locked"),
+                execs().with_status(101).with_stderr("\
+[ERROR] the lock file needs to be updated but --locked was passed to prevent this
+"));
+    assert!(!p.root().join("Cargo.lock").exists());
+}
+
+#[test]
+fn locked_up_to_date() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("generate-lockfile"), execs().with_status(0));
+    assert_that(p.cargo("build").arg("--locked"), execs().with_status(0));
+    assert_that(p.cargo("test").arg("--locked"), execs().with_status(0));
+    assert_that(p.cargo("run").arg("--locked"), execs().with_status(0));
+}
+
+#[test]
+fn locked_lists_changes() {
+    Package::new("bar", "0.1.0").publish();
+    Package::new("baz", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    assert_that(p.cargo_process("generate-lockfile"), execs().with_status(0));
+
+    let mut lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock"))).read_to_string(&mut lock));
+
+    Package::new("bar", "0.1.1").publish();
+    t!(t!(File::create(p.root().join("Cargo.toml"))).write_all(br#"
+        [project]
+        name = "foo"
+        version = "0.0.1"
+        authors = []
+
+        [dependencies]
+        bar = "0.1.1"
+        baz = "0.1.0"
+    "#));
+
+    assert_that(p.cargo("build").arg("--locked"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] the lock file needs to be updated but --locked was passed to prevent this
+
+changes to the lock file:
+  changed bar v0.1.0 -> v0.1.1
+  added baz v0.1.0 (registry file://[..])
+"));
+
+    let mut lock2 = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock"))).read_to_string(&mut lock2));
+    assert_eq!(lock, lock2);
+}
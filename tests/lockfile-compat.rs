@@ -0,0 +1,168 @@
+#[macro_use]
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::File;
+use std::io::prelude::*;
+
+use cargotest::support::registry::{self, Package};
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+fn bar_cksum() -> String {
+    let mut tarball = Vec::new();
+    let dl = registry::dl_path().join("bar/0.1.0/download");
+    t!(t!(File::open(&dl)).read_to_end(&mut tarball));
+    registry::cksum(&tarball)
+}
+
+#[test]
+fn checksums_are_recorded() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let mut lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock"))).read_to_string(&mut lock));
+    let expected = format!("\"checksum bar 0.1.0 (registry+{})\" = \"{}\"",
+                           registry::registry(), bar_cksum());
+    assert!(lock.contains(&expected), "bad lockfile:\n{}", lock);
+}
+
+#[test]
+fn oldest_lockfile_still_works() {
+    Package::new("bar", "0.1.0").publish();
+
+    let lockfile = format!(r#"
+[root]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar 0.1.0 (registry+{reg})",
+]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+{reg}"
+"#, reg = registry::registry());
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", &lockfile);
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    // The missing checksum is filled in
+    let mut lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock"))).read_to_string(&mut lock));
+    assert!(lock.starts_with(lockfile.trim()), "bad lockfile:\n{}", lock);
+    assert!(lock.contains(&bar_cksum()), "bad lockfile:\n{}", lock);
+}
+
+#[test]
+fn wrong_checksum_is_an_error() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", &format!(r#"
+[root]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar 0.1.0 (registry+{reg})",
+]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+{reg}"
+
+[metadata]
+"checksum bar 0.1.0 (registry+{reg})" = "checksum"
+"#, reg = registry::registry()));
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] checksum for `bar v0.1.0 ([..])` changed between lock files
+
+this could be indicative of a few possible errors:
+
+    * the lock file is corrupt
+    * a replacement source in use (e.g. a mirror) returned a different checksum
+    * the source itself may be corrupt in one way or another
+
+unable to verify that `bar v0.1.0 ([..])` is the same as when the lockfile was generated
+"));
+}
+
+#[test]
+fn unrelated_metadata_is_kept_alongside_checksums() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "")
+        .file("Cargo.lock", &format!(r#"
+[root]
+name = "foo"
+version = "0.0.1"
+dependencies = [
+ "bar 0.1.0 (registry+{reg})",
+]
+
+[[package]]
+name = "bar"
+version = "0.1.0"
+source = "registry+{reg}"
+
+[metadata]
+foo = "bar"
+"#, reg = registry::registry()));
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let mut lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock"))).read_to_string(&mut lock));
+    assert!(lock.contains("foo = \"bar\""), "bad lockfile:\n{}", lock);
+    assert!(lock.contains(&bar_cksum()), "bad lockfile:\n{}", lock);
+}
@@ -0,0 +1,171 @@
+extern crate cargotest;
+extern crate hamcrest;
+
+use cargotest::support::git;
+use cargotest::support::registry::Package;
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+#[test]
+fn offline_prefers_cached_versions() {
+    Package::new("bar", "0.0.1").publish();
+    Package::new("bar", "0.0.2").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "=0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    let p2 = project("foo2")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo2"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "*"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    assert_that(p2.cargo_process("build").arg("--offline"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] bar v0.0.1 (registry file://[..])
+[COMPILING] foo2 v0.0.1 ({dir})
+", dir = p2.url())));
+}
+
+#[test]
+fn offline_missing_download() {
+    Package::new("bar", "0.0.1").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    assert_that(p.cargo_process("generate-lockfile"), execs().with_status(0));
+
+    assert_that(p.cargo("build").arg("--offline"),
+                execs().with_status(101).with_stderr_contains("\
+[..]cannot download `bar v0.0.1 (registry file://[..])` because the network \
+is disabled (--offline/--frozen), and it is not in the local cache
+"));
+}
+
+#[test]
+fn offline_without_index() {
+    Package::new("bar", "0.0.1").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--offline"),
+                execs().with_status(101).with_stderr_contains("\
+[..]the index of registry `file://[..]` has not been downloaded yet and the \
+network is disabled (--offline/--frozen)
+"));
+}
+
+#[test]
+fn offline_from_config() {
+    Package::new("bar", "0.0.1").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [net]
+            offline = true
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]the index of registry `file://[..]` has not been downloaded yet [..]
+"));
+}
+
+#[test]
+fn offline_git_not_downloaded() {
+    let bar = git::new("bar", |project| {
+        project.file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+    }).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", &format!(r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = {{ git = '{}' }}
+        "#, bar.url()))
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--offline"),
+                execs().with_status(101).with_stderr_contains("\
+[..]cannot fetch git repository `[..]` because the network is disabled \
+(--offline/--frozen), and it has not been downloaded before
+"));
+
+    // Once fetched the checkout can be used offline.
+    assert_that(p.cargo("build"), execs().with_status(0));
+    assert_that(p.cargo("build").arg("--offline"), execs().with_status(0));
+}
+
+#[test]
+fn frozen_requires_lockfile() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("build").arg("--frozen"),
+                execs().with_status(101).with_stderr("\
+[ERROR] the lock file needs to be updated but --frozen was passed to prevent this
+"));
+
+    assert_that(p.cargo("build"), execs().with_status(0));
+    assert_that(p.cargo("build").arg("--frozen"), execs().with_status(0));
+}
@@ -2,6 +2,8 @@ extern crate cargotest;
 extern crate hamcrest;
 
 use std::env;
+use std::fs::File;
+use std::io::prelude::*;
 use std::path::MAIN_SEPARATOR as SEP;
 
 use cargotest::support::{project, execs};
@@ -107,3 +109,584 @@ fn top_level_overrides_deps() {
                     prefix = env::consts::DLL_PREFIX,
                     suffix = env::consts::DLL_SUFFIX)));
 }
+
+#[test]
+fn custom_profile() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.release-with-debuginfo]
+            inherits = "release"
+            debug = true
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build").arg("-v")
+                 .arg("--profile").arg("release-with-debuginfo"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] test v0.0.0 ({url})
+[RUNNING] `rustc src{sep}lib.rs --crate-name test --crate-type lib \
+        -C opt-level=3 \
+        -g \
+        --out-dir {dir}{sep}target{sep}profiles{sep}release-with-debuginfo \
+        --emit=dep-info,link \
+        -L dependency={dir}{sep}target{sep}profiles{sep}release-with-debuginfo \
+        -L dependency={dir}{sep}target{sep}profiles{sep}release-with-debuginfo{sep}deps`
+", sep = SEP,
+dir = p.root().display(),
+url = p.url(),
+)));
+}
+
+#[test]
+fn custom_profile_inherits_custom_profile() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.ci]
+            inherits = "dev"
+            opt-level = 1
+
+            [profile.ci-rpath]
+            inherits = "ci"
+            rpath = true
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build").arg("-v")
+                 .arg("--profile").arg("ci-rpath"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] test v0.0.0 ({url})
+[RUNNING] `rustc src{sep}lib.rs --crate-name test --crate-type lib \
+        -C opt-level=1 \
+        -g \
+        -C debug-assertions=on \
+        -C rpath \
+        --out-dir {dir}{sep}target{sep}profiles{sep}ci-rpath \
+        --emit=dep-info,link \
+        -L dependency={dir}{sep}target{sep}profiles{sep}ci-rpath \
+        -L dependency={dir}{sep}target{sep}profiles{sep}ci-rpath{sep}deps`
+", sep = SEP,
+dir = p.root().display(),
+url = p.url(),
+)));
+}
+
+#[test]
+fn custom_profile_errors() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.ci]
+            opt-level = 1
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  profile `ci` must specify which profile it `inherits` from
+"));
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.a]
+            inherits = "b"
+
+            [profile.b]
+            inherits = "a"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+  profile `[..]` inherits from itself: [..]"));
+
+    let p = project("baz")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build").arg("--profile").arg("nope"),
+                execs().with_status(101).with_stderr("\
+[ERROR] profile `nope` is not defined in `[..]Cargo.toml`
+"));
+    assert_that(p.cargo("build").arg("--profile").arg("dev")
+                 .arg("--release"),
+                execs().with_status(101).with_stderr("\
+[ERROR] `--release` and `--profile` can't be used together
+"));
+}
+
+#[test]
+fn invalid_profiles_of_deps_are_ignored() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [dependencies.foo]
+            path = "foo"
+        "#)
+        .file("src/lib.rs", "")
+        .file("foo/Cargo.toml", r#"
+            [package]
+
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+
+            [profile.debug]
+            inherits = "dev"
+        "#)
+        .file("foo/src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] foo v0.0.0 ({url}/foo)
+[COMPILING] test v0.0.0 ({url})
+[WARNING] ignoring the invalid profiles of `foo v0.0.0 ({url}/foo)`, only \
+those of the root package are used: profile name `debug` is reserved
+", url = p.url())));
+
+    // They're still an error when it's the package being built
+    assert_that(p.cargo("build").arg("--manifest-path").arg("foo/Cargo.toml"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]foo[..]Cargo.toml`
+
+Caused by:
+  profile name `debug` is reserved
+"));
+}
+
+#[test]
+fn package_override() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [dependencies.foo]
+            path = "foo"
+
+            [profile.dev.package.foo]
+            opt-level = 3
+        "#)
+        .file("src/lib.rs", "")
+        .file("foo/Cargo.toml", r#"
+            [package]
+
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("foo/src/lib.rs", "");
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] foo v0.0.0 ({url}/foo)
+[RUNNING] `rustc foo{sep}src{sep}lib.rs --crate-name foo --crate-type lib \
+        -C opt-level=3 \
+        -g \
+        -C debug-assertions=on \
+        -C metadata=[..] \
+        -C extra-filename=-[..] \
+        --out-dir {dir}{sep}target{sep}debug{sep}deps \
+        --emit=dep-info,link \
+        -L dependency={dir}{sep}target{sep}debug{sep}deps \
+        -L dependency={dir}{sep}target{sep}debug{sep}deps`
+[COMPILING] test v0.0.0 ({url})
+[RUNNING] `rustc src{sep}lib.rs --crate-name test --crate-type lib \
+        -g \
+        --out-dir {dir}{sep}target{sep}debug \
+        --emit=dep-info,link \
+        -L dependency={dir}{sep}target{sep}debug \
+        -L dependency={dir}{sep}target{sep}debug{sep}deps \
+        --extern foo={dir}{sep}target{sep}debug{sep}deps{sep}libfoo-[..].rlib`
+", sep = SEP,
+dir = p.root().display(),
+url = p.url(),
+)));
+    assert!(!p.root().join("target/debug/incremental").exists());
+
+    // Changing the override rebuilds the package it applies to
+    File::create(&p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [package]
+
+        name = "test"
+        version = "0.0.0"
+        authors = []
+
+        [dependencies.foo]
+        path = "foo"
+
+        [profile.dev.package."foo:0.0.0"]
+        opt-level = 2
+    "#).unwrap();
+    assert_that(p.cargo("build").arg("-v"),
+                execs().with_status(0).with_stderr_contains("\
+[RUNNING] `rustc foo[..]src[..]lib.rs --crate-name foo --crate-type lib \
+        -C opt-level=2 [..]`"));
+}
+
+#[test]
+fn most_specific_package_override_wins() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [dependencies.foo]
+            path = "foo"
+
+            [profile.dev.package."foo:0.0.0"]
+            opt-level = 1
+
+            [profile.dev.package.foo]
+            opt-level = 3
+        "#)
+        .file("src/lib.rs", "")
+        .file("foo/Cargo.toml", r#"
+            [package]
+
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("foo/src/lib.rs", "");
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stderr_contains("\
+[RUNNING] `rustc foo[..]src[..]lib.rs --crate-name foo --crate-type lib \
+        -C opt-level=1 [..]`"));
+}
+
+#[test]
+fn custom_profile_package_override_extends_inherited_one() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [dependencies.foo]
+            path = "foo"
+
+            [profile.dev.package.foo]
+            opt-level = 3
+
+            [profile.fast]
+            inherits = "dev"
+
+            [profile.fast.package.foo]
+            debug-assertions = false
+        "#)
+        .file("src/lib.rs", "")
+        .file("foo/Cargo.toml", r#"
+            [package]
+
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("foo/src/lib.rs", "");
+
+    // Both the inherited `opt-level` and the new `debug-assertions` apply
+    assert_that(p.cargo_process("build").arg("-v")
+                 .arg("--profile").arg("fast"),
+                execs().with_status(0).with_stderr_contains("\
+[RUNNING] `rustc foo[..]src[..]lib.rs --crate-name foo --crate-type lib \
+        -C opt-level=3 \
+        -g \
+        -C metadata=[..]`"));
+}
+
+#[test]
+fn package_override_errors() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.release.package.foo]
+            lto = true
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  `lto` can't be set in the package override `[profile.release.package.\"foo\"]`
+"));
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev.package."foo:not-a-version"]
+            opt-level = 3
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  invalid package id specification `foo:not-a-version` in a profile override
+
+Caused by:
+  [..]
+"));
+}
+
+#[test]
+fn build_override() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "foo"
+            version = "0.0.0"
+            authors = []
+            build = "build.rs"
+
+            [dependencies.bar]
+            path = "bar"
+
+            [build-dependencies.bar]
+            path = "bar"
+
+            [profile.dev.build-override]
+            opt-level = 3
+        "#)
+        .file("build.rs", "extern crate bar; fn main() {}")
+        .file("src/lib.rs", "extern crate bar;")
+        .file("bar/Cargo.toml", r#"
+            [package]
+
+            name = "bar"
+            version = "0.0.0"
+            authors = []
+        "#)
+        .file("bar/src/lib.rs", "");
+
+    // `bar` is compiled once for the build script with the override, and once
+    // for the library without it
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0)
+                       .with_stderr_contains("\
+[RUNNING] `rustc bar[..]src[..]lib.rs --crate-name bar --crate-type lib \
+        -C opt-level=3 [..]`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc bar[..]src[..]lib.rs --crate-name bar --crate-type lib \
+        -g [..]`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc build.rs --crate-name build_script_build --crate-type bin \
+        -C opt-level=3 [..]`")
+                       .with_stderr_contains("\
+[RUNNING] `rustc src[..]lib.rs --crate-name foo --crate-type lib -g [..]`"));
+
+    assert_that(p.cargo("build").arg("-v"),
+                execs().with_status(0).with_stderr("\
+[FRESH] bar v0.0.0 ([..])
+[FRESH] foo v0.0.0 ([..])
+"));
+}
+
+#[test]
+fn build_override_errors() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev.build-override]
+            panic = "abort"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  `panic` can't be set in `[profile.dev.build-override]`
+"));
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.test.build-override]
+            opt-level = 3
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr("\
+[ERROR] failed to parse manifest at `[..]`
+
+Caused by:
+  `build-override` can only be set in the `dev`, `release` and custom \
+profiles, not in `test`
+"));
+}
+
+#[test]
+fn profile_knobs() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev]
+            opt-level = "s"
+            debug = 1
+            overflow-checks = false
+            incremental = true
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build").arg("-v"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] test v0.0.0 ({url})
+[RUNNING] `rustc src{sep}lib.rs --crate-name test --crate-type lib \
+        -C opt-level=s \
+        -C debuginfo=1 \
+        -C debug-assertions=on \
+        -C overflow-checks=off \
+        -C incremental={dir}{sep}target{sep}debug{sep}incremental{sep}\
+           test-[..]{sep}lib-test \
+        --out-dir {dir}{sep}target{sep}debug \
+        --emit=dep-info,link \
+        -L dependency={dir}{sep}target{sep}debug \
+        -L dependency={dir}{sep}target{sep}debug{sep}deps`
+", sep = SEP,
+dir = p.root().display(),
+url = p.url(),
+)));
+    assert!(p.root().join("target/debug/incremental").is_dir());
+
+    // Any change to the profile rebuilds
+    File::create(&p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [package]
+
+        name = "test"
+        version = "0.0.0"
+        authors = []
+
+        [profile.dev]
+        opt-level = "s"
+        debug = 1
+        incremental = true
+    "#).unwrap();
+    assert_that(p.cargo("build").arg("-v"),
+                execs().with_status(0).with_stderr_contains("\
+[RUNNING] `rustc src[..]lib.rs --crate-name test --crate-type lib \
+        -C opt-level=s \
+        -C debuginfo=1 \
+        -C debug-assertions=on [..]`"));
+}
+
+#[test]
+fn strip_unsupported_by_rustc() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev]
+            strip = "symbols"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] the profile of `test v0.0.0 ([..])` sets `strip = \"symbols\"`, \
+but `[..]` doesn't support `-C strip`
+compiler: rustc [..]
+remove `strip` from the profile or use a newer compiler"));
+}
+
+#[test]
+fn profile_knob_errors() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev]
+            opt-level = "fast"
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]expected an integer, a string \"s\" or a string \"z\"[..]"));
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [package]
+
+            name = "test"
+            version = "0.0.0"
+            authors = []
+
+            [profile.dev]
+            debug = 3
+        "#)
+        .file("src/lib.rs", "");
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]expected a boolean or a debuginfo level of 0, 1 or 2[..]"));
+}
@@ -1,6 +1,7 @@
 extern crate cargotest;
 extern crate flate2;
 extern crate hamcrest;
+extern crate rustc_serialize;
 extern crate tar;
 extern crate url;
 
@@ -14,6 +15,7 @@ use cargotest::support::paths;
 use cargotest::support::{project, execs};
 use flate2::read::GzDecoder;
 use hamcrest::assert_that;
+use rustc_serialize::json::Json;
 use tar::Archive;
 use url::Url;
 
@@ -172,3 +174,119 @@ fn unpublishable_crate() {
 `foo` is marked as unpublishable
 "));
 }
+
+#[test]
+fn publish_to_named_registry() {
+    setup();
+    let config = paths::root().join(".cargo/config");
+    let mut f = fs::OpenOptions::new().append(true).open(&config).unwrap();
+    f.write_all(format!(r#"
+        [registries.company]
+            index = "{reg}"
+            token = "company-token"
+    "#, reg = registry()).as_bytes()).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--registry").arg("company"),
+                execs().with_status(0).with_stderr(&format!("\
+[UPDATING] registry `{reg}`
+[WARNING] manifest has no documentation, [..]
+[PACKAGING] foo v0.0.1 ({dir})
+[UPLOADING] foo v0.0.1 ({dir})
+",
+        dir = p.url(),
+        reg = registry())));
+}
+
+#[test]
+fn publish_to_unknown_registry() {
+    setup();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--registry").arg("nope"),
+                execs().with_status(101).with_stderr("\
+[ERROR] no index found for registry: `nope`
+"));
+}
+
+#[test]
+fn dependencies_in_other_registries_list_their_index() {
+    setup();
+    let company = paths::root().join("company-registry");
+    repo(&company)
+        .file("config.json", &format!(r#"{{
+            "dl": "{0}",
+            "api": "{0}"
+        }}"#, upload()))
+        .build();
+    let company = Url::from_file_path(&*company).ok().unwrap();
+    let config = paths::root().join(".cargo/config");
+    let mut f = fs::OpenOptions::new().append(true).open(&config).unwrap();
+    f.write_all(format!(r#"
+        [registries.company]
+            index = "{}"
+            token = "company-token"
+    "#, company).as_bytes()).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+            license = "MIT"
+            description = "foo"
+
+            [dependencies]
+            bar = "0.1"
+            baz = { version = "0.1", registry = "company" }
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    assert_that(p.cargo_process("publish").arg("--no-verify")
+                 .arg("--registry").arg("company"),
+                execs().with_status(0));
+
+    let mut f = File::open(&upload_path().join("api/v1/crates/new")).unwrap();
+    let mut sz = [0; 4];
+    assert_eq!(f.read(&mut sz).unwrap(), 4);
+    let sz = ((sz[0] as u32) <<  0) |
+             ((sz[1] as u32) <<  8) |
+             ((sz[2] as u32) << 16) |
+             ((sz[3] as u32) << 24);
+    let mut metadata = String::new();
+    f.take(sz as u64).read_to_string(&mut metadata).unwrap();
+    let metadata = Json::from_str(&metadata).unwrap();
+
+    let deps = metadata.find("deps").unwrap().as_array().unwrap();
+    let registry_of = |name: &str| {
+        let dep = deps.iter().find(|d| {
+            d.find("name").and_then(|n| n.as_string()) == Some(name)
+        }).unwrap();
+        dep.find("registry").map(|r| r.as_string().unwrap().to_string())
+    };
+    assert_eq!(registry_of("bar"), Some(registry().to_string()));
+    assert_eq!(registry_of("baz"), None);
+}
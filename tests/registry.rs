@@ -1,9 +1,16 @@
 #[macro_use]
 extern crate cargotest;
+extern crate git2;
 extern crate hamcrest;
 
 use std::fs::{self, File};
+use std::io::BufReader;
 use std::io::prelude::*;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
 
 use cargotest::cargo_process;
 use cargotest::support::git;
@@ -141,6 +148,41 @@ versions found: 0.0.4, 0.0.3, 0.0.2, ...
 "));
 }
 
+#[test]
+fn corrupt_cached_crate() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            bar = "0.0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+
+    Package::new("bar", "0.0.1").publish();
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    // Once it's unpacked the tarball in the cache isn't verified again
+    let cache = paths::home().join(".cargo/registry/cache");
+    let cache = t!(t!(fs::read_dir(&cache)).next().unwrap()).path();
+    t!(t!(File::create(cache.join("bar-0.0.1.crate"))).write_all(b"bad"));
+    assert_that(p.cargo("build"), execs().with_status(0).with_stderr(""));
+
+    // but it is when it has to be unpacked again
+    let src = paths::home().join(".cargo/registry/src");
+    let src = t!(t!(fs::read_dir(&src)).next().unwrap()).path();
+    t!(fs::remove_dir_all(src.join("bar-0.0.1")));
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]failed to verify the checksum of `bar v0.0.1 (registry file://[..])`, \
+its tarball at `[..]bar-0.0.1.crate` is corrupt
+"));
+}
+
 #[test]
 fn bad_cksum() {
     let p = project("foo")
@@ -1045,3 +1087,501 @@ fn resolve_and_backtracking() {
     assert_that(p.cargo("build"),
                 execs().with_status(0));
 }
+
+#[test]
+fn downloads_are_limited_by_download_jobs() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            a = "0.1"
+            b = "0.1"
+            c = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [net]
+            download-jobs = 2
+        "#);
+    p.build();
+
+    Package::new("d", "0.1.0").publish();
+    Package::new("a", "0.1.0").dep("d", "0.1").publish();
+    Package::new("b", "0.1.0").dep("d", "0.1").publish();
+    Package::new("c", "0.1.0").publish();
+
+    // Serve the crates over HTTP, slowly, keeping track of how many requests
+    // are in flight at once.
+    let listener = t!(TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(listener.local_addr());
+    let active = Arc::new(AtomicUsize::new(0));
+    let max_active = Arc::new(AtomicUsize::new(0));
+    let requests = Arc::new(AtomicUsize::new(0));
+    {
+        let active = active.clone();
+        let max_active = max_active.clone();
+        let requests = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = t!(stream);
+                let active = active.clone();
+                let max_active = max_active.clone();
+                let requests = requests.clone();
+                thread::spawn(move || {
+                    let mut rdr = BufReader::new(t!(stream.try_clone()));
+                    let mut stream = stream;
+                    loop {
+                        let mut line = String::new();
+                        if t!(rdr.read_line(&mut line)) == 0 {
+                            break
+                        }
+                        let path = line.split(' ').nth(1).unwrap().to_string();
+                        loop {
+                            let mut header = String::new();
+                            t!(rdr.read_line(&mut header));
+                            if header.trim().is_empty() {
+                                break
+                            }
+                        }
+
+                        let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                        loop {
+                            let max = max_active.load(Ordering::SeqCst);
+                            if now <= max || max_active.compare_and_swap(
+                                    max, now, Ordering::SeqCst) == max {
+                                break
+                            }
+                        }
+                        requests.fetch_add(1, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(200));
+
+                        let file = registry::dl_path()
+                                            .join(&path["/dl/".len()..]);
+                        let mut body = Vec::new();
+                        t!(t!(File::open(&file)).read_to_end(&mut body));
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        t!(write!(stream, "HTTP/1.1 200 OK\r\n\
+                                           Content-Length: {}\r\n\r\n",
+                                  body.len()));
+                        t!(stream.write_all(&body));
+                    }
+                });
+            }
+        });
+    }
+
+    let repo = t!(git2::Repository::open(&registry::registry_path()));
+    t!(t!(File::create(registry::registry_path().join("config.json")))
+        .write_all(format!(r#"{{"dl":"http://{}/dl","api":""}}"#, addr)
+                       .as_bytes()));
+    git::add(&repo);
+    git::commit(&repo);
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr_contains("\
+[DOWNLOADING] d v0.1.0 ([..])
+").with_stderr_contains("\
+[COMPILING] foo v0.5.0 ([..])
+"));
+    assert_eq!(requests.load(Ordering::SeqCst), 4);
+    assert!(max_active.load(Ordering::SeqCst) <= 2);
+    assert_that(p.cargo("fetch"),
+                execs().with_status(0).with_stderr(""));
+}
+
+#[test]
+fn bad_download_jobs() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            a = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [net]
+            download-jobs = 0
+        "#);
+    p.build();
+
+    Package::new("a", "0.1.0").publish();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]net.download-jobs must be positive, but found 0 in [..]
+"));
+}
+
+/// Returns the checkout of the only registry index.
+fn index_dir() -> ::std::path::PathBuf {
+    let index = paths::home().join(".cargo/registry/index");
+    t!(t!(fs::read_dir(&index)).next().unwrap()).path()
+}
+
+/// Returns the path of the cached index entries of `bar` in the only index.
+fn bar_index_cache() -> ::std::path::PathBuf {
+    index_dir().join(".cache/3/b/bar")
+}
+
+/// Returns whether the cached index entries of `bar` are for the commit the
+/// index is checked out at.
+fn bar_index_cache_is_current() -> bool {
+    let repo = t!(git2::Repository::open(&index_dir()));
+    let head = t!(repo.head()).target().unwrap().to_string();
+    let mut contents = Vec::new();
+    t!(t!(File::open(&bar_index_cache())).read_to_end(&mut contents));
+    contents.windows(head.len()).any(|w| w == head.as_bytes())
+}
+
+#[test]
+fn index_entries_are_cached() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("bar", "0.1.0").publish();
+
+    assert_that(p.cargo("build"), execs().with_status(0));
+    assert!(bar_index_cache().is_file());
+    assert!(bar_index_cache_is_current());
+
+    // Without the raw index entry `bar` can still be resolved from the cache
+    let entry = index_dir().join("3/b/bar");
+    let mut raw = Vec::new();
+    t!(t!(File::open(&entry)).read_to_end(&mut raw));
+    fs::remove_file(&entry).unwrap();
+    assert_that(p.cargo("build"), execs().with_status(0).with_stderr(""));
+    t!(t!(File::create(&entry)).write_all(&raw));
+
+    // A cache which can't be read is ignored and rewritten
+    t!(t!(File::create(&bar_index_cache())).write_all(b"garbage"));
+    fs::remove_file(p.root().join("Cargo.lock")).unwrap();
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] registry `[..]`
+"));
+    assert!(bar_index_cache_is_current());
+}
+
+#[test]
+fn index_cache_holds_decoded_entries() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("baz", "0.1.0").publish();
+    Package::new("bar", "0.1.0").dep("baz", "0.1").publish();
+
+    assert_that(p.cargo("build"), execs().with_status(0));
+    assert!(bar_index_cache_is_current());
+
+    // The cache holds the decoded entries rather than the JSON of the index
+    let mut cache = Vec::new();
+    t!(t!(File::open(&bar_index_cache())).read_to_end(&mut cache));
+    assert!(!cache.contains(&b'{'));
+
+    // so `bar` and its dependencies are resolved without decoding its lines
+    let entry = index_dir().join("3/b/bar");
+    t!(t!(File::create(&entry)).write_all(b"not json\n"));
+    assert_that(p.cargo("clean"), execs().with_status(0));
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr("\
+[COMPILING] baz v0.1.0 (registry [..])
+[COMPILING] bar v0.1.0 (registry [..])
+[COMPILING] foo v0.5.0 ([..])
+"));
+}
+
+#[test]
+fn index_cache_is_invalidated_by_updates() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}");
+    p.build();
+
+    Package::new("bar", "0.1.0").publish();
+    assert_that(p.cargo("build"), execs().with_status(0));
+    assert!(bar_index_cache().is_file());
+    assert!(bar_index_cache_is_current());
+
+    Package::new("bar", "0.1.1").publish();
+    assert_that(p.cargo("update"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] registry `[..]`
+[UPDATING] bar v0.1.0 (registry [..]) -> v0.1.1
+"));
+    assert!(bar_index_cache_is_current());
+
+    // The new version is resolved from the rewritten cache alone
+    fs::remove_file(index_dir().join("3/b/bar")).unwrap();
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr_contains("\
+[COMPILING] bar v0.1.1 (registry [..])
+"));
+}
+
+#[test]
+fn index_mirror_is_used_when_registry_is_unreachable() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &format!(r#"
+            [registry]
+            index = "http://127.0.0.1:1/index"
+            mirrors = ["{reg}"]
+
+            [net]
+            retry = 0
+        "#, reg = registry::registry()));
+    p.build();
+
+    Package::new("bar", "0.1.0").publish();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr_contains(&format!("\
+[WARNING] failed to fetch `http://127.0.0.1:1/index`, trying mirror `{reg}`
+", reg = registry::registry())).with_stderr_contains("\
+[COMPILING] bar v0.1.0 (registry http://127.0.0.1:1/index)
+"));
+
+    // Packages are still identified by the registry itself
+    let mut lock = String::new();
+    t!(t!(File::open(p.root().join("Cargo.lock"))).read_to_string(&mut lock));
+    assert!(lock.contains("registry+http://127.0.0.1:1/index"));
+    assert!(!lock.contains(registry::registry().as_str()));
+}
+
+#[test]
+fn dl_mirror_is_used_when_download_fails() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", &format!(r#"
+            [registry]
+            dl-mirrors = ["{dl}"]
+
+            [net]
+            retry = 0
+        "#, dl = registry::dl_url()));
+    p.build();
+
+    Package::new("bar", "0.1.0").publish();
+
+    // Point the index at a download endpoint which is down
+    let repo = t!(git2::Repository::open(&registry::registry_path()));
+    t!(t!(File::create(registry::registry_path().join("config.json")))
+        .write_all(br#"{"dl":"http://127.0.0.1:1/dl","api":""}"#));
+    git::add(&repo);
+    git::commit(&repo);
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr_contains("\
+[WARNING] failed to download `bar v0.1.0 ([..])` from \
+http://127.0.0.1:1/dl/bar/0.1.0/download, trying mirror [..]
+").with_stderr_contains("\
+[COMPILING] bar v0.1.0 ([..])
+"));
+}
+
+#[test]
+fn mirrors_are_not_used_for_other_errors() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [registry]
+            dl-mirrors = ["http://127.0.0.1:1/dl"]
+        "#);
+    p.build();
+
+    let pkg = Package::new("bar", "0.1.0");
+    pkg.publish();
+    File::create(&pkg.archive_dst()).unwrap();
+
+    assert_that(p.cargo("build").arg("-v"),
+                execs().with_status(101).with_stderr("\
+[UPDATING] registry [..]
+[DOWNLOADING] bar [..]
+[ERROR] unable to get packages from source
+
+Caused by:
+  failed to download package `bar v0.1.0 (registry file://[..])` from [..]
+
+Caused by:
+  failed to verify the checksum of `bar v0.1.0 (registry file://[..])`
+"));
+}
+
+#[test]
+fn index_ttl_skips_updates() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [registry]
+            index-ttl = 3600
+        "#);
+    p.build();
+
+    Package::new("bar", "0.1.0").publish();
+    Package::new("baz", "0.1.0").publish();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr_contains("\
+[UPDATING] registry [..]
+"));
+
+    File::create(&p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [project]
+        name = "foo"
+        version = "0.5.0"
+        authors = []
+
+        [dependencies]
+        bar = "0.1"
+        baz = "0.1"
+    "#).unwrap();
+    // Without a lockfile the index would normally be updated
+    fs::remove_file(p.root().join("Cargo.lock")).unwrap();
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr("\
+[DOWNLOADING] baz v0.1.0 (registry file://[..])
+[COMPILING] baz v0.1.0 (registry file://[..])
+[COMPILING] foo v0.5.0 ([..])
+"));
+}
+
+#[test]
+fn index_ttl_updates_when_version_is_missing() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [registry]
+            index-ttl = 3600
+        "#);
+    p.build();
+
+    Package::new("bar", "0.1.0").publish();
+    assert_that(p.cargo("build"), execs().with_status(0));
+
+    Package::new("bar", "0.2.0").publish();
+    File::create(&p.root().join("Cargo.toml")).unwrap().write_all(br#"
+        [project]
+        name = "foo"
+        version = "0.5.0"
+        authors = []
+
+        [dependencies]
+        bar = "0.2"
+    "#).unwrap();
+    fs::remove_file(p.root().join("Cargo.lock")).unwrap();
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr("\
+[UPDATING] registry [..]
+[DOWNLOADING] bar v0.2.0 (registry file://[..])
+[COMPILING] bar v0.2.0 (registry file://[..])
+[COMPILING] foo v0.5.0 ([..])
+"));
+}
+
+#[test]
+fn bad_index_ttl() {
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "foo"
+            version = "0.5.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1"
+        "#)
+        .file("src/main.rs", "fn main() {}")
+        .file(".cargo/config", r#"
+            [registry]
+            index-ttl = -1
+        "#);
+    p.build();
+
+    Package::new("bar", "0.1.0").publish();
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr_contains("\
+[..]registry.index-ttl must not be negative, but found -1 in [..]
+"));
+}
@@ -0,0 +1,167 @@
+#[macro_use]
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+
+use cargotest::support::paths;
+use cargotest::support::registry::{self, Package};
+use cargotest::support::{project, execs};
+use hamcrest::assert_that;
+
+fn setup() {
+    let root = paths::root();
+    t!(fs::create_dir(&root.join(".cargo")));
+    t!(t!(File::create(root.join(".cargo/config"))).write_all(format!(r#"
+        [source.crates-io]
+        replace-with = 'my-sparse-registry'
+
+        [source.my-sparse-registry]
+        registry = 'sparse+{}'
+    "#, registry::registry()).as_bytes()));
+}
+
+#[test]
+fn simple() {
+    setup();
+    Package::new("foo", "0.0.1")
+            .file("src/lib.rs", "pub fn foo() {}")
+            .publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.0.1"
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate foo;
+            pub fn bar() {
+                foo::foo();
+            }
+        "#);
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[UPDATING] registry `sparse+{reg}`
+[DOWNLOADING] foo v0.0.1 ([..])
+[COMPILING] foo v0.0.1 ([..])
+[COMPILING] bar v0.0.1 ({dir})
+",
+        reg = registry::registry(),
+        dir = p.url())));
+    assert_that(p.cargo("build"), execs().with_status(0).with_stderr(""));
+
+    // Only the files which were needed were fetched
+    let index = paths::home().join(".cargo/registry/index");
+    let index = t!(t!(fs::read_dir(&index)).next().unwrap()).path();
+    assert!(index.join("config.json").is_file());
+    assert!(index.join("3/f/foo").is_file());
+}
+
+#[test]
+fn transitive_deps_are_fetched() {
+    setup();
+    Package::new("baz", "0.1.0").publish();
+    Package::new("foo", "0.1.0").dep("baz", "0.1.0").publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1.0"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(0).with_stderr_contains("\
+[COMPILING] baz v0.1.0 ([..])
+"));
+}
+
+#[test]
+fn missing_crate() {
+    setup();
+    Package::new("foo", "0.0.1").publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            nope = "0.0.1"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] no matching package named `nope` found (required by `bar`)
+"));
+}
+
+#[test]
+fn update_picks_up_new_versions() {
+    setup();
+    Package::new("foo", "0.1.0").publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.1"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+
+    Package::new("foo", "0.1.1").publish();
+
+    assert_that(p.cargo("update"),
+                execs().with_status(0).with_stderr(&format!("\
+[UPDATING] registry `sparse+{reg}`
+[UPDATING] foo v0.1.0 ([..]) -> v0.1.1
+",
+        reg = registry::registry())));
+}
+
+#[test]
+fn offline_uses_fetched_files() {
+    setup();
+    Package::new("foo", "0.0.1").publish();
+
+    let p = project("bar")
+        .file("Cargo.toml", r#"
+            [project]
+            name = "bar"
+            version = "0.0.1"
+            authors = []
+
+            [dependencies]
+            foo = "0.0.1"
+        "#)
+        .file("src/lib.rs", "");
+
+    assert_that(p.cargo_process("build"), execs().with_status(0));
+    assert_that(p.cargo("build").arg("--offline"),
+                execs().with_status(0));
+
+    fs::remove_file(p.root().join("Cargo.lock")).unwrap();
+    assert_that(p.cargo("build").arg("--offline"),
+                execs().with_status(0));
+}
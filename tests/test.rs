@@ -2019,11 +2019,13 @@ fn bin_does_not_rebuild_tests() {
     File::create(&p.root().join("src/main.rs")).unwrap()
          .write_all(b"fn main() { 3; }").unwrap();
 
-    assert_that(p.cargo("test").arg("-v").arg("--no-run"),
+    assert_that(p.cargo("test").arg("-vj1").arg("--no-run"),
                 execs().with_status(0)
                        .with_stderr("\
+[DIRTY] foo v0.0.1 ([..]) ([..]): the file `[..]main.rs` has changed
 [COMPILING] foo v0.0.1 ([..])
 [RUNNING] `rustc src[..]main.rs [..]`
+[DIRTY] foo v0.0.1 ([..]) ([..]): the file `[..]main.rs` has changed
 [RUNNING] `rustc src[..]main.rs [..]`
 "));
 }
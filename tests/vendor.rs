@@ -0,0 +1,267 @@
+#[macro_use]
+extern crate cargotest;
+extern crate hamcrest;
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+
+use cargotest::support::paths;
+use cargotest::support::registry::{self, Package};
+use cargotest::support::{project, execs, git};
+use hamcrest::{assert_that, existing_file, existing_dir, is_not};
+
+#[test]
+fn vendor_simple() {
+    Package::new("bar", "0.1.0")
+        .file("src/lib.rs", "pub fn bar() {}")
+        .publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate bar;
+
+            pub fn foo() {
+                bar::bar();
+            }
+        "#);
+
+    assert_that(p.cargo_process("vendor"),
+                execs().with_status(0).with_stdout_contains("\
+[source.vendored-sources]
+directory = \"vendor\"
+"));
+
+    let vendor = p.root().join("vendor/bar-0.1.0");
+    assert_that(&vendor.join("src/lib.rs"), existing_file());
+    assert_that(&vendor.join(".cargo-checksum.json"), existing_file());
+
+    let mut f = t!(File::open(vendor.join(".cargo-checksum.json")));
+    let mut cksum = String::new();
+    t!(f.read_to_string(&mut cksum));
+    assert!(cksum.contains("\"src/lib.rs\""), "bad checksum file: {}", cksum);
+
+    // The checksum of the tarball is carried over from the lock file
+    let mut tarball = Vec::new();
+    let dl = registry::dl_path().join("bar/0.1.0/download");
+    t!(t!(File::open(&dl)).read_to_end(&mut tarball));
+    let expected = format!("\"package\":\"{}\"", registry::cksum(&tarball));
+    assert!(cksum.contains(&expected), "bad checksum file: {}", cksum);
+}
+
+#[test]
+fn build_from_vendored_sources() {
+    Package::new("bar", "0.1.0")
+        .file("src/lib.rs", "pub fn bar() {}")
+        .publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", r#"
+            extern crate bar;
+
+            pub fn foo() {
+                bar::bar();
+            }
+        "#);
+    p.build();
+
+    let output = p.cargo("vendor").exec_with_output().unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let config = &stdout[stdout.find("[source").unwrap()..];
+
+    t!(fs::create_dir(p.root().join(".cargo")));
+    t!(t!(File::create(p.root().join(".cargo/config")))
+           .write_all(config.as_bytes()));
+
+    // Remove the registry and its caches to ensure the vendored copy is used.
+    t!(fs::remove_dir_all(paths::home().join(".cargo/registry")));
+    t!(fs::remove_dir_all(registry::registry_path()));
+    t!(fs::remove_dir_all(p.root().join("target")));
+    assert_that(p.cargo("build"),
+                execs().with_status(0).with_stderr(&format!("\
+[COMPILING] bar v0.1.0 (registry file://[..])
+[COMPILING] foo v0.1.0 ({dir})
+", dir = p.url())));
+}
+
+#[test]
+fn vendored_crate_without_checksum_is_an_error() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "");
+    p.build();
+
+    assert_that(p.cargo("vendor"), execs().with_status(0));
+    t!(fs::create_dir(p.root().join(".cargo")));
+    t!(t!(File::create(p.root().join(".cargo/config"))).write_all(br#"
+        [source.crates-io]
+        replace-with = 'vendored-sources'
+
+        [source.vendored-sources]
+        directory = 'vendor'
+    "#));
+
+    // Forget the checksum of the tarball the crate was unpacked from
+    let path = p.root().join("vendor/bar-0.1.0/.cargo-checksum.json");
+    let mut cksum = String::new();
+    t!(t!(File::open(&path)).read_to_string(&mut cksum));
+    let start = cksum.find("\"package\":\"").unwrap() + 11;
+    let end = start + cksum[start..].find('"').unwrap();
+    cksum.drain(start..end);
+    t!(t!(File::create(&path)).write_all(cksum.as_bytes()));
+
+    assert_that(p.cargo("build"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] checksum for `bar v0.1.0 ([..])` could not be calculated, but a \
+checksum is listed in the existing lock file
+"));
+}
+
+#[test]
+fn deletes_stale_crates() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "")
+        .file("vendor/old-0.1.0/Cargo.toml", "")
+        .file("vendor/old-0.1.0/.cargo-checksum.json", "{}");
+
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    assert_that(&p.root().join("vendor/bar-0.1.0"), existing_dir());
+    assert_that(&p.root().join("vendor/old-0.1.0"), is_not(existing_dir()));
+}
+
+#[test]
+fn no_delete_keeps_stale_crates() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "")
+        .file("vendor/old-0.1.0/Cargo.toml", "")
+        .file("vendor/old-0.1.0/.cargo-checksum.json", "{}");
+
+    assert_that(p.cargo_process("vendor").arg("--no-delete"),
+                execs().with_status(0));
+    assert_that(&p.root().join("vendor/bar-0.1.0"), existing_dir());
+    assert_that(&p.root().join("vendor/old-0.1.0"), existing_dir());
+}
+
+#[test]
+fn only_deletes_vendored_crates() {
+    Package::new("bar", "0.1.0").publish();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+        "#)
+        .file("src/lib.rs", "")
+        .file("vendor/notes/README", "");
+
+    assert_that(p.cargo_process("vendor"), execs().with_status(0));
+    assert_that(&p.root().join("vendor/notes/README"), existing_file());
+
+    // vendoring into the project itself leaves the project alone
+    assert_that(p.cargo("vendor").arg("."), execs().with_status(0));
+    assert_that(&p.root().join("bar-0.1.0"), existing_dir());
+    assert_that(&p.root().join("src/lib.rs"), existing_file());
+    assert_that(&p.root().join("vendor/notes/README"), existing_file());
+}
+
+#[test]
+fn same_package_from_two_sources() {
+    Package::new("bar", "0.1.0").publish();
+
+    let git = git::new("bar", |p| {
+        p.file("Cargo.toml", r#"
+            [package]
+            name = "bar"
+            version = "0.1.0"
+            authors = []
+        "#)
+        .file("src/lib.rs", "")
+    }).unwrap();
+
+    let p = project("foo")
+        .file("Cargo.toml", r#"
+            [package]
+            name = "foo"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = "0.1.0"
+            baz = { path = "baz" }
+        "#)
+        .file("src/lib.rs", "")
+        .file("baz/Cargo.toml", &format!(r#"
+            [package]
+            name = "baz"
+            version = "0.1.0"
+            authors = []
+
+            [dependencies]
+            bar = {{ git = '{}' }}
+        "#, git.url()))
+        .file("baz/src/lib.rs", "");
+
+    assert_that(p.cargo_process("vendor"),
+                execs().with_status(101).with_stderr_contains("\
+[ERROR] found duplicate version of package `bar v0.1.0` vendored from two \
+sources:
+
+\tsource 1: [..]
+\tsource 2: [..]
+"));
+    assert_that(&p.root().join("vendor/bar-0.1.0"), is_not(existing_dir()));
+}